@@ -10,10 +10,13 @@ use anyhow::{Context, Result, anyhow, bail};
 use auth_git2::GitAuthenticator;
 use git2::{Progress, Repository};
 use gnostr_ngit::{
-    cli_interactor::count_lines_per_msg_vec,
+    blossom::{import_packs, is_blossom_url},
+    cli_interactor::{LineOutput, count_lines_per_msg_vec},
+    client::{self, get_state_from_cache},
     git::{
         Repo, RepoActions,
         nostr_url::{CloneUrl, NostrUrlDecoded, ServerProtocol},
+        remote::serialized_credentials,
         utils::check_ssh_keys,
     },
     git_events::tag_value,
@@ -27,6 +30,7 @@ use crate::utils::{
     Direction, fetch_or_list_error_is_not_authentication_failure,
     find_proposal_and_patches_by_branch_name, get_oids_from_fetch_batch,
     get_open_or_draft_proposals, get_read_protocols_to_try, join_with_and, set_protocol_preference,
+    show_progress,
 };
 
 pub async fn run_fetch(
@@ -47,18 +51,32 @@ pub async fn run_fetch(
     let mut errors = vec![];
     let term = console::Term::stderr();
 
-    for git_server_url in &repo_ref.git_server {
-        let term = console::Term::stderr();
-        if let Err(error) = fetch_from_git_server(
+    if client::is_offline() {
+        errors.push(anyhow!("offline so git servers were not contacted"));
+    } else {
+        let git_servers = repo_ref
+            .git_server
+            .iter()
+            .filter(|url| !is_blossom_url(url))
+            .cloned()
+            .collect::<Vec<String>>();
+        errors = fetch_from_git_servers(
             git_repo,
             &oids_from_git_servers,
-            git_server_url,
+            &git_servers,
             &repo_ref.to_nostr_git_url(&None),
-            &term,
-        ) {
-            errors.push(error);
-        } else {
-            break;
+        );
+        // blossom servers are a fallback for when no git server could be reached
+        if errors.len() == git_servers.len() {
+            for git_server_url in repo_ref.git_server.iter().filter(|url| is_blossom_url(url)) {
+                if let Err(error) =
+                    fetch_from_blossom_server(git_repo, repo_ref, git_server_url, &term).await
+                {
+                    errors.push(error);
+                } else {
+                    break;
+                }
+            }
         }
     }
 
@@ -158,12 +176,31 @@ async fn fetch_open_or_draft_proposals(
     Ok(())
 }
 
+/// fetches `oids` from each git server in turn until one succeeds and returns
+/// the errors from those that failed before it
+pub fn fetch_from_git_servers(
+    git_repo: &Repo,
+    oids: &[String],
+    git_server_urls: &[String],
+    decoded_nostr_url: &NostrUrlDecoded,
+) -> Vec<anyhow::Error> {
+    let term = console::Term::stderr();
+    let mut errors = vec![];
+    for git_server_url in git_server_urls {
+        match fetch_from_git_server(git_repo, oids, git_server_url, decoded_nostr_url, &term) {
+            Ok(()) => break,
+            Err(error) => errors.push(error),
+        }
+    }
+    errors
+}
+
 pub fn fetch_from_git_server(
     git_repo: &Repo,
     oids: &[String],
     git_server_url: &str,
     decoded_nostr_url: &NostrUrlDecoded,
-    term: &console::Term,
+    term: &dyn LineOutput,
 ) -> Result<()> {
     let already_have_oids = oids
         .iter()
@@ -229,6 +266,53 @@ pub fn fetch_from_git_server(
     }
 }
 
+/// imports the packs listed in the nostr state event from a blossom server
+pub async fn fetch_from_blossom_server(
+    git_repo: &Repo,
+    repo_ref: &RepoRef,
+    git_server_url: &str,
+    term: &console::Term,
+) -> Result<()> {
+    let server_url = git_server_url.parse::<CloneUrl>()?;
+    let nostr_state = get_state_from_cache(Some(git_repo.get_path()?), repo_ref)
+        .await
+        .context("blossom servers require a nostr state event")?;
+    let oids = nostr_state
+        .state
+        .values()
+        .filter(|value| !value.starts_with("ref: "))
+        .cloned()
+        .collect::<Vec<String>>();
+    let already_have_oids = oids
+        .iter()
+        .all(|oid| git_repo.does_commit_exist(oid).is_ok_and(|outcome| outcome));
+    if already_have_oids {
+        return Ok(());
+    }
+
+    term.write_line(format!("fetching {} over blossom...", server_url.short_name()).as_str())?;
+    let result = import_packs(git_repo, git_server_url, &nostr_state.blossom_packs)
+        .await
+        .and_then(|_| {
+            if oids
+                .iter()
+                .all(|oid| git_repo.does_commit_exist(oid).is_ok_and(|outcome| outcome))
+            {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "packs listed in nostr state event don't contain all the objects it references"
+                ))
+            }
+        });
+    if let Err(error) = result {
+        let error = anyhow!("{} failed over blossom: {error}", server_url.short_name());
+        term.write_line(format!("fetch: {error}").as_str())?;
+        return Err(error);
+    }
+    Ok(())
+}
+
 #[allow(clippy::cast_precision_loss)]
 #[allow(clippy::float_cmp)]
 #[allow(clippy::needless_pass_by_value)]
@@ -299,12 +383,12 @@ fn report_on_transfer_progress(
 struct FetchReporter<'a> {
     remote_msgs: Vec<String>,
     transfer_progress_msgs: Vec<String>,
-    term: &'a console::Term,
+    term: &'a dyn LineOutput,
     start_time: Option<Instant>,
     end_time: Option<Instant>,
 }
 impl<'a> FetchReporter<'a> {
-    fn new(term: &'a console::Term) -> Self {
+    fn new(term: &'a dyn LineOutput) -> Self {
         Self {
             remote_msgs: vec![],
             transfer_progress_msgs: vec![],
@@ -323,7 +407,7 @@ impl<'a> FetchReporter<'a> {
         }
     }
     fn count_all_existing_lines(&self) -> usize {
-        let width = self.term.size().1;
+        let width = self.term.columns();
         count_lines_per_msg_vec(width, &self.remote_msgs, "remote: ".len())
             + count_lines_per_msg_vec(width, &self.transfer_progress_msgs, 0)
     }
@@ -334,7 +418,7 @@ impl<'a> FetchReporter<'a> {
         }
     }
     fn just_count_transfer_progress(&self) -> usize {
-        let width = self.term.size().1;
+        let width = self.term.columns();
         count_lines_per_msg_vec(width, &self.transfer_progress_msgs, 0)
     }
     fn process_remote_msg(&mut self, data: &[u8]) {
@@ -401,7 +485,7 @@ fn fetch_from_git_server_url(
     oids: &[String],
     git_server_url: &str,
     dont_authenticate: bool,
-    term: &console::Term,
+    term: &dyn LineOutput,
 ) -> Result<()> {
     if git_server_url.parse::<CloneUrl>()?.protocol() == ServerProtocol::Ssh && !check_ssh_keys() {
         bail!("no ssh keys found");
@@ -412,25 +496,27 @@ fn fetch_from_git_server_url(
     let mut fetch_options = git2::FetchOptions::new();
     let mut remote_callbacks = git2::RemoteCallbacks::new();
     let fetch_reporter = Arc::new(Mutex::new(FetchReporter::new(term)));
-    remote_callbacks.sideband_progress({
-        let fetch_reporter = Arc::clone(&fetch_reporter);
-        move |data| {
-            let mut reporter = fetch_reporter.lock().unwrap();
-            reporter.process_remote_msg(data);
-            true
-        }
-    });
-    remote_callbacks.transfer_progress({
-        let fetch_reporter = Arc::clone(&fetch_reporter);
-        move |stats| {
-            let mut reporter = fetch_reporter.lock().unwrap();
-            reporter.process_transfer_progress_update(&stats);
-            true
-        }
-    });
+    if show_progress() {
+        remote_callbacks.sideband_progress({
+            let fetch_reporter = Arc::clone(&fetch_reporter);
+            move |data| {
+                let mut reporter = fetch_reporter.lock().unwrap();
+                reporter.process_remote_msg(data);
+                true
+            }
+        });
+        remote_callbacks.transfer_progress({
+            let fetch_reporter = Arc::clone(&fetch_reporter);
+            move |stats| {
+                let mut reporter = fetch_reporter.lock().unwrap();
+                reporter.process_transfer_progress_update(&stats);
+                true
+            }
+        });
+    }
 
     if !dont_authenticate {
-        remote_callbacks.credentials(auth.credentials(&git_config));
+        remote_callbacks.credentials(serialized_credentials(&auth, &git_config));
     }
     fetch_options.remote_callbacks(remote_callbacks);
     git_server_remote.download(oids, Some(&mut fetch_options))?;
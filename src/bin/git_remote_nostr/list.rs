@@ -1,37 +1,35 @@
 use core::str;
 use std::collections::HashMap;
 
-use anyhow::{Context, Result, anyhow};
-use auth_git2::GitAuthenticator;
+use anyhow::{Context, Result, bail};
 use client::get_state_from_cache;
-use git::RepoActions;
+use git::{CommitId, RepoActions};
 use gnostr_ngit::{
+    blossom::is_blossom_url,
     client,
-    git::{
-        self,
-        nostr_url::{CloneUrl, NostrUrlDecoded, ServerProtocol},
-    },
+    git::{self, nostr_url::NostrUrlDecoded, remote::list_from_remote},
     git_events::event_to_cover_letter,
-    login::get_curent_user,
+    login::{get_curent_user, user::get_display_name},
     repo_ref,
+    repo_state::{RepoState, get_last_verified_state, save_verified_state},
 };
-use nostr_sdk::hashes::sha1::Hash as Sha1Hash;
 use repo_ref::RepoRef;
 
 use crate::{
-    fetch::{fetch_from_git_server, make_commits_for_proposal},
-    git::Repo,
-    utils::{
-        Direction, fetch_or_list_error_is_not_authentication_failure, get_open_or_draft_proposals,
-        get_read_protocols_to_try, get_short_git_server_name, join_with_and,
-        set_protocol_preference,
+    fetch::{
+        fetch_from_blossom_server, fetch_from_git_server, fetch_from_git_servers,
+        make_commits_for_proposal,
     },
+    git::Repo,
+    progress::run_on_git_servers,
+    utils::{get_open_or_draft_proposals, get_short_git_server_name},
 };
 
 pub async fn run_list(
     git_repo: &Repo,
     repo_ref: &RepoRef,
     for_push: bool,
+    report_object_format: bool,
 ) -> Result<HashMap<String, HashMap<String, String>>> {
     let nostr_state =
         if let Ok(nostr_state) = get_state_from_cache(Some(git_repo.get_path()?), repo_ref).await {
@@ -42,14 +40,18 @@ pub async fn run_list(
 
     let term = console::Term::stderr();
 
-    let remote_states = list_from_remotes(
-        &term,
-        git_repo,
-        &repo_ref.git_server,
-        &repo_ref.to_nostr_git_url(&None),
-    );
+    let remote_states = if client::is_offline() {
+        HashMap::new()
+    } else {
+        list_from_remotes(
+            git_repo,
+            &repo_ref.git_server,
+            &repo_ref.to_nostr_git_url(&None),
+        )
+        .await
+    };
 
-    let mut state = if let Some(nostr_state) = nostr_state {
+    let mut state = if let Some(nostr_state) = &nostr_state {
         for (name, value) in &nostr_state.state {
             for (url, remote_state) in &remote_states {
                 let remote_name = get_short_git_server_name(git_repo, url);
@@ -77,7 +79,7 @@ pub async fn run_list(
                 }
             }
         }
-        nostr_state.state
+        nostr_state.state.clone()
     } else {
         repo_ref
             .git_server
@@ -86,7 +88,11 @@ pub async fn run_list(
             .cloned()
             .collect::<Vec<HashMap<String, String>>>()
             .first()
-            .context("failed to get refs from git server")?
+            .context(if client::is_offline() {
+                "offline and no nostr state event found in cache"
+            } else {
+                "failed to get refs from git server"
+            })?
             .clone()
     };
 
@@ -95,8 +101,19 @@ pub async fn run_list(
     let proposals_state =
         get_open_and_draft_proposals_state(&term, git_repo, repo_ref, &remote_states).await?;
 
+    if let Some(nostr_state) = &nostr_state {
+        if !client::is_offline() {
+            verify_nostr_state(&term, git_repo, repo_ref, nostr_state).await?;
+        }
+        report_state_conflicts(&term, git_repo, nostr_state).await?;
+    }
+
     state.extend(proposals_state);
 
+    if report_object_format {
+        println!(":object-format {}", git_repo.get_object_format()?);
+    }
+
     // TODO 'for push' should we check with the git servers to see if any of them
     // allow push from the user?
     for (name, value) in state {
@@ -113,6 +130,124 @@ pub async fn run_list(
     Ok(remote_states)
 }
 
+/// refuses to list a nostr state that fails verification unless git config
+/// `nostr.allow-unverified-state` is true. objects missing locally are
+/// fetched first so that a ref only fails if no git server has it
+async fn verify_nostr_state(
+    term: &console::Term,
+    git_repo: &Repo,
+    repo_ref: &RepoRef,
+    nostr_state: &RepoState,
+) -> Result<()> {
+    // include every maintainer's refs so divergent branches can be compared
+    let maintainer_states = nostr_state.maintainer_states();
+    let missing_oids = |git_repo: &Repo| -> Vec<String> {
+        let mut oids: Vec<String> = maintainer_states
+            .iter()
+            .flat_map(|(_, state)| state.values())
+            .filter(|value| {
+                git2::Oid::from_str(value)
+                    .is_ok_and(|oid| !git_repo.git_repo.odb().is_ok_and(|odb| odb.exists(oid)))
+            })
+            .cloned()
+            .collect();
+        oids.sort();
+        oids.dedup();
+        oids
+    };
+    let missing = missing_oids(git_repo);
+    if !missing.is_empty() {
+        let git_servers = repo_ref
+            .git_server
+            .iter()
+            .filter(|url| !is_blossom_url(url))
+            .cloned()
+            .collect::<Vec<String>>();
+        fetch_from_git_servers(
+            git_repo,
+            &missing,
+            &git_servers,
+            &repo_ref.to_nostr_git_url(&None),
+        );
+    }
+    for git_server_url in repo_ref.git_server.iter().filter(|url| is_blossom_url(url)) {
+        if missing_oids(git_repo).is_empty() {
+            break;
+        }
+        let _ = fetch_from_blossom_server(git_repo, repo_ref, git_server_url, term).await;
+    }
+
+    let git_repo_path = git_repo.get_path()?;
+    let previous_state = get_last_verified_state(git_repo_path, &repo_ref.identifier).await?;
+    let verification =
+        nostr_state.verify(git_repo, &repo_ref.maintainers, previous_state.as_ref())?;
+    for warning in &verification.warnings {
+        term.write_line(format!("WARNING: force pushed in nostr state: {warning}").as_str())?;
+    }
+    let failures = verification.failures;
+
+    if failures.is_empty() {
+        if let Some(previous_state) = &previous_state {
+            for name in &nostr_state.force_pushed {
+                if previous_state.get(name) != nostr_state.state.get(name) {
+                    term.write_line(
+                        format!("WARNING: {name} was force pushed in nostr state").as_str(),
+                    )?;
+                }
+            }
+        }
+        save_verified_state(git_repo_path, nostr_state).await?;
+        return Ok(());
+    }
+
+    if git_repo
+        .get_git_config_item("nostr.allow-unverified-state", None)
+        .is_ok_and(|value| value.is_some_and(|v| git2::Config::parse_bool(v).unwrap_or(false)))
+    {
+        for failure in &failures {
+            term.write_line(format!("WARNING: unverified nostr state: {failure}").as_str())?;
+        }
+        return Ok(());
+    }
+
+    bail!(
+        "nostr state event failed verification:\r\n{}\r\nif you trust it run `git config nostr.allow-unverified-state true`",
+        failures
+            .iter()
+            .map(|failure| format!(" - {failure}"))
+            .collect::<Vec<String>>()
+            .join("\r\n")
+    );
+}
+
+/// warns about branches that maintainers point at divergent commits. the
+/// newest value is used
+async fn report_state_conflicts(
+    term: &console::Term,
+    git_repo: &Repo,
+    nostr_state: &RepoState,
+) -> Result<()> {
+    for conflict in nostr_state.conflicts(git_repo) {
+        let mut descriptions = vec![];
+        for (public_key, value) in &conflict.values {
+            descriptions.push(format!(
+                "{} points to {}",
+                get_display_name(Some(git_repo.get_path()?), public_key).await,
+                &value[..value.len().min(7)],
+            ));
+        }
+        term.write_line(
+            format!(
+                "WARNING: maintainers disagree on {}: {}. using the newest. see `ngit state diff`",
+                conflict.name,
+                descriptions.join(", "),
+            )
+            .as_str(),
+        )?;
+    }
+    Ok(())
+}
+
 async fn get_open_and_draft_proposals_state(
     term: &console::Term,
     git_repo: &Repo,
@@ -126,6 +261,7 @@ async fn get_open_and_draft_proposals_state(
 
     // without trusting commit_id we must apply each patch which requires the oid of
     // the parent so we much do a fetch
+    let mut fetched = false;
     for (git_server_url, oids_from_git_servers) in remote_states {
         if fetch_from_git_server(
             git_repo,
@@ -140,9 +276,20 @@ async fn get_open_and_draft_proposals_state(
         )
         .is_ok()
         {
+            fetched = true;
             break;
         }
     }
+    if !fetched && !client::is_offline() {
+        for git_server_url in repo_ref.git_server.iter().filter(|url| is_blossom_url(url)) {
+            if fetch_from_blossom_server(git_repo, repo_ref, git_server_url, term)
+                .await
+                .is_ok()
+            {
+                break;
+            }
+        }
+    }
 
     let mut state = HashMap::new();
     let open_and_draft_proposals = get_open_or_draft_proposals(git_repo, repo_ref).await?;
@@ -176,147 +323,32 @@ async fn get_open_and_draft_proposals_state(
     Ok(state)
 }
 
-pub fn list_from_remotes(
-    term: &console::Term,
+pub async fn list_from_remotes(
     git_repo: &Repo,
-    git_servers: &Vec<String>,
-    decoded_nostr_url: &NostrUrlDecoded, // Add this parameter
+    git_servers: &[String],
+    decoded_nostr_url: &NostrUrlDecoded,
 ) -> HashMap<String, HashMap<String, String>> {
-    let mut remote_states = HashMap::new();
-    let mut errors = HashMap::new();
-    for url in git_servers {
-        match list_from_remote(term, git_repo, url, decoded_nostr_url) {
-            Err(error) => {
-                errors.insert(url, error);
-            }
-            Ok(state) => {
-                remote_states.insert(url.to_string(), state);
-            }
-        }
-    }
-    remote_states
-}
-
-pub fn list_from_remote(
-    term: &console::Term,
-    git_repo: &Repo,
-    git_server_url: &str,
-    decoded_nostr_url: &NostrUrlDecoded, // Add this parameter
-) -> Result<HashMap<String, String>> {
-    let server_url = git_server_url.parse::<CloneUrl>()?;
-    let protocols_to_attempt = get_read_protocols_to_try(git_repo, &server_url, decoded_nostr_url);
-
-    let mut failed_protocols = vec![];
-    let mut remote_state: Option<HashMap<String, String>> = None;
-
-    for protocol in &protocols_to_attempt {
-        term.write_line(
-            format!(
-                "fetching {} ref list over {protocol}...",
-                server_url.short_name(),
-            )
-            .as_str(),
-        )?;
-
-        let formatted_url = server_url.format_as(protocol, &decoded_nostr_url.user)?;
-        let res = list_from_remote_url(
-            git_repo,
-            &formatted_url,
-            [ServerProtocol::UnauthHttps, ServerProtocol::UnauthHttp].contains(protocol),
-            term,
-        );
-
-        match res {
-            Ok(state) => {
-                remote_state = Some(state);
-                term.clear_last_lines(1)?;
-                if !failed_protocols.is_empty() {
-                    term.write_line(
-                        format!(
-                            "list: succeeded over {protocol} from {}",
-                            server_url.short_name(),
-                        )
-                        .as_str(),
-                    )?;
-                    let _ =
-                        set_protocol_preference(git_repo, protocol, &server_url, &Direction::Fetch);
-                }
-                break;
-            }
-            Err(error) => {
-                term.clear_last_lines(1)?;
-                term.write_line(
-                    format!("list: {formatted_url} failed over {protocol}: {error}").as_str(),
-                )?;
-                failed_protocols.push(protocol);
-                if protocol == &ServerProtocol::Ssh
-                    && fetch_or_list_error_is_not_authentication_failure(&error)
-                {
-                    // authenticated by failed to complete request
-                    break;
-                }
-            }
-        }
-    }
-    if let Some(remote_state) = remote_state {
-        if failed_protocols.is_empty() {
-            term.clear_last_lines(1)?;
-        }
-        Ok(remote_state)
-    } else {
-        let error = anyhow!(
-            "{} failed over {}{}",
-            server_url.short_name(),
-            join_with_and(&failed_protocols),
-            if decoded_nostr_url.protocol.is_some() {
-                " and nostr url contains protocol override so no other protocols were attempted"
-            } else {
-                ""
-            },
-        );
-        term.write_line(format!("list: {error}").as_str())?;
-        Err(error)
-    }
-}
-
-fn list_from_remote_url(
-    git_repo: &Repo,
-    git_server_remote_url: &str,
-    dont_authenticate: bool,
-    term: &console::Term,
-) -> Result<HashMap<String, String>> {
-    let git_config = git_repo.git_repo.config()?;
-
-    let mut git_server_remote = git_repo.git_repo.remote_anonymous(git_server_remote_url)?;
-    // authentication may be required
-    let auth = GitAuthenticator::default();
-    let mut remote_callbacks = git2::RemoteCallbacks::new();
-    if !dont_authenticate {
-        remote_callbacks.credentials(auth.credentials(&git_config));
-    }
-    term.write_line("list: connecting...")?;
-    git_server_remote.connect_auth(git2::Direction::Fetch, Some(remote_callbacks), None)?;
-    term.clear_last_lines(1)?;
-    let mut state = HashMap::new();
-    for head in git_server_remote.list()? {
-        if let Some(symbolic_reference) = head.symref_target() {
-            state.insert(
-                head.name().to_string(),
-                format!("ref: {symbolic_reference}"),
-            );
-        } else {
-            state.insert(head.name().to_string(), head.oid().to_string());
-        }
-    }
-    git_server_remote.disconnect()?;
-    Ok(state)
+    // blossom servers don't have refs. they serve the objects in the nostr state
+    let git_servers = git_servers
+        .iter()
+        .filter(|url| !is_blossom_url(url))
+        .cloned()
+        .collect::<Vec<String>>();
+    let decoded_nostr_url = decoded_nostr_url.clone();
+    run_on_git_servers(git_repo, &git_servers, move |git_repo, url, term| {
+        list_from_remote(term, git_repo, url, &decoded_nostr_url)
+    })
+    .await
+    .into_iter()
+    .filter_map(|(url, result)| result.ok().map(|state| (url, state)))
+    .collect()
 }
 
 fn get_ahead_behind(
     git_repo: &Repo,
     base_ref_or_oid: &str,
     latest_ref_or_oid: &str,
-) -> Result<(Vec<Sha1Hash>, Vec<Sha1Hash>)> {
+) -> Result<(Vec<CommitId>, Vec<CommitId>)> {
     let base = git_repo.get_commit_or_tip_of_reference(base_ref_or_oid)?;
     let latest = git_repo.get_commit_or_tip_of_reference(latest_ref_or_oid)?;
     git_repo.get_commits_ahead_behind(&base, &latest)
@@ -12,16 +12,17 @@ use std::{
 };
 
 use anyhow::{Context, Result, bail};
-use client::{Connect, consolidate_fetch_reports, get_repo_ref_from_cache};
-use git::{RepoActions, nostr_url::NostrUrlDecoded};
+use client::{Connect, FetchOptions, consolidate_fetch_reports, get_repo_ref_from_cache};
+use git::{ObjectFormat, RepoActions, nostr_url::NostrUrlDecoded};
 use gnostr_ngit::{client, git, login::existing::load_existing_login};
 use nostr::nips::nip19::Nip19Coordinate;
-use utils::read_line;
+use utils::{read_line, set_progress, set_verbosity};
 
 use crate::{client::Client, git::Repo};
 
 mod fetch;
 mod list;
+mod progress;
 mod push;
 mod utils;
 
@@ -33,6 +34,12 @@ async fn main() -> Result<()> {
 
     let git_repo_path = git_repo.get_path()?;
 
+    client::set_offline(
+        git_repo
+            .get_git_config_item("nostr.offline", None)?
+            .is_some_and(|v| git2::Config::parse_bool(v).unwrap_or(false)),
+    );
+
     let mut client = Client::default();
 
     if let Ok((signer, _, _)) = load_existing_login(
@@ -51,6 +58,8 @@ async fn main() -> Result<()> {
         client.set_signer(signer).await;
     }
 
+    // git only sends `option progress` and `option verbosity` after the
+    // capabilities handshake so this fetch always reports with the defaults
     fetching_with_report_for_helper(git_repo_path, &client, &decoded_nostr_url.coordinate).await?;
 
     let mut repo_ref =
@@ -58,10 +67,29 @@ async fn main() -> Result<()> {
 
     repo_ref.set_nostr_git_url(decoded_nostr_url.clone());
 
+    if let Some(warning) = repo_ref.archived_warning() {
+        let term = console::Term::stderr();
+        term.write_line(&format!("WARNING: {warning}"))?;
+        if let Some(moved_to) = &repo_ref.moved_to {
+            term.write_line(&format!(
+                "hint: point your remote at the new home with `git remote set-url {} {moved_to}`",
+                // git passes the remote name before the url unless the url was used directly
+                env::args()
+                    .nth(2)
+                    .and(env::args().nth(1))
+                    .unwrap_or("<remote>".to_string())
+            ))?;
+        }
+    }
+
     let stdin = io::stdin();
     let mut line = String::new();
 
     let mut list_outputs = None;
+    let mut push_options = push::ProposalPushOptions::default();
+    let mut dry_run = false;
+    let mut atomic = false;
+    let mut report_object_format = false;
     loop {
         let tokens = read_line(&stdin, &mut line)?;
 
@@ -70,9 +98,63 @@ async fn main() -> Result<()> {
                 println!("option");
                 println!("push");
                 println!("fetch");
+                println!("object-format");
                 println!();
             }
-            ["option", "verbosity"] => {
+            ["option", "verbosity", value] => match value.parse::<u8>() {
+                Ok(value) => {
+                    set_verbosity(value);
+                    println!("ok");
+                }
+                Err(_) => println!("error invalid verbosity {value}"),
+            },
+            ["option", "progress", value] => match *value {
+                "true" | "false" => {
+                    set_progress(value.eq(&"true"));
+                    println!("ok");
+                }
+                _ => println!("error invalid progress value {value}"),
+            },
+            ["option", "dry-run", value] => match *value {
+                "true" | "false" => {
+                    dry_run = value.eq(&"true");
+                    println!("ok");
+                }
+                _ => println!("error invalid dry-run value {value}"),
+            },
+            ["option", "atomic", value] => match *value {
+                "true" | "false" => {
+                    atomic = value.eq(&"true");
+                    println!("ok");
+                }
+                _ => println!("error invalid atomic value {value}"),
+            },
+            // git asks for the object format to be reported in list output or, when
+            // it already knows it, checks that the helper can use it
+            ["option", "object-format", value] => match *value {
+                "true" => {
+                    report_object_format = true;
+                    println!("ok");
+                }
+                algorithm => match algorithm.parse::<ObjectFormat>() {
+                    Ok(object_format)
+                        if git_repo
+                            .get_object_format()
+                            .is_ok_and(|repo_format| repo_format == object_format) =>
+                    {
+                        report_object_format = true;
+                        println!("ok");
+                    }
+                    _ => println!("error unsupported object format {algorithm}"),
+                },
+            },
+            ["option", "push-option", value @ ..] => {
+                // answering error would abort pushes of branches that aren't
+                // proposals too
+                if let Err(error) = push_options.add(&value.join(" ")) {
+                    console::Term::stderr()
+                        .write_line(format!("WARNING: {error}. ignoring it").as_str())?;
+                }
                 println!("ok");
             }
             ["option", ..] => {
@@ -89,14 +171,19 @@ async fn main() -> Result<()> {
                     refspec,
                     &client,
                     list_outputs.clone(),
+                    &push_options,
+                    dry_run,
+                    atomic,
                 )
                 .await?;
             }
             ["list"] => {
-                list_outputs = Some(list::run_list(&git_repo, &repo_ref, false).await?);
+                list_outputs =
+                    Some(list::run_list(&git_repo, &repo_ref, false, report_object_format).await?);
             }
             ["list", "for-push"] => {
-                list_outputs = Some(list::run_list(&git_repo, &repo_ref, true).await?);
+                list_outputs =
+                    Some(list::run_list(&git_repo, &repo_ref, true, report_object_format).await?);
             }
             [] => {
                 return Ok(());
@@ -130,6 +217,12 @@ async fn process_args() -> Result<Option<(NostrUrlDecoded, Repo)>> {
         println!(
             " - to open a PR, push a branch with the prefix `pr/` or use `ngit send` for advanced options"
         );
+        println!(
+            " - set the PR title, description, draft status or the proposal it revises with `git push -o title=<title> -o description=<description> -o draft -o in-reply-to=<nevent>`"
+        );
+        println!(
+            " - branches are only added to nostr state once they reach `git config nostr.push-quorum` git servers (default 1). use `git push --atomic` to reject every ref if any fails"
+        );
         println!("- publish a repository to nostr with `ngit init`");
         return Ok(None);
     };
@@ -151,12 +244,17 @@ async fn fetching_with_report_for_helper(
     trusted_maintainer_coordinate: &Nip19Coordinate,
 ) -> Result<()> {
     let term = console::Term::stderr();
+    if client::is_offline() {
+        term.write_line("nostr: offline - using cached events")?;
+        return Ok(());
+    }
     term.write_line("nostr: fetching...")?;
     let (relay_reports, progress_reporter) = client
         .fetch_all(
             Some(git_repo_path),
             Some(trusted_maintainer_coordinate),
             &HashSet::new(),
+            FetchOptions::default(),
         )
         .await?;
     if !relay_reports.iter().any(std::result::Result::is_err) {
@@ -0,0 +1,225 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use gnostr_ngit::{
+    cli_interactor::{LineOutput, count_lines_per_msg},
+    git::Repo,
+};
+
+/// runs `job` against each git server on its own blocking thread so that slow
+/// servers don't hold up the others. output from a single git server is
+/// written straight to the terminal, otherwise it is combined into one display
+pub async fn run_on_git_servers<T, F>(
+    git_repo: &Repo,
+    git_server_urls: &[String],
+    job: F,
+) -> Vec<(String, Result<T>)>
+where
+    T: Send + 'static,
+    F: Fn(&Repo, &str, &dyn LineOutput) -> Result<T> + Send + Sync + 'static,
+{
+    let term = console::Term::stderr();
+    let combined_output = if git_server_urls.len() > 1 {
+        Some(CombinedOutput::new(term.clone(), git_server_urls.len()))
+    } else {
+        None
+    };
+    let git_dir = git_repo.git_repo.path().to_path_buf();
+    let job = Arc::new(job);
+
+    let handles = git_server_urls
+        .iter()
+        .enumerate()
+        .map(|(index, git_server_url)| {
+            let git_dir = git_dir.clone();
+            let git_server_url = git_server_url.clone();
+            let job = Arc::clone(&job);
+            let block = combined_output.as_ref().map(|output| output.block(index));
+            let term = term.clone();
+            tokio::task::spawn_blocking(move || {
+                // git2::Repository can't be shared between threads
+                let result = Repo::from_path(&git_dir).and_then(|git_repo| {
+                    if let Some(block) = &block {
+                        job(&git_repo, &git_server_url, block)
+                    } else {
+                        job(&git_repo, &git_server_url, &term)
+                    }
+                });
+                if let Some(block) = &block {
+                    block.finish();
+                }
+                result
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut results = vec![];
+    for (git_server_url, handle) in git_server_urls.iter().zip(handles) {
+        results.push((
+            git_server_url.clone(),
+            handle
+                .await
+                .context(format!("{git_server_url} task failed to complete"))
+                .and_then(|result| result),
+        ));
+    }
+    results
+}
+
+/// combines the output of operations running concurrently on several git
+/// servers. each server writes to its own block of lines and the blocks are
+/// redrawn in order so their progress reports don't interleave
+pub struct CombinedOutput {
+    term: console::Term,
+    state: Mutex<CombinedOutputState>,
+}
+
+struct CombinedOutputState {
+    blocks: Vec<Block>,
+    drawn_lines: usize,
+}
+
+#[derive(Default)]
+struct Block {
+    lines: Vec<String>,
+    finished: bool,
+}
+
+impl CombinedOutput {
+    pub fn new(term: console::Term, number_of_blocks: usize) -> Arc<Self> {
+        Arc::new(Self {
+            term,
+            state: Mutex::new(CombinedOutputState {
+                blocks: (0..number_of_blocks).map(|_| Block::default()).collect(),
+                drawn_lines: 0,
+            }),
+        })
+    }
+
+    pub fn block(self: &Arc<Self>, index: usize) -> BlockOutput {
+        BlockOutput {
+            output: Arc::clone(self),
+            index,
+        }
+    }
+
+    fn update(&self, index: usize, update: impl FnOnce(&mut Block, u16)) {
+        let mut state = self.state.lock().unwrap();
+        let width = self.term.size().1;
+        if let Some(block) = state.blocks.get_mut(index) {
+            update(block, width);
+        }
+        if state.drawn_lines > 0 {
+            let _ = self.term.clear_last_lines(state.drawn_lines);
+        }
+        // blocks are only drawn once every block above them has started so
+        // output appears in the same order as the git servers are listed
+        let mut drawn_lines = 0;
+        for block in &state.blocks {
+            if block.lines.is_empty() && !block.finished {
+                break;
+            }
+            for line in &block.lines {
+                let _ = self.term.write_line(line);
+                drawn_lines += lines_on_screen(width, line);
+            }
+        }
+        state.drawn_lines = drawn_lines;
+    }
+}
+
+/// the block of a [`CombinedOutput`] written to by the operation on a single
+/// git server
+pub struct BlockOutput {
+    output: Arc<CombinedOutput>,
+    index: usize,
+}
+
+impl BlockOutput {
+    /// lets blocks below this one be drawn even if it never wrote anything
+    pub fn finish(&self) {
+        self.output
+            .update(self.index, |block, _| block.finished = true);
+    }
+}
+
+impl LineOutput for BlockOutput {
+    fn write_line(&self, line: &str) -> std::io::Result<()> {
+        self.output
+            .update(self.index, |block, _| block.lines.push(line.to_string()));
+        Ok(())
+    }
+
+    fn clear_last_lines(&self, n: usize) -> std::io::Result<()> {
+        self.output.update(self.index, |block, width| {
+            let mut cleared = 0;
+            while cleared < n {
+                let Some(line) = block.lines.pop() else {
+                    break;
+                };
+                cleared += lines_on_screen(width, &line);
+            }
+        });
+        Ok(())
+    }
+
+    fn columns(&self) -> u16 {
+        self.output.term.size().1
+    }
+}
+
+fn lines_on_screen(width: u16, line: &str) -> usize {
+    count_lines_per_msg(width, line, 0).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a terminal that discards everything written to it
+    fn hidden_term() -> console::Term {
+        let null_device = if cfg!(windows) { "NUL" } else { "/dev/null" };
+        console::Term::read_write_pair(
+            std::fs::File::open(null_device).unwrap(),
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(null_device)
+                .unwrap(),
+        )
+    }
+
+    fn drawn_lines(output: &Arc<CombinedOutput>) -> usize {
+        output.state.lock().unwrap().drawn_lines
+    }
+
+    #[test]
+    fn blocks_not_drawn_until_blocks_above_have_started() -> Result<()> {
+        let output = CombinedOutput::new(hidden_term(), 2);
+        output.block(1).write_line("push: second over ssh...")?;
+        assert_eq!(drawn_lines(&output), 0);
+        output.block(0).write_line("push: first over ssh...")?;
+        assert_eq!(drawn_lines(&output), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn finished_empty_block_lets_blocks_below_be_drawn() -> Result<()> {
+        let output = CombinedOutput::new(hidden_term(), 2);
+        output.block(1).write_line("fetching second over ssh...")?;
+        output.block(0).finish();
+        assert_eq!(drawn_lines(&output), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn clear_last_lines_only_clears_own_block() -> Result<()> {
+        let output = CombinedOutput::new(hidden_term(), 2);
+        output.block(0).write_line("push: first over ssh...")?;
+        output.block(1).write_line("push: second over ssh...")?;
+        output.block(1).write_line("Writing objects: 50%")?;
+        output.block(0).clear_last_lines(1)?;
+        assert_eq!(drawn_lines(&output), 2);
+        assert!(output.state.lock().unwrap().blocks[0].lines.is_empty());
+        Ok(())
+    }
+}
@@ -8,32 +8,37 @@ use std::{
 
 use anyhow::{Context, Result, anyhow, bail};
 use auth_git2::GitAuthenticator;
-use client::{
-    STATE_KIND, get_events_from_local_cache, get_state_from_cache, send_events, sign_event,
-};
+use client::{get_events_from_local_cache, get_state_from_cache, send_events, sign_event};
 use console::Term;
-use git::{RepoActions, sha1_to_oid};
+use git::{CommitId, RepoActions, commit_id_to_oid};
 use git_events::{
-    generate_cover_letter_and_patch_events, generate_patch_event, get_commit_id_from_patch,
+    generate_cover_letter_and_patch_events, generate_patch_event, generate_status_event,
+    get_commit_id_from_patch, get_root_proposal_id_and_mentions_from_in_reply_to, get_status_name,
+    status_kinds,
 };
 use git2::{Oid, Repository};
 use gnostr_ngit::{
-    cli_interactor::count_lines_per_msg_vec,
+    blossom::{is_blossom_url, upload_blob},
+    cli_interactor::{LineOutput, count_lines_per_msg_vec},
     client::{self, get_event_from_cache_by_id},
     git::{
         self,
         nostr_url::{CloneUrl, NostrUrlDecoded},
         oid_to_shorthand_string,
+        remote::serialized_credentials,
     },
     git_events::{self, event_to_cover_letter, get_event_root},
-    login::{self, user::UserRef},
+    login::{
+        self, get_curent_user,
+        user::{UserMetadata, UserRef, UserRelays, get_user_ref_from_cache},
+    },
     repo_ref::{self, get_repo_config_from_yaml},
-    repo_state,
+    repo_state::{self, get_non_fast_forward_refs},
 };
 use nostr::nips::nip10::Marker;
 use nostr_sdk::{
-    Event, EventBuilder, EventId, Kind, NostrSigner, PublicKey, RelayUrl, Tag, TagStandard,
-    hashes::sha1::Hash as Sha1Hash,
+    Event, EventBuilder, EventId, Keys, Kind, NostrSigner, PublicKey, RelayUrl, Tag, TagStandard,
+    Timestamp, ToBech32,
 };
 use repo_ref::RepoRef;
 use repo_state::RepoState;
@@ -42,14 +47,103 @@ use crate::{
     client::Client,
     git::Repo,
     list::list_from_remotes,
+    progress::run_on_git_servers,
     utils::{
         Direction, find_proposal_and_patches_by_branch_name, get_all_proposals,
         get_remote_name_by_url, get_short_git_server_name, get_write_protocols_to_try,
         join_with_and, push_error_is_not_authentication_failure, read_line,
-        set_protocol_preference,
+        set_protocol_preference, show_progress, verbosity,
     },
 };
 
+/// cover letter, status and thread details for `pr/` branches, set with
+/// `git push -o <option>`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProposalPushOptions {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub draft: bool,
+    pub in_reply_to: Vec<String>,
+}
+
+impl ProposalPushOptions {
+    /// adds a push option as sent by git in `option push-option <value>`
+    pub fn add(&mut self, value: &str) -> Result<()> {
+        let option = unquote_c_style(value);
+        match option.split_once('=') {
+            Some(("title", title)) => self.title = Some(title.to_string()),
+            Some(("description", description)) => {
+                self.description = Some(description.to_string());
+            }
+            Some(("draft", draft)) => self.draft = !matches!(draft, "false" | "no" | "0"),
+            None if option.eq("draft") => self.draft = true,
+            Some(("in-reply-to", references)) => self.in_reply_to.extend(
+                references
+                    .split([' ', ','])
+                    .filter(|r| !r.is_empty())
+                    .map(std::string::ToString::to_string),
+            ),
+            _ => bail!(
+                "unsupported push option '{option}'. supported: title=<title>, description=<description>, draft, in-reply-to=<nevent>"
+            ),
+        }
+        Ok(())
+    }
+
+    fn cover_letter_title_description(&self) -> Result<Option<(String, String)>> {
+        match (&self.title, &self.description) {
+            (Some(title), description) => Ok(Some((
+                title.clone(),
+                description.clone().unwrap_or_default(),
+            ))),
+            (None, Some(_)) => bail!("push option description requires a title"),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// reverses git's `quote_c_style`, used for string option values
+fn unquote_c_style(value: &str) -> String {
+    let Some(quoted) = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    else {
+        return value.to_string();
+    };
+    let mut bytes = vec![];
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('a') => bytes.push(0x07),
+            Some('b') => bytes.push(0x08),
+            Some('f') => bytes.push(0x0c),
+            Some('n') => bytes.push(b'\n'),
+            Some('r') => bytes.push(b'\r'),
+            Some('t') => bytes.push(b'\t'),
+            Some('v') => bytes.push(0x0b),
+            Some(d @ '0'..='3') => {
+                let octal: String = [Some(d), chars.next(), chars.next()]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                bytes.push(u8::from_str_radix(&octal, 8).unwrap_or(b'?'));
+            }
+            Some(c) => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            None => bytes.push(b'\\'),
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn run_push(
     git_repo: &Repo,
     repo_ref: &RepoRef,
@@ -57,6 +151,9 @@ pub async fn run_push(
     initial_refspec: &str,
     client: &Client,
     list_outputs: Option<HashMap<String, HashMap<String, String>>>,
+    push_options: &ProposalPushOptions,
+    dry_run: bool,
+    atomic: bool,
 ) -> Result<()> {
     let refspecs = get_refspecs_from_push_batch(stdin, initial_refspec)?;
 
@@ -74,14 +171,16 @@ pub async fn run_push(
 
     let term = console::Term::stderr();
 
-    let list_outputs = list_outputs.unwrap_or_else(|| {
+    let list_outputs = if let Some(list_outputs) = list_outputs {
+        list_outputs
+    } else {
         list_from_remotes(
-            &term,
             git_repo,
             &repo_ref.git_server,
             &repo_ref.to_nostr_git_url(&None),
         )
-    });
+        .await
+    };
 
     let existing_state = {
         // if no state events - create from first git server listed
@@ -93,6 +192,11 @@ pub async fn run_push(
             .find(|&url| list_outputs.contains_key(url))
         {
             list_outputs.get(url).unwrap().to_owned()
+        } else if !repo_ref.git_server.is_empty()
+            && repo_ref.git_server.iter().all(|url| is_blossom_url(url))
+        {
+            // blossom servers don't have refs so start with an empty state
+            HashMap::new()
         } else {
             bail!(
                 "failed to connect to git servers: {}",
@@ -119,52 +223,48 @@ pub async fn run_push(
         }
     });
 
+    if atomic && !rejected_refspecs.is_empty() {
+        for refspec in git_server_refspecs.iter().chain(proposal_refspecs.iter()) {
+            let (_, to) = refspec_to_from_to(refspec)?;
+            println!("error {to} atomic push failed as another ref was rejected");
+        }
+        println!();
+        return Ok(());
+    }
+
     // all refspecs aren't rejected
     if !(git_server_refspecs.is_empty() && proposal_refspecs.is_empty()) {
-        let (rejected_proposal_refspecs, rejected) = create_and_publish_events(
+        let failed_refspecs = create_and_publish_events(
             git_repo,
             repo_ref,
             &git_server_refspecs,
             &proposal_refspecs,
+            &remote_refspecs,
             client,
             existing_state,
             &term,
+            push_options,
+            dry_run,
+            atomic,
         )
         .await?;
 
-        if !rejected {
-            for refspec in git_server_refspecs.iter().chain(proposal_refspecs.iter()) {
-                if rejected_proposal_refspecs.contains(refspec) {
-                    continue;
-                }
-                let (_, to) = refspec_to_from_to(refspec)?;
-                println!("ok {to}");
-                update_remote_refs_pushed(
-                    &git_repo.git_repo,
-                    refspec,
-                    &repo_ref.to_nostr_git_url(&None).to_string(),
-                )
-                .context("could not update remote_ref locally")?;
+        for refspec in git_server_refspecs.iter().chain(proposal_refspecs.iter()) {
+            let (_, to) = refspec_to_from_to(refspec)?;
+            if let Some(reason) = failed_refspecs.get(refspec) {
+                println!("error {to} {reason}");
+                continue;
             }
-
-            // TODO make async - check gitlib2 callbacks work async
-
-            for (git_server_url, remote_refspecs) in remote_refspecs {
-                let remote_refspecs = remote_refspecs
-                    .iter()
-                    .filter(|refspec| git_server_refspecs.contains(refspec))
-                    .cloned()
-                    .collect::<Vec<String>>();
-                if !refspecs.is_empty() {
-                    let _ = push_to_remote(
-                        git_repo,
-                        &git_server_url,
-                        &repo_ref.to_nostr_git_url(&None),
-                        &remote_refspecs,
-                        &term,
-                    );
-                }
+            println!("ok {to}");
+            if dry_run {
+                continue;
             }
+            update_remote_refs_pushed(
+                &git_repo.git_repo,
+                refspec,
+                &repo_ref.to_nostr_git_url(&None).to_string(),
+            )
+            .context("could not update remote_ref locally")?;
         }
     }
 
@@ -172,34 +272,112 @@ pub async fn run_push(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn create_and_publish_events(
     git_repo: &Repo,
     repo_ref: &RepoRef,
-    git_server_refspecs: &Vec<String>,
+    git_server_refspecs: &[String],
     proposal_refspecs: &Vec<String>,
+    remote_refspecs: &HashMapUrlRefspecs,
     client: &Client,
     existing_state: HashMap<String, String>,
     term: &Term,
-) -> Result<(Vec<String>, bool)> {
-    let (signer, user_ref, _) =
-        login::login_or_signup(&Some(git_repo), &None, &None, Some(client), true).await?;
+    push_options: &ProposalPushOptions,
+    dry_run: bool,
+    atomic: bool,
+) -> Result<HashMap<String, String>> {
+    let (signer, user_ref) = if dry_run {
+        get_dry_run_signer_and_user_ref(git_repo).await?
+    } else {
+        let (signer, user_ref, _) =
+            login::login_or_signup(&Some(git_repo), &None, &None, Some(client), true).await?;
+        (signer, user_ref)
+    };
+
+    let mut failed_refspecs = HashMap::new();
 
     if !repo_ref.maintainers.contains(&user_ref.public_key) {
         for refspec in git_server_refspecs {
-            let (_, to) = refspec_to_from_to(refspec).unwrap();
-            eprintln!(
-                "error {to} your nostr account {} isn't listed as a maintainer of the repo",
-                user_ref.metadata.name
+            failed_refspecs.insert(
+                refspec.to_string(),
+                format!(
+                    "your nostr account {} isn't listed as a maintainer of the repo",
+                    user_ref.metadata.name
+                ),
             );
         }
-        if proposal_refspecs.is_empty() {
-            return Ok((vec![], true));
-        }
     }
 
-    let mut events = vec![];
+    let (mut events, rejected_proposal_refspecs) = process_proposal_refspecs(
+        git_repo,
+        repo_ref,
+        proposal_refspecs,
+        &user_ref,
+        &signer,
+        term,
+        push_options,
+    )
+    .await?;
+    failed_refspecs.extend(rejected_proposal_refspecs);
+
+    if atomic && !failed_refspecs.is_empty() {
+        return Ok(reject_remaining_refspecs(
+            failed_refspecs,
+            git_server_refspecs.iter().chain(proposal_refspecs.iter()),
+        ));
+    }
+
+    let mut git_server_refspecs = git_server_refspecs
+        .iter()
+        .filter(|refspec| !failed_refspecs.contains_key(*refspec))
+        .cloned()
+        .collect::<Vec<String>>();
+
+    // the state event must only point at commits that git servers have, so push
+    // to them before it is published
+    if !git_server_refspecs.is_empty() {
+        let failed_git_server_refspecs = if dry_run {
+            for (git_server_url, refspecs) in remote_refspecs {
+                let refspecs = refspecs
+                    .iter()
+                    .filter(|refspec| pushed_refspec_for(refspec, &git_server_refspecs).is_some())
+                    .cloned()
+                    .collect::<Vec<String>>();
+                if !refspecs.is_empty() {
+                    term.write_line(&format!(
+                        "dry run: would push {} to {}",
+                        refspecs.join(" "),
+                        get_short_git_server_name(git_repo, git_server_url),
+                    ))?;
+                }
+            }
+            HashMap::new()
+        } else {
+            push_to_git_servers(
+                git_repo,
+                repo_ref,
+                remote_refspecs,
+                &git_server_refspecs,
+                get_push_quorum(git_repo),
+            )
+            .await?
+        };
+        if atomic && !failed_git_server_refspecs.is_empty() {
+            term.write_line(
+                "WARNING: atomic push failed so nostr state wasn't updated. some git servers may have accepted the push",
+            )?;
+            failed_refspecs.extend(failed_git_server_refspecs);
+            return Ok(reject_remaining_refspecs(
+                failed_refspecs,
+                git_server_refspecs.iter().chain(proposal_refspecs.iter()),
+            ));
+        }
+        git_server_refspecs.retain(|refspec| !failed_git_server_refspecs.contains_key(refspec));
+        failed_refspecs.extend(failed_git_server_refspecs);
+    }
 
     if !git_server_refspecs.is_empty() {
+        let git_server_refspecs = &git_server_refspecs;
         let new_state = generate_updated_state(git_repo, &existing_state, git_server_refspecs)?;
 
         let store_state =
@@ -210,8 +388,32 @@ async fn create_and_publish_events(
             };
 
         if store_state {
-            let new_repo_state =
-                RepoState::build(repo_ref.identifier.clone(), new_state, &signer).await?;
+            let blossom_packs = if dry_run {
+                get_state_from_cache(Some(git_repo.get_path()?), repo_ref)
+                    .await
+                    .map(|state| state.blossom_packs)
+                    .unwrap_or_default()
+            } else {
+                push_to_blossom_servers(
+                    git_repo,
+                    repo_ref,
+                    &existing_state,
+                    &new_state,
+                    &signer,
+                    term,
+                )
+                .await?
+            };
+            let force_pushed =
+                get_force_pushed_refs(git_repo, &existing_state, &new_state, git_server_refspecs);
+            let new_repo_state = RepoState::build(
+                repo_ref.identifier.clone(),
+                new_state,
+                blossom_packs,
+                force_pushed,
+                &signer,
+            )
+            .await?;
             events.push(new_repo_state.event);
         }
 
@@ -242,35 +444,226 @@ async fn create_and_publish_events(
         }
     }
 
-    let (proposal_events, rejected_proposal_refspecs) = process_proposal_refspecs(
-        git_repo,
-        repo_ref,
-        proposal_refspecs,
-        &user_ref,
-        &signer,
-        term,
-    )
-    .await?;
-    for e in proposal_events {
-        events.push(e);
+    if !events.is_empty() {
+        if dry_run {
+            term.write_line("dry run: would broadcast to nostr relays:")?;
+            for event in &events {
+                term.write_line(&format!("  {}", summarise_event(event)))?;
+            }
+        } else {
+            if verbosity() > 0 {
+                term.write_line("broadcast to nostr relays:")?;
+            }
+            send_events(
+                client,
+                Some(git_repo.get_path()?),
+                events,
+                user_ref.relays.write(),
+                repo_ref.relays.clone(),
+                show_progress(),
+                verbosity() == 0,
+            )
+            .await?;
+        }
     }
+    Ok(failed_refspecs)
+}
 
-    // TODO check whether tip of each branch pushed is on at least one git server
-    // before broadcasting the nostr state
-    if !events.is_empty() {
-        term.write_line("broadcast to nostr relays:")?;
-        send_events(
-            client,
-            Some(git_repo.get_path()?),
-            events,
-            user_ref.relays.write(),
-            repo_ref.relays.clone(),
-            true,
-            false,
+/// when pushing with `--atomic` every refspec is rejected if any one is
+fn reject_remaining_refspecs<'a>(
+    mut failed_refspecs: HashMap<String, String>,
+    refspecs: impl Iterator<Item = &'a String>,
+) -> HashMap<String, String> {
+    for refspec in refspecs {
+        failed_refspecs
+            .entry(refspec.clone())
+            .or_insert_with(|| "atomic push failed as another ref was rejected".to_string());
+    }
+    failed_refspecs
+}
+
+/// minimum number of git servers that must accept a ref before it is included
+/// in the nostr state event. set via `git config nostr.push-quorum <n>`
+fn get_push_quorum(git_repo: &Repo) -> usize {
+    git_repo
+        .get_git_config_item("nostr.push-quorum", None)
+        .ok()
+        .flatten()
+        .and_then(|quorum| quorum.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// pushes to each git server and returns the refspecs that were accepted by
+/// fewer than `quorum` of the git servers they were pushed to, with the reason
+async fn push_to_git_servers(
+    git_repo: &Repo,
+    repo_ref: &RepoRef,
+    remote_refspecs: &HashMapUrlRefspecs,
+    git_server_refspecs: &[String],
+    quorum: usize,
+) -> Result<HashMap<String, String>> {
+    let remote_refspecs = remote_refspecs
+        .iter()
+        .map(|(git_server_url, refspecs)| {
+            (
+                git_server_url.clone(),
+                refspecs
+                    .iter()
+                    .filter(|refspec| pushed_refspec_for(refspec, git_server_refspecs).is_some())
+                    .cloned()
+                    .collect::<Vec<String>>(),
+            )
+        })
+        .filter(|(_, refspecs)| !refspecs.is_empty())
+        .collect::<HashMapUrlRefspecs>();
+    let git_server_urls = remote_refspecs.keys().cloned().collect::<Vec<String>>();
+
+    let outcomes = run_on_git_servers(git_repo, &git_server_urls, {
+        let remote_refspecs = remote_refspecs.clone();
+        let decoded_nostr_url = repo_ref.to_nostr_git_url(&None);
+        move |git_repo, git_server_url, term| {
+            push_to_remote(
+                git_repo,
+                git_server_url,
+                &decoded_nostr_url,
+                &remote_refspecs[git_server_url],
+                term,
+            )
+        }
+    })
+    .await;
+
+    let mut results: HashMap<String, RefspecPushResults> = HashMap::new();
+    for (git_server_url, outcome) in outcomes {
+        let short_name = get_short_git_server_name(git_repo, &git_server_url);
+        for refspec in &remote_refspecs[&git_server_url] {
+            let result = results
+                .entry(
+                    pushed_refspec_for(refspec, git_server_refspecs)
+                        .unwrap()
+                        .clone(),
+                )
+                .or_default();
+            match &outcome {
+                Err(error) => result.failures.push(format!("{short_name}: {error}")),
+                Ok(rejected_refs) => {
+                    let (_, to) = refspec_to_from_to(refspec)?;
+                    if let Some(error) = rejected_refs.get(to) {
+                        result.failures.push(format!("{short_name}: {error}"));
+                    } else {
+                        result.successes += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(refspecs_below_quorum(results, quorum))
+}
+
+/// refspecs for a git server may be prefixed with `+` to force push so are
+/// matched back to the refspec git asked us to push
+fn pushed_refspec_for<'a>(
+    remote_refspec: &str,
+    git_server_refspecs: &'a [String],
+) -> Option<&'a String> {
+    git_server_refspecs
+        .iter()
+        .find(|refspec| refspec.trim_start_matches('+') == remote_refspec.trim_start_matches('+'))
+}
+
+#[derive(Default)]
+struct RefspecPushResults {
+    successes: usize,
+    failures: Vec<String>,
+}
+
+/// a refspec meets quorum if it is accepted by `quorum` git servers or, if it
+/// was pushed to fewer servers than that, by all of them
+fn refspecs_below_quorum(
+    results: HashMap<String, RefspecPushResults>,
+    quorum: usize,
+) -> HashMap<String, String> {
+    results
+        .into_iter()
+        .filter(|(_, result)| {
+            result.successes < quorum.min(result.successes + result.failures.len())
+        })
+        .map(|(refspec, result)| {
+            (
+                refspec,
+                format!(
+                    "accepted by {} of the required {} git servers. {}",
+                    result.successes,
+                    quorum.min(result.successes + result.failures.len()),
+                    result.failures.join(". "),
+                ),
+            )
+        })
+        .collect()
+}
+
+/// events previewed by `git push --dry-run` are signed with a throwaway key so
+/// the user's signer is never asked to sign anything
+async fn get_dry_run_signer_and_user_ref(
+    git_repo: &Repo,
+) -> Result<(Arc<dyn NostrSigner>, UserRef)> {
+    let keys = Keys::generate();
+    let public_key = get_curent_user(git_repo)?.unwrap_or(keys.public_key());
+    let user_ref = match get_user_ref_from_cache(Some(git_repo.get_path()?), &public_key).await {
+        Ok(user_ref) => user_ref,
+        Err(_) => UserRef {
+            public_key,
+            metadata: UserMetadata {
+                name: public_key.to_bech32()?,
+                created_at: Timestamp::from(0),
+                nip05: None,
+            },
+            relays: UserRelays {
+                relays: vec![],
+                created_at: Timestamp::from(0),
+            },
+        },
+    };
+    Ok((Arc::new(keys), user_ref))
+}
+
+/// one line description of an event for `git push --dry-run`
+fn summarise_event(event: &Event) -> String {
+    if event.kind.eq(&client::STATE_KIND) {
+        let mut refs: Vec<String> = event
+            .tags
+            .iter()
+            .filter_map(|t| match t.as_slice() {
+                [name, value] if name.starts_with("refs/") || name.eq("HEAD") => {
+                    Some(if value.starts_with("ref: ") {
+                        format!("{name} {value}")
+                    } else {
+                        format!("{name} {}", value.chars().take(7).collect::<String>())
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        refs.sort();
+        format!("state: {}", refs.join(", "))
+    } else if event.kind.eq(&Kind::GitPatch) {
+        format!(
+            "patch: {}",
+            event
+                .content
+                .lines()
+                .find_map(|line| line.strip_prefix("Subject: "))
+                .unwrap_or_default()
         )
-        .await?;
+    } else if event.kind.eq(&Kind::GitRepoAnnouncement) {
+        "repository announcement".to_string()
+    } else if status_kinds().contains(&event.kind) {
+        format!("status: {}", get_status_name(event.kind))
+    } else {
+        format!("kind {}", event.kind.as_u16())
     }
-    Ok((rejected_proposal_refspecs, false))
 }
 
 #[allow(clippy::too_many_lines)]
@@ -281,17 +674,26 @@ async fn process_proposal_refspecs(
     user_ref: &UserRef,
     signer: &Arc<dyn NostrSigner>,
     term: &Term,
-) -> Result<(Vec<Event>, Vec<String>)> {
+    push_options: &ProposalPushOptions,
+) -> Result<(Vec<Event>, HashMap<String, String>)> {
     let mut events = vec![];
-    let mut rejected_proposal_refspecs = vec![];
+    let mut rejected_proposal_refspecs = HashMap::new();
     if proposal_refspecs.is_empty() {
         return Ok((events, rejected_proposal_refspecs));
     }
     let all_proposals = get_all_proposals(git_repo, repo_ref).await?;
     let current_user = &user_ref.public_key;
 
+    let cover_letter_title_description = push_options.cover_letter_title_description()?;
+    let (in_reply_to_proposal_id, mention_tags) =
+        get_root_proposal_id_and_mentions_from_in_reply_to(
+            git_repo.get_path()?,
+            &push_options.in_reply_to,
+        )
+        .await?;
+
     for refspec in proposal_refspecs {
-        let (from, to) = refspec_to_from_to(refspec).unwrap();
+        let (from, to) = refspec_to_from_to(refspec)?;
         let tip_of_pushed_branch = git_repo.get_commit_or_tip_of_reference(from)?;
 
         // this failed to find existing PR from user
@@ -309,18 +711,30 @@ async fn process_proposal_refspecs(
                         git_repo.get_commits_ahead_behind(&main_tip, &tip_of_pushed_branch)?;
                     ahead.reverse();
                     for patch in generate_cover_letter_and_patch_events(
-                        None,
+                        cover_letter_title_description.clone(),
                         git_repo,
                         &ahead,
                         signer,
                         repo_ref,
                         &Some(proposal.id.to_string()),
-                        &[],
+                        &mention_tags,
                     )
                     .await?
                     {
                         events.push(patch);
                     }
+                    if push_options.draft {
+                        events.push(
+                            generate_status_event(
+                                signer,
+                                repo_ref,
+                                Kind::GitStatusDraft,
+                                proposal,
+                                vec![],
+                            )
+                            .await?,
+                        );
+                    }
                 } else {
                     // fast forward push
                     let tip_patch = patches.first().unwrap();
@@ -331,6 +745,14 @@ async fn process_proposal_refspecs(
                     let (mut ahead, behind) = git_repo
                         .get_commits_ahead_behind(&tip_of_proposal_commit, &tip_of_pushed_branch)?;
                     if behind.is_empty() {
+                        if cover_letter_title_description.is_some() {
+                            term.write_line(
+                                format!(
+                                    "WARNING: title and description push options ignored as {from} adds commits to an existing proposal. force push to replace its cover letter",
+                                )
+                                .as_str(),
+                            )?;
+                        }
                         let thread_id = if let Ok(root_event_id) = get_event_root(tip_patch) {
                             root_event_id
                         } else {
@@ -361,6 +783,18 @@ async fn process_proposal_refspecs(
                             events.push(new_patch.clone());
                             parent_patch = new_patch;
                         }
+                        if push_options.draft {
+                            events.push(
+                                generate_status_event(
+                                    signer,
+                                    repo_ref,
+                                    Kind::GitStatusDraft,
+                                    proposal,
+                                    vec![],
+                                )
+                                .await?,
+                            );
+                        }
                     } else {
                         // we shouldn't get here
                         term.write_line(
@@ -370,17 +804,18 @@ async fn process_proposal_refspecs(
                                 .as_str(),
                             )
                             .unwrap();
-                        println!(
-                            "error {to} failed to fastforward as newer patches found on proposal"
+                        rejected_proposal_refspecs.insert(
+                            refspec.to_string(),
+                            "failed to fastforward as newer patches found on proposal".to_string(),
                         );
-                        rejected_proposal_refspecs.push(refspec.to_string());
                     }
                 }
             } else {
-                println!(
-                    "error {to} permission denied. you are not the proposal author or a repo maintainer"
+                rejected_proposal_refspecs.insert(
+                    refspec.to_string(),
+                    "permission denied. you are not the proposal author or a repo maintainer"
+                        .to_string(),
                 );
-                rejected_proposal_refspecs.push(refspec.to_string());
             }
         } else {
             // TODO new proposal / couldn't find exisiting proposal
@@ -388,62 +823,149 @@ async fn process_proposal_refspecs(
             let (mut ahead, _) =
                 git_repo.get_commits_ahead_behind(&main_tip, &tip_of_pushed_branch)?;
             ahead.reverse();
-            for patch in generate_cover_letter_and_patch_events(
-                None,
+            let new_events = generate_cover_letter_and_patch_events(
+                cover_letter_title_description.clone(),
                 git_repo,
                 &ahead,
                 signer,
                 repo_ref,
-                &None,
-                &[],
+                &in_reply_to_proposal_id,
+                &mention_tags,
             )
-            .await?
-            {
-                events.push(patch);
-            }
+            .await?;
+            let draft_status = if push_options.draft {
+                let root = if let Some(id) = &in_reply_to_proposal_id {
+                    get_event_from_cache_by_id(git_repo, &EventId::parse(id)?).await?
+                } else {
+                    new_events
+                        .first()
+                        .context("proposal should contain at least one patch")?
+                        .clone()
+                };
+                Some(
+                    generate_status_event(signer, repo_ref, Kind::GitStatusDraft, &root, vec![])
+                        .await?,
+                )
+            } else {
+                None
+            };
+            events.extend(new_events);
+            events.extend(draft_status);
         }
     }
 
     Ok((events, rejected_proposal_refspecs))
 }
 
+/// uploads a packfile of the objects needed for `new_state` to each blossom
+/// git server before the state event referencing it is published. returns the
+/// packs to list in the new state event
+async fn push_to_blossom_servers(
+    git_repo: &Repo,
+    repo_ref: &RepoRef,
+    existing_state: &HashMap<String, String>,
+    new_state: &HashMap<String, String>,
+    signer: &Arc<dyn NostrSigner>,
+    term: &Term,
+) -> Result<Vec<String>> {
+    let existing_packs = get_state_from_cache(Some(git_repo.get_path()?), repo_ref)
+        .await
+        .map(|state| state.blossom_packs)
+        .unwrap_or_default();
+    let blossom_servers: Vec<&String> = repo_ref
+        .git_server
+        .iter()
+        .filter(|url| is_blossom_url(url))
+        .collect();
+    if blossom_servers.is_empty() {
+        return Ok(existing_packs);
+    }
+
+    let oids = |state: &HashMap<String, String>| -> Vec<String> {
+        state
+            .values()
+            .filter(|value| !value.starts_with("ref: "))
+            .cloned()
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect()
+    };
+    // objects reachable from the existing state are already in existing packs
+    let exclude = if existing_packs.is_empty() {
+        vec![]
+    } else {
+        oids(existing_state)
+    };
+    let Some(pack) = git_repo.create_pack(&oids(new_state), &exclude)? else {
+        return Ok(existing_packs);
+    };
+
+    let mut uploaded = None;
+    for git_server_url in blossom_servers {
+        let short_name = git_server_url.parse::<CloneUrl>()?.short_name();
+        term.write_line(format!("push: {short_name} over blossom...").as_str())?;
+        match upload_blob(git_server_url, pack.clone(), signer).await {
+            Ok(sha256) => {
+                term.clear_last_lines(1)?;
+                uploaded = Some(sha256);
+            }
+            Err(error) => {
+                term.write_line(
+                    format!("push: {short_name} failed over blossom: {error}").as_str(),
+                )?;
+            }
+        }
+    }
+    if let Some(sha256) = uploaded {
+        Ok([existing_packs, vec![sha256]].concat())
+    } else if repo_ref.git_server.iter().all(|url| is_blossom_url(url)) {
+        bail!("failed to upload git objects to any blossom server");
+    } else {
+        Ok(existing_packs)
+    }
+}
+
 fn push_to_remote(
     git_repo: &Repo,
     git_server_url: &str,
     decoded_nostr_url: &NostrUrlDecoded,
     remote_refspecs: &[String],
-    term: &Term,
-) -> Result<()> {
+    term: &dyn LineOutput,
+) -> Result<HashMap<String, String>> {
     let server_url = git_server_url.parse::<CloneUrl>()?;
     let protocols_to_attempt = get_write_protocols_to_try(git_repo, &server_url, decoded_nostr_url);
 
     let mut failed_protocols = vec![];
-    let mut success = false;
+    let mut rejected_refs = None;
 
     for protocol in &protocols_to_attempt {
         term.write_line(format!("push: {} over {protocol}...", server_url.short_name(),).as_str())?;
 
         let formatted_url = server_url.format_as(protocol, &decoded_nostr_url.user)?;
 
-        if let Err(error) = push_to_remote_url(git_repo, &formatted_url, remote_refspecs, term) {
-            term.write_line(
-                format!("push: {formatted_url} failed over {protocol}: {error}").as_str(),
-            )?;
-            failed_protocols.push(protocol);
-            if push_error_is_not_authentication_failure(&error) {
-                break;
+        match push_to_remote_url(git_repo, &formatted_url, remote_refspecs, term) {
+            Err(error) => {
+                term.write_line(
+                    format!("push: {formatted_url} failed over {protocol}: {error}").as_str(),
+                )?;
+                failed_protocols.push(protocol);
+                if push_error_is_not_authentication_failure(&error) {
+                    break;
+                }
             }
-        } else {
-            success = true;
-            if !failed_protocols.is_empty() {
-                term.write_line(format!("push: succeeded over {protocol}").as_str())?;
-                let _ = set_protocol_preference(git_repo, protocol, &server_url, &Direction::Push);
+            Ok(rejected) => {
+                rejected_refs = Some(rejected);
+                if !failed_protocols.is_empty() {
+                    term.write_line(format!("push: succeeded over {protocol}").as_str())?;
+                    let _ =
+                        set_protocol_preference(git_repo, protocol, &server_url, &Direction::Push);
+                }
+                break;
             }
-            break;
         }
     }
-    if success {
-        Ok(())
+    if let Some(rejected_refs) = rejected_refs {
+        Ok(rejected_refs)
     } else {
         let error = anyhow!(
             "{} failed over {}{}",
@@ -464,22 +986,28 @@ fn push_to_remote_url(
     git_repo: &Repo,
     git_server_url: &str,
     remote_refspecs: &[String],
-    term: &Term,
-) -> Result<()> {
+    term: &dyn LineOutput,
+) -> Result<HashMap<String, String>> {
     let git_config = git_repo.git_repo.config()?;
     let mut git_server_remote = git_repo.git_repo.remote_anonymous(git_server_url)?;
     let auth = GitAuthenticator::default();
     let mut push_options = git2::PushOptions::new();
     let mut remote_callbacks = git2::RemoteCallbacks::new();
     let push_reporter = Arc::new(Mutex::new(PushReporter::new(term)));
+    let rejected_refs = Arc::new(Mutex::new(HashMap::new()));
 
-    remote_callbacks.credentials(auth.credentials(&git_config));
+    remote_callbacks.credentials(serialized_credentials(&auth, &git_config));
 
     remote_callbacks.push_update_reference({
         let push_reporter = Arc::clone(&push_reporter);
+        let rejected_refs = Arc::clone(&rejected_refs);
         move |name, error| {
             let mut reporter = push_reporter.lock().unwrap();
             if let Some(error) = error {
+                rejected_refs
+                    .lock()
+                    .unwrap()
+                    .insert(name.to_string(), error.to_string());
                 let existing_lines = reporter.count_all_existing_lines();
                 reporter.update_reference_errors.push(format!(
                     "WARNING: {} failed to push {name} error: {error}",
@@ -494,6 +1022,9 @@ fn push_to_remote_url(
     remote_callbacks.push_negotiation({
         let push_reporter = Arc::clone(&push_reporter);
         move |updates| {
+            if verbosity() == 0 {
+                return Ok(());
+            }
             let mut reporter = push_reporter.lock().unwrap();
             let existing_lines = reporter.count_all_existing_lines();
 
@@ -542,27 +1073,30 @@ fn push_to_remote_url(
         }
     });
 
-    remote_callbacks.push_transfer_progress({
-        let push_reporter = Arc::clone(&push_reporter);
-        #[allow(clippy::cast_precision_loss)]
-        move |current, total, bytes| {
-            let mut reporter = push_reporter.lock().unwrap();
-            reporter.process_transfer_progress_update(current, total, bytes);
-        }
-    });
+    if show_progress() {
+        remote_callbacks.push_transfer_progress({
+            let push_reporter = Arc::clone(&push_reporter);
+            #[allow(clippy::cast_precision_loss)]
+            move |current, total, bytes| {
+                let mut reporter = push_reporter.lock().unwrap();
+                reporter.process_transfer_progress_update(current, total, bytes);
+            }
+        });
 
-    remote_callbacks.sideband_progress({
-        let push_reporter = Arc::clone(&push_reporter);
-        move |data| {
-            let mut reporter = push_reporter.lock().unwrap();
-            reporter.process_remote_msg(data);
-            true
-        }
-    });
+        remote_callbacks.sideband_progress({
+            let push_reporter = Arc::clone(&push_reporter);
+            move |data| {
+                let mut reporter = push_reporter.lock().unwrap();
+                reporter.process_remote_msg(data);
+                true
+            }
+        });
+    }
     push_options.remote_callbacks(remote_callbacks);
     git_server_remote.push(remote_refspecs, Some(&mut push_options))?;
     let _ = git_server_remote.disconnect();
-    Ok(())
+    let rejected_refs = rejected_refs.lock().unwrap().clone();
+    Ok(rejected_refs)
 }
 
 #[allow(clippy::cast_precision_loss)]
@@ -611,12 +1145,12 @@ struct PushReporter<'a> {
     negotiation: Vec<String>,
     transfer_progress_msgs: Vec<String>,
     update_reference_errors: Vec<String>,
-    term: &'a console::Term,
+    term: &'a dyn LineOutput,
     start_time: Option<Instant>,
     end_time: Option<Instant>,
 }
 impl<'a> PushReporter<'a> {
-    fn new(term: &'a console::Term) -> Self {
+    fn new(term: &'a dyn LineOutput) -> Self {
         Self {
             remote_msgs: vec![],
             negotiation: vec![],
@@ -644,7 +1178,7 @@ impl<'a> PushReporter<'a> {
     }
 
     fn count_all_existing_lines(&self) -> usize {
-        let width = self.term.size().1;
+        let width = self.term.columns();
         count_lines_per_msg_vec(width, &self.remote_msgs, "remote: ".len())
             + count_lines_per_msg_vec(width, &self.negotiation, 0)
             + count_lines_per_msg_vec(width, &self.transfer_progress_msgs, 0)
@@ -925,6 +1459,26 @@ fn generate_updated_state(
     Ok(new_state)
 }
 
+/// refs force pushed to a commit that isn't a descendant of the existing state
+/// so clients verifying the state accept the rewrite
+fn get_force_pushed_refs(
+    git_repo: &Repo,
+    existing_state: &HashMap<String, String>,
+    new_state: &HashMap<String, String>,
+    refspecs: &[String],
+) -> Vec<String> {
+    let forced: Vec<&str> = refspecs
+        .iter()
+        .filter(|refspec| refspec.starts_with('+'))
+        .filter_map(|refspec| refspec_to_from_to(refspec).ok())
+        .map(|(_, to)| to)
+        .collect();
+    get_non_fast_forward_refs(git_repo, existing_state, new_state)
+        .into_iter()
+        .filter(|name| forced.contains(&name.as_str()))
+        .collect()
+}
+
 async fn get_maintainers_yaml_update(
     term: &console::Term,
     decoded_nostr_url: &NostrUrlDecoded,
@@ -947,13 +1501,13 @@ async fn get_maintainers_yaml_update(
                 Some(
                     &git_repo
                         .git_repo
-                        .find_commit(sha1_to_oid(&tip_of_pushed_branch)?)?
+                        .find_commit(commit_id_to_oid(&tip_of_pushed_branch)?)?
                         .tree()?,
                 ),
                 Some(
                     &git_repo
                         .git_repo
-                        .find_commit(sha1_to_oid(&tip_of_remote_branch)?)?
+                        .find_commit(commit_id_to_oid(&tip_of_remote_branch)?)?
                         .tree()?,
                 ),
                 None,
@@ -1045,17 +1599,19 @@ async fn get_merged_status_events(
 
 /// (`proposal_id`, `revision_id`)
 type MergedProposalsInfo =
-    HashMap<EventId, (Option<EventId>, HashMap<Sha1Hash, MergedPRCommitType>)>;
+    HashMap<EventId, (Option<EventId>, HashMap<CommitId, MergedPRCommitType>)>;
 
 async fn get_merged_proposals_info(
     git_repo: &Repo,
-    ahead: &Vec<Sha1Hash>,
+    ahead: &Vec<CommitId>,
     available_patches: &[Event],
 ) -> Result<MergedProposalsInfo> {
     let mut proposals: MergedProposalsInfo = HashMap::new();
 
     for commit_hash in ahead {
-        let commit = git_repo.git_repo.find_commit(sha1_to_oid(commit_hash)?)?;
+        let commit = git_repo
+            .git_repo
+            .find_commit(commit_id_to_oid(commit_hash)?)?;
         // three-way merge - just to set merge commit id as the merged branch commits
         // are in ahead
         if commit.parent_count() > 1 {
@@ -1227,7 +1783,7 @@ async fn create_merge_events(
                     vec![*commit]
                 } else {
                     // child commits were added to merged_patches first so we reverse it
-                    let mut t: Vec<Sha1Hash> = merged_patches.keys().copied().collect();
+                    let mut t: Vec<CommitId> = merged_patches.keys().copied().collect();
                     t.reverse();
                     t
                 },
@@ -1264,7 +1820,7 @@ async fn create_merge_status(
     repo_ref: &RepoRef,
     proposal: &Event,
     revision: Option<&Event>,
-    merge_commits: Vec<Sha1Hash>,
+    merge_commits: Vec<CommitId>,
     merged_patches: Vec<EventId>,
     applied: bool,
 ) -> Result<Event> {
@@ -1528,40 +2084,6 @@ fn get_refspecs_from_push_batch(stdin: &Stdin, initial_refspec: &str) -> Result<
     Ok(refspecs)
 }
 
-trait BuildRepoState {
-    async fn build(
-        identifier: String,
-        state: HashMap<String, String>,
-        signer: &Arc<dyn NostrSigner>,
-    ) -> Result<RepoState>;
-}
-impl BuildRepoState for RepoState {
-    async fn build(
-        identifier: String,
-        state: HashMap<String, String>,
-        signer: &Arc<dyn NostrSigner>,
-    ) -> Result<RepoState> {
-        let mut tags = vec![Tag::identifier(identifier.clone())];
-        for (name, value) in &state {
-            tags.push(Tag::custom(
-                nostr_sdk::TagKind::Custom(name.into()),
-                vec![value.clone()],
-            ));
-        }
-        let event = sign_event(
-            EventBuilder::new(STATE_KIND, "").tags(tags),
-            signer,
-            "git state".to_string(),
-        )
-        .await?;
-        Ok(RepoState {
-            identifier,
-            state,
-            event,
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1575,4 +2097,153 @@ mod tests {
             assert_eq!(from, "testing");
         }
     }
+
+    mod summarise_event {
+        use super::*;
+
+        #[test]
+        fn patch_summarised_by_subject() -> Result<()> {
+            let event = EventBuilder::new(
+                Kind::GitPatch,
+                "From 1234 Mon Sep 17 00:00:00 2001\nSubject: [PATCH 1/2] add feature\n\nbody",
+            )
+            .sign_with_keys(&Keys::generate())?;
+            assert_eq!(summarise_event(&event), "patch: [PATCH 1/2] add feature");
+            Ok(())
+        }
+
+        #[test]
+        fn state_lists_refs_with_short_ids() -> Result<()> {
+            let event = EventBuilder::new(client::STATE_KIND, "")
+                .tags([
+                    Tag::identifier("example"),
+                    Tag::custom(
+                        nostr_sdk::TagKind::Custom("refs/heads/main".into()),
+                        vec!["431b84edc0d2fa118d63faa3c2db9c73d630a5ae".to_string()],
+                    ),
+                    Tag::custom(
+                        nostr_sdk::TagKind::Custom("HEAD".into()),
+                        vec!["ref: refs/heads/main".to_string()],
+                    ),
+                ])
+                .sign_with_keys(&Keys::generate())?;
+            assert_eq!(
+                summarise_event(&event),
+                "state: HEAD ref: refs/heads/main, refs/heads/main 431b84e"
+            );
+            Ok(())
+        }
+    }
+
+    mod refspecs_below_quorum {
+        use super::*;
+
+        fn results(successes: usize, failures: usize) -> HashMap<String, RefspecPushResults> {
+            HashMap::from([(
+                "refs/heads/main:refs/heads/main".to_string(),
+                RefspecPushResults {
+                    successes,
+                    failures: (0..failures).map(|i| format!("server{i}: error")).collect(),
+                },
+            )])
+        }
+
+        #[test]
+        fn accepted_by_one_server_meets_default_quorum() {
+            assert!(refspecs_below_quorum(results(1, 2), 1).is_empty());
+        }
+
+        #[test]
+        fn rejected_by_all_servers_fails_with_reasons() {
+            let failed = refspecs_below_quorum(results(0, 2), 1);
+            let reason = failed.get("refs/heads/main:refs/heads/main").unwrap();
+            assert!(reason.contains("server0: error"));
+            assert!(reason.contains("server1: error"));
+        }
+
+        #[test]
+        fn below_configured_quorum_fails() {
+            assert_eq!(refspecs_below_quorum(results(1, 1), 2).len(), 1);
+        }
+
+        #[test]
+        fn quorum_capped_at_servers_pushed_to() {
+            assert!(refspecs_below_quorum(results(2, 0), 3).is_empty());
+        }
+    }
+
+    mod reject_remaining_refspecs {
+        use super::*;
+
+        #[test]
+        fn keeps_original_reason_and_rejects_others() {
+            let refspecs = vec!["main:main".to_string(), "dev:dev".to_string()];
+            let failed = reject_remaining_refspecs(
+                HashMap::from([("main:main".to_string(), "out of sync".to_string())]),
+                refspecs.iter(),
+            );
+            assert_eq!(failed.get("main:main").unwrap(), "out of sync");
+            assert!(
+                failed
+                    .get("dev:dev")
+                    .unwrap()
+                    .starts_with("atomic push failed")
+            );
+        }
+    }
+
+    mod proposal_push_options {
+        use super::*;
+
+        #[test]
+        fn title_description_draft_and_in_reply_to_parsed() -> Result<()> {
+            let mut options = ProposalPushOptions::default();
+            options.add("title=my proposal title")?;
+            options.add("description=some description")?;
+            options.add("draft")?;
+            options.add("in-reply-to=nevent123,npub123")?;
+            assert_eq!(
+                options,
+                ProposalPushOptions {
+                    title: Some("my proposal title".to_string()),
+                    description: Some("some description".to_string()),
+                    draft: true,
+                    in_reply_to: vec!["nevent123".to_string(), "npub123".to_string()],
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn quoted_values_unescaped() -> Result<()> {
+            let mut options = ProposalPushOptions::default();
+            options.add("\"description=line one\\nline \\\"two\\\"\"")?;
+            assert_eq!(
+                options.description,
+                Some("line one\nline \"two\"".to_string())
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn draft_false_not_draft() -> Result<()> {
+            let mut options = ProposalPushOptions::default();
+            options.add("draft=false")?;
+            assert!(!options.draft);
+            Ok(())
+        }
+
+        #[test]
+        fn unknown_option_rejected() {
+            assert!(ProposalPushOptions::default().add("ci.skip").is_err());
+        }
+
+        #[test]
+        fn description_without_title_rejected() -> Result<()> {
+            let mut options = ProposalPushOptions::default();
+            options.add("description=some description")?;
+            assert!(options.cover_letter_title_description().is_err());
+            Ok(())
+        }
+    }
 }
@@ -1,13 +1,16 @@
 use core::str;
 use std::{
     collections::HashMap,
-    fmt,
     io::{self, Stdin},
-    str::FromStr,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
 };
 
 use anyhow::{Context, Result, bail};
 use git2::Repository;
+pub use gnostr_ngit::git::remote::{
+    Direction, fetch_or_list_error_is_not_authentication_failure, get_read_protocols_to_try,
+    join_with_and, set_protocol_preference,
+};
 use gnostr_ngit::{
     client::{
         get_all_proposal_patch_events_from_cache, get_events_from_local_cache,
@@ -16,6 +19,7 @@ use gnostr_ngit::{
     git::{
         Repo, RepoActions,
         nostr_url::{CloneUrl, NostrUrlDecoded, ServerProtocol},
+        remote::{error_might_be_authentication_related, get_protocol_preference},
     },
     git_events::{
         event_is_revision_root, get_most_recent_patch_with_ancestors,
@@ -25,6 +29,29 @@ use gnostr_ngit::{
 };
 use nostr_sdk::{Event, EventId, Kind, PublicKey, Url};
 
+static PROGRESS: AtomicBool = AtomicBool::new(true);
+static VERBOSITY: AtomicU8 = AtomicU8::new(1);
+
+/// set by git with `option progress`. when false, git server transfer
+/// progress and relay progress bars aren't drawn
+pub fn set_progress(progress: bool) {
+    PROGRESS.store(progress, Ordering::Relaxed);
+}
+
+pub fn show_progress() -> bool {
+    PROGRESS.load(Ordering::Relaxed)
+}
+
+/// set by git with `option verbosity`. 0 is `--quiet`, 1 is the default and
+/// each `-v` adds one
+pub fn set_verbosity(verbosity: u8) {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
 pub fn get_short_git_server_name(git_repo: &Repo, url: &str) -> std::string::String {
     if let Ok(name) = get_remote_name_by_url(&git_repo.git_repo, url) {
         return name;
@@ -195,63 +222,6 @@ pub fn find_proposal_and_patches_by_branch_name<'a>(
     })
 }
 
-pub fn join_with_and<T: ToString>(items: &[T]) -> String {
-    match items.len() {
-        0 => String::new(),
-        1 => items[0].to_string(),
-        _ => {
-            let last_item = items.last().unwrap().to_string();
-            let rest = &items[..items.len() - 1];
-            format!(
-                "{} and {}",
-                rest.iter()
-                    .map(std::string::ToString::to_string)
-                    .collect::<Vec<_>>()
-                    .join(", "),
-                last_item
-            )
-        }
-    }
-}
-
-/// get an ordered vector of server protocols to attempt
-pub fn get_read_protocols_to_try(
-    git_repo: &Repo,
-    server_url: &CloneUrl,
-    decoded_nostr_url: &NostrUrlDecoded,
-) -> Vec<ServerProtocol> {
-    if server_url.protocol() == ServerProtocol::Filesystem {
-        vec![(ServerProtocol::Filesystem)]
-    } else if let Some(protocol) = &decoded_nostr_url.protocol {
-        vec![protocol.clone()]
-    } else {
-        let mut list = if server_url.protocol() == ServerProtocol::Http {
-            vec![
-                ServerProtocol::UnauthHttp,
-                ServerProtocol::Ssh,
-                // note: list and fetch stop here if ssh was authenticated
-                ServerProtocol::Http,
-            ]
-        } else if server_url.protocol() == ServerProtocol::Ftp {
-            vec![ServerProtocol::Ftp, ServerProtocol::Ssh]
-        } else {
-            vec![
-                ServerProtocol::UnauthHttps,
-                ServerProtocol::Ssh,
-                // note: list and fetch stop here if ssh was authenticated
-                ServerProtocol::Https,
-            ]
-        };
-        if let Some(protocol) = get_protocol_preference(git_repo, server_url, &Direction::Fetch) {
-            if let Some(pos) = list.iter().position(|p| *p == protocol) {
-                list.remove(pos);
-                list.insert(0, protocol);
-            }
-        }
-        list
-    }
-}
-
 /// get an ordered vector of server protocols to attempt
 pub fn get_write_protocols_to_try(
     git_repo: &Repo,
@@ -289,140 +259,7 @@ pub fn get_write_protocols_to_try(
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub enum Direction {
-    Push,
-    Fetch,
-}
-impl fmt::Display for Direction {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Direction::Push => write!(f, "push"),
-            Direction::Fetch => write!(f, "fetch"),
-        }
-    }
-}
-
-pub fn get_protocol_preference(
-    git_repo: &Repo,
-    server_url: &CloneUrl,
-    direction: &Direction,
-) -> Option<ServerProtocol> {
-    let server_short_name = server_url.short_name();
-    if let Ok(Some(list)) =
-        git_repo.get_git_config_item(format!("nostr.protocol-{direction}").as_str(), Some(false))
-    {
-        for item in list.split(';') {
-            let pair = item.split(',').collect::<Vec<&str>>();
-            if let Some(url) = pair.get(1) {
-                if *url == server_short_name {
-                    if let Some(protocol) = pair.first() {
-                        if let Ok(protocol) = ServerProtocol::from_str(protocol) {
-                            return Some(protocol);
-                        }
-                    }
-                }
-            }
-        }
-    }
-    None
-}
-
-pub fn set_protocol_preference(
-    git_repo: &Repo,
-    protocol: &ServerProtocol,
-    server_url: &CloneUrl,
-    direction: &Direction,
-) -> Result<()> {
-    let server_short_name = server_url.short_name();
-    let mut new = String::new();
-    if let Some(list) =
-        git_repo.get_git_config_item(format!("nostr.protocol-{direction}").as_str(), Some(false))?
-    {
-        for item in list.split(';') {
-            let pair = item.split(',').collect::<Vec<&str>>();
-            if let Some(url) = pair.get(1) {
-                if *url != server_short_name && !item.is_empty() {
-                    new.push_str(format!("{item};").as_str());
-                }
-            }
-        }
-    }
-    new.push_str(format!("{protocol},{server_short_name};").as_str());
-
-    git_repo.save_git_config_item(
-        format!("nostr.protocol-{direction}").as_str(),
-        new.as_str(),
-        false,
-    )
-}
-
-/// to understand whether to try over another protocol
-pub fn fetch_or_list_error_is_not_authentication_failure(error: &anyhow::Error) -> bool {
-    !error_might_be_authentication_related(error)
-}
-
 /// to understand whether to try over another protocol
 pub fn push_error_is_not_authentication_failure(error: &anyhow::Error) -> bool {
     !error_might_be_authentication_related(error)
 }
-
-pub fn error_might_be_authentication_related(error: &anyhow::Error) -> bool {
-    let error_str = error.to_string();
-    for s in [
-        "no ssh keys found",
-        "invalid or unknown remote ssh hostkey",
-        "authentication",
-        "Permission",
-        "permission",
-        "not found",
-    ] {
-        if error_str.contains(s) {
-            return true;
-        }
-    }
-    false
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    mod join_with_and {
-        use super::*;
-        #[test]
-        fn test_empty() {
-            let items: Vec<&str> = vec![];
-            assert_eq!(join_with_and(&items), "");
-        }
-
-        #[test]
-        fn test_single_item() {
-            let items = vec!["apple"];
-            assert_eq!(join_with_and(&items), "apple");
-        }
-
-        #[test]
-        fn test_two_items() {
-            let items = vec!["apple", "banana"];
-            assert_eq!(join_with_and(&items), "apple and banana");
-        }
-
-        #[test]
-        fn test_three_items() {
-            let items = vec!["apple", "banana", "cherry"];
-            assert_eq!(join_with_and(&items), "apple, banana and cherry");
-        }
-
-        #[test]
-        fn test_four_items() {
-            let items = vec!["apple", "banana", "cherry", "date"];
-            assert_eq!(join_with_and(&items), "apple, banana, cherry and date");
-        }
-
-        #[test]
-        fn test_multiple_items() {
-            let items = vec!["one", "two", "three", "four", "five"];
-            assert_eq!(join_with_and(&items), "one, two, three, four and five");
-        }
-    }
-}
@@ -5,21 +5,71 @@
 use anyhow::Result;
 use clap::Parser;
 use gnostr_ngit::{
-    cli::{AccountCommands, Cli, Commands},
+    cli::{
+        AccountCommands, Cli, Commands, IssueCommands, OutboxCommands, RepoCommands, StateCommands,
+    },
     cli_interactor, client, git, git_events, login, repo_ref, sub_commands,
 };
+use nostr_sdk::Kind;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    client::set_offline(cli.offline);
     match &cli.command {
         Commands::Account(args) => match &args.account_command {
             AccountCommands::Login(sub_args) => sub_commands::login::launch(&cli, sub_args).await,
             AccountCommands::Logout => sub_commands::logout::launch().await,
             AccountCommands::ExportKeys => sub_commands::export_keys::launch().await,
         },
+        Commands::Issue(args) => match &args.issue_command {
+            IssueCommands::New(sub_args) => sub_commands::issue::launch_new(&cli, sub_args).await,
+            IssueCommands::List(sub_args) => sub_commands::issue::launch_list(sub_args).await,
+            IssueCommands::View(sub_args) => sub_commands::issue::launch_view(sub_args).await,
+            IssueCommands::Comment(sub_args) => {
+                sub_commands::issue::launch_comment(&cli, sub_args).await
+            }
+            IssueCommands::Close(sub_args) => {
+                sub_commands::issue::launch_set_status(&cli, sub_args, Kind::GitStatusClosed).await
+            }
+            IssueCommands::Reopen(sub_args) => {
+                sub_commands::issue::launch_set_status(&cli, sub_args, Kind::GitStatusOpen).await
+            }
+        },
+        Commands::Comment(args) => sub_commands::comment::launch(&cli, args).await,
         Commands::Init(args) => sub_commands::init::launch(&cli, args).await,
-        Commands::List => sub_commands::list::launch().await,
+        Commands::List(args) => sub_commands::list::launch(args).await,
+        Commands::Checkout(args) => sub_commands::checkout::launch(args).await,
+        Commands::Apply(args) => sub_commands::apply::launch(args).await,
         Commands::Send(args) => sub_commands::send::launch(&cli, args, false).await,
+        Commands::Status(args) => sub_commands::status::launch(&cli, args).await,
+        Commands::Outbox(args) => match &args.outbox_command {
+            OutboxCommands::List => sub_commands::outbox::launch_list().await,
+            OutboxCommands::Retry(sub_args) => sub_commands::outbox::launch_retry(sub_args).await,
+            OutboxCommands::Drop(sub_args) => sub_commands::outbox::launch_drop(sub_args).await,
+        },
+        Commands::Sync(args) => sub_commands::sync::launch(args).await,
+        Commands::State(args) => match &args.state_command {
+            StateCommands::Show => sub_commands::state::launch_show().await,
+            StateCommands::Publish(sub_args) => {
+                sub_commands::state::launch_publish(&cli, sub_args).await
+            }
+            StateCommands::SyncFrom(sub_args) => {
+                sub_commands::state::launch_sync_from(&cli, sub_args).await
+            }
+            StateCommands::Diff => sub_commands::state::launch_diff().await,
+        },
+        Commands::Repo(args) => match &args.repo_command {
+            RepoCommands::Show(sub_args) => sub_commands::repo::launch_show(sub_args).await,
+            RepoCommands::Edit(sub_args) => sub_commands::repo::launch_edit(&cli, sub_args).await,
+            RepoCommands::Archive(sub_args) => {
+                sub_commands::repo::launch_archive(&cli, sub_args).await
+            }
+            RepoCommands::Delete(sub_args) => {
+                sub_commands::repo::launch_delete(&cli, sub_args).await
+            }
+        },
+        Commands::Search(args) => sub_commands::search::launch(args).await,
+        Commands::Fork(args) => sub_commands::fork::launch(&cli, args).await,
     }
 }
@@ -0,0 +1,203 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::{Context, Result, bail};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use nostr::{
+    JsonUtil,
+    hashes::{Hash, sha256::Hash as Sha256Hash},
+};
+use nostr_sdk::{EventBuilder, Kind, NostrSigner, Tag, TagKind, Timestamp};
+use serde::Deserialize;
+
+use crate::{
+    client::{get_local_data, save_local_data, sign_event},
+    git::{
+        Repo, RepoActions,
+        nostr_url::{CloneUrl, ServerProtocol},
+    },
+};
+
+/// BUD-01 authorization event kind
+static BLOSSOM_AUTH_KIND: u16 = 24242;
+static BLOSSOM_AUTH_EXPIRATION_SECS: u64 = 60 * 5;
+static IMPORTED_PACK_IDENTIFIER_PREFIX: &str = "ngit-blossom-pack/";
+
+#[derive(Deserialize)]
+struct BlobDescriptor {
+    sha256: String,
+}
+
+pub fn is_blossom_url(git_server_url: &str) -> bool {
+    git_server_url
+        .parse::<CloneUrl>()
+        .is_ok_and(|url| url.protocol() == ServerProtocol::Blossom)
+}
+
+pub fn blob_sha256(bytes: &[u8]) -> String {
+    Sha256Hash::hash(bytes).to_string()
+}
+
+fn base_url(git_server_url: &str) -> Result<String> {
+    git_server_url
+        .parse::<CloneUrl>()?
+        .format_as(&ServerProtocol::Blossom, &None)
+}
+
+async fn authorization_header(
+    signer: &Arc<dyn NostrSigner>,
+    verb: &str,
+    sha256: &str,
+) -> Result<String> {
+    let event = sign_event(
+        EventBuilder::new(Kind::Custom(BLOSSOM_AUTH_KIND), format!("{verb} git pack")).tags([
+            Tag::hashtag(verb),
+            Tag::expiration(Timestamp::from(
+                Timestamp::now().as_u64() + BLOSSOM_AUTH_EXPIRATION_SECS,
+            )),
+            Tag::custom(TagKind::Custom("x".into()), vec![sha256.to_string()]),
+        ]),
+        signer,
+        "blossom authorization".to_string(),
+    )
+    .await?;
+    Ok(format!("Nostr {}", BASE64_STANDARD.encode(event.as_json())))
+}
+
+/// BUD-02 upload. returns the sha256 of the blob
+pub async fn upload_blob(
+    git_server_url: &str,
+    blob: Vec<u8>,
+    signer: &Arc<dyn NostrSigner>,
+) -> Result<String> {
+    let sha256 = blob_sha256(&blob);
+    let response = reqwest::Client::new()
+        .put(format!("{}/upload", base_url(git_server_url)?))
+        .header(
+            "Authorization",
+            authorization_header(signer, "upload", &sha256).await?,
+        )
+        .header("Content-Type", "application/octet-stream")
+        .body(blob)
+        .send()
+        .await
+        .context("failed to connect to blossom server")?;
+    if !response.status().is_success() {
+        bail!(
+            "upload rejected with status {}{}",
+            response.status(),
+            response
+                .headers()
+                .get("X-Reason")
+                .and_then(|reason| reason.to_str().ok())
+                .map(|reason| format!(": {reason}"))
+                .unwrap_or_default(),
+        );
+    }
+    let descriptor: BlobDescriptor = response
+        .json()
+        .await
+        .context("blossom server returned an invalid blob descriptor")?;
+    if descriptor.sha256 != sha256 {
+        bail!("blossom server stored blob with an unexpected sha256");
+    }
+    Ok(sha256)
+}
+
+/// BUD-01 get. errors if the blob doesn't match its sha256
+pub async fn download_blob(git_server_url: &str, sha256: &str) -> Result<Vec<u8>> {
+    let response = reqwest::get(format!("{}/{sha256}", base_url(git_server_url)?))
+        .await
+        .context("failed to connect to blossom server")?;
+    if !response.status().is_success() {
+        bail!("blob {sha256} not found: status {}", response.status());
+    }
+    let blob = response
+        .bytes()
+        .await
+        .context("failed to download blob")?
+        .to_vec();
+    if blob_sha256(&blob) != sha256 {
+        bail!("blob {sha256} from blossom server doesn't match its sha256");
+    }
+    Ok(blob)
+}
+
+/// downloads and imports packs that haven't been imported into the repository
+/// before. returns the number imported
+pub async fn import_packs(
+    git_repo: &Repo,
+    git_server_url: &str,
+    packs: &[String],
+) -> Result<usize> {
+    let git_repo_path = git_repo.get_path()?;
+    let imported: HashSet<String> = get_local_data(git_repo_path, IMPORTED_PACK_IDENTIFIER_PREFIX)
+        .await?
+        .into_iter()
+        .map(|(identifier, _)| identifier)
+        .collect();
+    let mut count = 0;
+    for pack in packs {
+        let identifier = format!("{IMPORTED_PACK_IDENTIFIER_PREFIX}{pack}");
+        if imported.contains(&identifier) {
+            continue;
+        }
+        git_repo
+            .import_pack(&download_blob(git_server_url, pack).await?)
+            .context(format!("failed to import pack {pack}"))?;
+        save_local_data(git_repo_path, &identifier, "").await?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::{TEST_KEY_1_KEYS, blossom::BlossomServer, git::GitTestRepo};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn pushed_pack_can_be_imported_into_another_repo() -> Result<()> {
+        let server = BlossomServer::start()?;
+        let git_server_url = format!("blossom+http://localhost:{}", server.port);
+        let signer: Arc<dyn NostrSigner> = Arc::new(TEST_KEY_1_KEYS.clone());
+
+        let test_repo = GitTestRepo::default();
+        let tip = test_repo.populate()?;
+        let git_repo = Repo::from_path(&test_repo.dir)?;
+        let pack = git_repo
+            .create_pack(&[tip.to_string()], &[])?
+            .context("pack should contain objects")?;
+
+        let sha256 = upload_blob(&git_server_url, pack.clone(), &signer).await?;
+        assert_eq!(sha256, blob_sha256(&pack));
+        assert!(server.blobs.lock().unwrap().contains_key(&sha256));
+
+        let other_test_repo = GitTestRepo::default();
+        let other_git_repo = Repo::from_path(&other_test_repo.dir)?;
+        let packs = vec![sha256];
+        assert_eq!(
+            import_packs(&other_git_repo, &git_server_url, &packs).await?,
+            1
+        );
+        assert!(other_git_repo.does_commit_exist(&tip.to_string())?);
+        // packs are only downloaded once
+        assert_eq!(
+            import_packs(&other_git_repo, &git_server_url, &packs).await?,
+            0
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_errors_when_blob_is_missing() -> Result<()> {
+        let server = BlossomServer::start()?;
+        let git_server_url = format!("blossom+http://localhost:{}", server.port);
+        assert!(
+            download_blob(&git_server_url, &blob_sha256(b"missing"))
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
+}
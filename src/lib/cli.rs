@@ -28,6 +28,10 @@ pub struct Cli {
     /// disable spinner animations
     #[arg(long, action, hide = true)]
     pub disable_cli_spinners: bool,
+    /// don't connect to relays. use cached events and queue new events in an
+    /// outbox to publish when next online
+    #[arg(long, global = true, action)]
+    pub offline: bool,
 }
 
 pub fn extract_signer_cli_arguments(args: &Cli) -> Result<Option<SignerInfo>> {
@@ -61,9 +65,33 @@ pub enum Commands {
     /// submit PR with advanced options
     Send(sub_commands::send::SubCommandArgs),
     /// list PRs; checkout, apply or download selected
-    List,
+    List(sub_commands::list::SubCommandArgs),
+    /// checkout the latest revision of a PR as a local branch
+    Checkout(sub_commands::checkout::SubCommandArgs),
+    /// apply the latest revision of a PR as new commits on the current branch
+    Apply(sub_commands::apply::SubCommandArgs),
     /// login, logout or export keys
     Account(AccountSubCommandArgs),
+    /// create, list, view, comment on and close issues
+    Issue(IssueSubCommandArgs),
+    /// comment on a proposal or one of its patches
+    Comment(sub_commands::comment::SubCommandArgs),
+    /// mark a PR as open, draft, closed or applied
+    Status(sub_commands::status::SubCommandArgs),
+    /// list, retry or drop events that haven't reached all their relays
+    Outbox(OutboxSubCommandArgs),
+    /// fetch the latest events from each relay and report what every relay
+    /// returned
+    Sync(sub_commands::sync::SubCommandArgs),
+    /// show, publish or repair the nostr state of the repository's branches and
+    /// tags
+    State(StateSubCommandArgs),
+    /// show, edit, archive or delete the repository announcement
+    Repo(RepoSubCommandArgs),
+    /// find repositories by name, description, hashtag or maintainer
+    Search(sub_commands::search::SubCommandArgs),
+    /// clone a repository and announce it as your own fork
+    Fork(sub_commands::fork::SubCommandArgs),
 }
 
 #[derive(Subcommand)]
@@ -81,3 +109,78 @@ pub struct AccountSubCommandArgs {
     #[command(subcommand)]
     pub account_command: AccountCommands,
 }
+
+#[derive(Subcommand)]
+pub enum IssueCommands {
+    /// open a new issue
+    New(sub_commands::issue::NewSubCommandArgs),
+    /// list open issues
+    List(sub_commands::issue::ListSubCommandArgs),
+    /// show an issue and its comments
+    View(sub_commands::issue::IssueRefSubCommandArgs),
+    /// comment on an issue
+    Comment(sub_commands::issue::CommentSubCommandArgs),
+    /// mark an issue as closed
+    Close(sub_commands::issue::IssueRefSubCommandArgs),
+    /// reopen a closed issue
+    Reopen(sub_commands::issue::IssueRefSubCommandArgs),
+}
+
+#[derive(clap::Parser)]
+pub struct IssueSubCommandArgs {
+    #[command(subcommand)]
+    pub issue_command: IssueCommands,
+}
+
+#[derive(Subcommand)]
+pub enum OutboxCommands {
+    /// list queued and undelivered events with their status on each relay
+    List,
+    /// try to publish events to the relays they haven't reached
+    Retry(sub_commands::outbox::EventIdsSubCommandArgs),
+    /// remove events from the outbox without publishing them
+    Drop(sub_commands::outbox::DropSubCommandArgs),
+}
+
+#[derive(clap::Parser)]
+pub struct OutboxSubCommandArgs {
+    #[command(subcommand)]
+    pub outbox_command: OutboxCommands,
+}
+
+#[derive(Subcommand)]
+pub enum StateCommands {
+    /// print the branches and tags in the nostr state
+    Show,
+    /// update the nostr state with local branches and tags without pushing to
+    /// git servers
+    Publish(sub_commands::state::PublishSubCommandArgs),
+    /// replace the nostr state with the branches and tags on a git server
+    SyncFrom(sub_commands::state::SyncFromSubCommandArgs),
+    /// compare each maintainer's state event and show branches that diverge
+    Diff,
+}
+
+#[derive(clap::Parser)]
+pub struct StateSubCommandArgs {
+    #[command(subcommand)]
+    pub state_command: StateCommands,
+}
+
+#[derive(Subcommand)]
+pub enum RepoCommands {
+    /// print the repository details ngit has resolved from nostr
+    Show(sub_commands::repo::ShowSubCommandArgs),
+    /// change individual fields of the repository announcement and republish it
+    Edit(sub_commands::repo::EditSubCommandArgs),
+    /// mark the repository as archived or moved to a new home
+    Archive(sub_commands::repo::ArchiveSubCommandArgs),
+    /// request relays delete your repository announcement and state
+    Delete(sub_commands::repo::DeleteSubCommandArgs),
+}
+
+#[derive(clap::Parser)]
+pub struct RepoSubCommandArgs {
+    #[command(subcommand)]
+    pub repo_command: RepoCommands,
+}
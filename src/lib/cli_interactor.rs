@@ -283,3 +283,26 @@ pub fn count_lines_per_msg_vec(width: u16, msgs: &[String], prefix_len: usize) -
         .map(|msg| count_lines_per_msg(width, msg, prefix_len))
         .sum()
 }
+
+/// line based output that can clear its last lines to redraw progress.
+/// implemented for [`console::Term`] and by displays that combine the output of
+/// concurrent git server operations
+pub trait LineOutput: Send + Sync {
+    fn write_line(&self, line: &str) -> std::io::Result<()>;
+    fn clear_last_lines(&self, n: usize) -> std::io::Result<()>;
+    fn columns(&self) -> u16;
+}
+
+impl LineOutput for console::Term {
+    fn write_line(&self, line: &str) -> std::io::Result<()> {
+        console::Term::write_line(self, line)
+    }
+
+    fn clear_last_lines(&self, n: usize) -> std::io::Result<()> {
+        console::Term::clear_last_lines(self, n)
+    }
+
+    fn columns(&self) -> u16 {
+        self.size().1
+    }
+}
@@ -15,11 +15,14 @@ use std::{
     fmt::{Display, Write},
     fs::create_dir_all,
     path::Path,
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
     time::Duration,
 };
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
 use async_trait::async_trait;
 use console::Style;
 use futures::{
@@ -40,20 +43,34 @@ use nostr_lmdb::NostrLMDB;
 use nostr_relay_pool::relay::ReqExitPolicy;
 use nostr_sdk::{
     EventBuilder, EventId, Kind, NostrSigner, Options, PublicKey, RelayUrl, SingleLetterTag,
-    Timestamp, prelude::RelayLimits,
+    SyncOptions, Tag, Timestamp, prelude::RelayLimits,
 };
 
 use crate::{
     get_dirs,
     git::{Repo, RepoActions},
     git_events::{
-        event_is_cover_letter, event_is_patch_set_root, event_is_revision_root, status_kinds,
+        event_is_cover_letter, event_is_issue, event_is_patch_set_root, event_is_revision_root,
+        event_tag_from_nip19_or_hex, is_event_proposal_root_for_branch, status_kinds,
     },
     login::{get_likely_logged_in_user, user::get_user_ref_from_cache},
+    outbox::{DeliveryStatus, OutboxEntry, add_to_outbox, publish_outbox, save_outbox_entry},
     repo_ref::RepoRef,
     repo_state::RepoState,
 };
 
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// when offline, relays are not contacted. fetches are served from the local
+/// cache and `send_events` queues events in the outbox.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
 #[allow(clippy::struct_field_names)]
 pub struct Client {
     client: nostr_sdk::Client,
@@ -106,6 +123,7 @@ pub trait Connect {
         git_repo_path: Option<&'a Path>,
         repo_coordinates: Option<&'a Nip19Coordinate>,
         user_profiles: &HashSet<PublicKey>,
+        options: FetchOptions,
     ) -> Result<(Vec<Result<FetchReport>>, MultiProgress)>;
     async fn fetch_all_from_relay<'a>(
         &self,
@@ -269,6 +287,12 @@ impl Connect for Client {
         filters: Vec<nostr::Filter>,
         progress_reporter: MultiProgress,
     ) -> Result<(Vec<Result<Vec<nostr::Event>>>, MultiProgress)> {
+        if is_offline() {
+            return Ok((
+                relays.iter().map(|_| Err(anyhow!("offline"))).collect(),
+                progress_reporter,
+            ));
+        }
         // add relays
         for relay in &relays {
             self.client
@@ -342,7 +366,20 @@ impl Connect for Client {
         git_repo_path: Option<&'a Path>,
         trusted_maintainer_coordinate: Option<&'a Nip19Coordinate>,
         user_profiles: &HashSet<PublicKey>,
+        options: FetchOptions,
     ) -> Result<(Vec<Result<FetchReport>>, MultiProgress)> {
+        if is_offline() {
+            return Ok((vec![], MultiProgress::new()));
+        }
+
+        // retry events that previously failed to reach relays
+        if let Some(git_repo_path) = git_repo_path {
+            if let Err(error) = publish_outbox(self, git_repo_path).await {
+                let _ = console::Term::stderr()
+                    .write_line(&format!("failed to publish events from outbox: {error}"));
+            }
+        }
+
         let fallback_relays = &self
             .fallback_relays
             .iter()
@@ -356,6 +393,10 @@ impl Connect for Client {
             fallback_relays.clone(),
         )
         .await?;
+        request.since = options.since;
+        // a checkpoint only means every earlier event has been fetched if the
+        // fetch it records wasn't itself bounded
+        request.use_since_checkpoints = !options.full && options.since.is_none();
 
         let progress_reporter = MultiProgress::new();
 
@@ -463,7 +504,7 @@ impl Connect for Client {
                                     .to_string(),
                                 );
                             }
-                            Err(error)
+                            Err(error.context(relay_url.to_string()))
                         }
                         Ok(res) => Ok(res),
                     }
@@ -532,22 +573,86 @@ impl Connect for Client {
             .copied()
             .collect();
 
-        let mut report = FetchReport::default();
-
         let relay_url = request
             .selected_relay
             .clone()
             .context("fetch_all_from_relay called without a relay")?;
 
+        let mut report = FetchReport {
+            relay: Some(relay_url.clone()),
+            ..FetchReport::default()
+        };
+
         let relay_column_width = request.relay_column_width;
 
+        let checkpoint_coordinates: Vec<Nip19Coordinate> = request
+            .repo_coordinates_without_relays
+            .iter()
+            .map(|(c, _)| c.clone())
+            .collect();
+        let fetch_started_at = Timestamp::now();
+        let mut checkpoint = match git_repo_path {
+            Some(git_repo_path) if request.use_since_checkpoints => {
+                get_since_checkpoint(git_repo_path, &relay_url, &checkpoint_coordinates).await?
+            }
+            _ => None,
+        };
+        let mut requested_proposal_roots: HashSet<EventId> = HashSet::new();
+
         self.connect(&relay_url).await?;
 
         let dim = Style::new().color256(247);
 
+        // assume NIP-77 support until the relay rejects it
+        let mut negentropy_supported = true;
+
         loop {
-            let filters =
-                get_fetch_filters(&fresh_coordinates, &fresh_proposal_roots, &fresh_profiles);
+            requested_proposal_roots.extend(fresh_proposal_roots.iter().copied());
+            // the checkpoint only applies to repositories and proposals that had
+            // been fetched from this relay when it was recorded. others, such as
+            // proposals since found on another relay, and profiles missing from
+            // the cache need their full history
+            let (checkpointed_coordinates, checkpointed_proposal_roots, since_checkpoint) =
+                if let Some(checkpoint) = checkpoint.take() {
+                    (
+                        fresh_coordinates
+                            .intersection(&checkpoint.coordinates)
+                            .cloned()
+                            .collect::<HashSet<Nip19Coordinate>>(),
+                        fresh_proposal_roots
+                            .intersection(&checkpoint.proposal_roots)
+                            .copied()
+                            .collect::<HashSet<EventId>>(),
+                        Some(checkpoint.since),
+                    )
+                } else {
+                    (HashSet::new(), HashSet::new(), None)
+                };
+            let filters = [
+                apply_since(
+                    get_fetch_filters(
+                        &checkpointed_coordinates,
+                        &checkpointed_proposal_roots,
+                        &HashSet::new(),
+                    ),
+                    request.since.or(since_checkpoint),
+                ),
+                apply_since(
+                    get_fetch_filters(
+                        &fresh_coordinates
+                            .difference(&checkpointed_coordinates)
+                            .cloned()
+                            .collect(),
+                        &fresh_proposal_roots
+                            .difference(&checkpointed_proposal_roots)
+                            .copied()
+                            .collect(),
+                        &fresh_profiles,
+                    ),
+                    request.since,
+                ),
+            ]
+            .concat();
 
             if let Some(pb) = &pb {
                 pb.set_prefix(
@@ -569,14 +674,26 @@ impl Connect for Client {
             fresh_profiles = HashSet::new();
 
             let relay = self.client.relay(&relay_url).await?;
-            let events: Vec<nostr::Event> = get_events_of(&relay, filters.clone(), &None)
-                .await?
+            let events = match git_repo_path {
+                Some(git_repo_path) if negentropy_supported => {
+                    if let Some(events) =
+                        get_missing_events_of_with_negentropy(&relay, git_repo_path, &filters)
+                            .await?
+                    {
+                        events
+                    } else {
+                        negentropy_supported = false;
+                        get_events_of(&relay, filters.clone(), &None).await?
+                    }
+                }
+                _ => get_events_of(&relay, filters.clone(), &None).await?,
+            };
+            let events: Vec<nostr::Event> = events
                 .iter()
                 // don't process events that don't match filters
                 .filter(|e| filters.iter().any(|f| f.match_event(e)))
                 .cloned()
                 .collect();
-            // TODO: try reconcile
 
             process_fetched_events(
                 events,
@@ -596,6 +713,18 @@ impl Connect for Client {
                 break;
             }
         }
+        if let Some(git_repo_path) = git_repo_path {
+            if request.use_since_checkpoints {
+                save_since_checkpoint(
+                    git_repo_path,
+                    &relay_url,
+                    &checkpoint_coordinates,
+                    fetch_started_at,
+                    &requested_proposal_roots,
+                )
+                .await?;
+            }
+        }
         if let Some(pb) = pb {
             pb.set_style(pb_after_style(true));
             pb.set_prefix(
@@ -618,24 +747,166 @@ impl Connect for Client {
 
 static CONNECTION_TIMEOUT: u64 = 3;
 static GET_EVENTS_TIMEOUT: u64 = 7;
+static NEGENTROPY_TIMEOUT: u64 = 3;
+/// ids per filter when requesting events found missing by negentropy
+static MISSING_IDS_PER_FILTER: usize = 500;
 
-async fn get_events_of(
-    relay: &nostr_sdk::Relay,
-    filters: Vec<nostr::Filter>,
-    pb: &Option<ProgressBar>,
-) -> Result<Vec<Event>> {
-    // relay.reconcile(filter, opts).await?;
-
+async fn connect_relay(relay: &nostr_sdk::Relay) -> Result<()> {
     if !relay.is_connected() {
         #[allow(clippy::large_futures)]
         let _ = relay
             .try_connect(std::time::Duration::from_secs(CONNECTION_TIMEOUT))
             .await;
     }
-
     if !relay.is_connected() {
         bail!("connection timeout");
-    } else if let Some(pb) = pb {
+    }
+    Ok(())
+}
+
+/// reconciles the local cache with the relay using NIP-77 negentropy and only
+/// downloads the events that are missing locally. returns None if the relay
+/// doesn't support negentropy so the caller can fall back to REQ filters.
+async fn get_missing_events_of_with_negentropy(
+    relay: &nostr_sdk::Relay,
+    git_repo_path: &Path,
+    filters: &[nostr::Filter],
+) -> Result<Option<Vec<Event>>> {
+    connect_relay(relay).await?;
+    let db = get_local_cache_database(git_repo_path).await?;
+    let opts = SyncOptions::default()
+        .initial_timeout(Duration::from_secs(NEGENTROPY_TIMEOUT))
+        .dry_run();
+    let mut missing: HashSet<EventId> = HashSet::new();
+    for filter in filters {
+        let items = db
+            .negentropy_items(filter.clone())
+            .await
+            .context("failed to get negentropy items from local cache")?;
+        match relay.sync_with_items(filter.clone(), items, &opts).await {
+            Ok(reconciliation) => missing.extend(reconciliation.remote),
+            Err(_) => return Ok(None),
+        }
+    }
+    if missing.is_empty() {
+        return Ok(Some(vec![]));
+    }
+    let missing: Vec<EventId> = missing.into_iter().collect();
+    Ok(Some(
+        get_events_of(
+            relay,
+            missing
+                .chunks(MISSING_IDS_PER_FILTER)
+                .map(|ids| nostr::Filter::default().ids(ids.to_vec()))
+                .collect(),
+            &None,
+        )
+        .await?,
+    ))
+}
+
+static SINCE_CHECKPOINT_IDENTIFIER_PREFIX: &str = "ngit-since-checkpoint/";
+/// events can reach a relay well after their created_at, eg. when published
+/// from another user's outbox or with a skewed clock, so checkpoints are
+/// applied with this overlap
+static SINCE_CHECKPOINT_OVERLAP_SECS: u64 = 60 * 60 * 24;
+
+fn since_checkpoint_identifier(relay_url: &RelayUrl, coordinate: &Nip19Coordinate) -> String {
+    format!(
+        "{SINCE_CHECKPOINT_IDENTIFIER_PREFIX}{}/{relay_url}",
+        coordinate.coordinate
+    )
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedSinceCheckpoint {
+    timestamp: u64,
+    /// proposals and issues whose events were requested from the relay in the
+    /// fetch the checkpoint records
+    proposal_roots: HashSet<EventId>,
+}
+
+/// what has already been fetched from a relay up to `since`
+struct SinceCheckpoint {
+    since: Timestamp,
+    coordinates: HashSet<Nip19Coordinate>,
+    proposal_roots: HashSet<EventId>,
+}
+
+/// the checkpoints of `coordinates` on relay. coordinates that have never been
+/// fully fetched from it are left out
+async fn get_since_checkpoint(
+    git_repo_path: &Path,
+    relay_url: &RelayUrl,
+    coordinates: &[Nip19Coordinate],
+) -> Result<Option<SinceCheckpoint>> {
+    let saved: HashMap<String, String> =
+        get_local_data(git_repo_path, SINCE_CHECKPOINT_IDENTIFIER_PREFIX)
+            .await?
+            .into_iter()
+            .collect();
+    let mut checkpoint: Option<SinceCheckpoint> = None;
+    for coordinate in coordinates {
+        let Some(saved) = saved
+            .get(&since_checkpoint_identifier(relay_url, coordinate))
+            .and_then(|c| serde_json::from_str::<SavedSinceCheckpoint>(c).ok())
+        else {
+            continue;
+        };
+        let since = Timestamp::from(
+            saved
+                .timestamp
+                .saturating_sub(SINCE_CHECKPOINT_OVERLAP_SECS),
+        );
+        let checkpoint = checkpoint.get_or_insert_with(|| SinceCheckpoint {
+            since,
+            coordinates: HashSet::new(),
+            proposal_roots: HashSet::new(),
+        });
+        checkpoint.since = checkpoint.since.min(since);
+        checkpoint.coordinates.insert(coordinate.clone());
+        checkpoint.proposal_roots.extend(saved.proposal_roots);
+    }
+    Ok(checkpoint)
+}
+
+async fn save_since_checkpoint(
+    git_repo_path: &Path,
+    relay_url: &RelayUrl,
+    coordinates: &[Nip19Coordinate],
+    timestamp: Timestamp,
+    proposal_roots: &HashSet<EventId>,
+) -> Result<()> {
+    let content = serde_json::to_string(&SavedSinceCheckpoint {
+        timestamp: timestamp.as_u64(),
+        proposal_roots: proposal_roots.clone(),
+    })?;
+    for coordinate in coordinates {
+        save_local_data(
+            git_repo_path,
+            &since_checkpoint_identifier(relay_url, coordinate),
+            &content,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+fn apply_since(filters: Vec<nostr::Filter>, since: Option<Timestamp>) -> Vec<nostr::Filter> {
+    if let Some(since) = since {
+        filters.into_iter().map(|f| f.since(since)).collect()
+    } else {
+        filters
+    }
+}
+
+async fn get_events_of(
+    relay: &nostr_sdk::Relay,
+    filters: Vec<nostr::Filter>,
+    pb: &Option<ProgressBar>,
+) -> Result<Vec<Event>> {
+    connect_relay(relay).await?;
+    if let Some(pb) = pb {
         pb.set_prefix(format!("connected  {}", relay.url()));
     }
 
@@ -868,6 +1139,101 @@ pub async fn save_event_in_global_cache(
     }
 }
 
+static LOCAL_DATA_KEY_CONFIG_ITEM: &str = "nostr.local-data-key";
+
+/// ngit-only data, such as the outbox, is kept in the local cache as NIP-78
+/// application data events. these are never sent to relays and are signed
+/// with a key generated for the repository and kept in its git config so that
+/// events cached from elsewhere can't be mistaken for them. the key is only
+/// generated when data is first saved.
+fn local_data_keys(git_repo_path: &Path) -> Result<nostr::Keys> {
+    if let Some(keys) = existing_local_data_keys(git_repo_path)? {
+        return Ok(keys);
+    }
+    let git_repo = Repo::from_path(&git_repo_path.to_path_buf())
+        .context("failed to open git repository to save local data key")?;
+    let keys = nostr::Keys::generate();
+    git_repo.save_git_config_item(
+        LOCAL_DATA_KEY_CONFIG_ITEM,
+        &keys.secret_key().to_secret_hex(),
+        false,
+    )?;
+    Ok(keys)
+}
+
+/// `None` if no local data has been saved for the repository
+fn existing_local_data_keys(git_repo_path: &Path) -> Result<Option<nostr::Keys>> {
+    let git_repo = Repo::from_path(&git_repo_path.to_path_buf())
+        .context("failed to open git repository to find local data key")?;
+    git_repo
+        .get_git_config_item(LOCAL_DATA_KEY_CONFIG_ITEM, Some(false))?
+        .map(|secret_key| {
+            nostr::Keys::parse(&secret_key).context(format!(
+                "invalid local data key in git config {LOCAL_DATA_KEY_CONFIG_ITEM}"
+            ))
+        })
+        .transpose()
+}
+
+fn local_data_filter(keys: &nostr::Keys) -> nostr::Filter {
+    nostr::Filter::default()
+        .kind(Kind::ApplicationSpecificData)
+        .author(keys.public_key())
+}
+
+pub async fn save_local_data(git_repo_path: &Path, identifier: &str, content: &str) -> Result<()> {
+    let keys = local_data_keys(git_repo_path)?;
+    let db = get_local_cache_database(git_repo_path).await?;
+    // replace rather than rely on created_at as updates can happen within a second
+    db.delete(local_data_filter(&keys).identifier(identifier))
+        .await
+        .context("failed to remove existing local data from cache")?;
+    let event = EventBuilder::new(Kind::ApplicationSpecificData, content)
+        .tag(Tag::identifier(identifier))
+        .sign_with_keys(&keys)
+        .context("failed to sign local data event")?;
+    db.save_event(&event)
+        .await
+        .context("failed to save local data in cache")?;
+    Ok(())
+}
+
+/// returns (identifier, content) of local data with identifiers starting with
+/// `identifier_prefix`
+pub async fn get_local_data(
+    git_repo_path: &Path,
+    identifier_prefix: &str,
+) -> Result<Vec<(String, String)>> {
+    let Some(keys) = existing_local_data_keys(git_repo_path)? else {
+        return Ok(vec![]);
+    };
+    Ok(
+        get_events_from_local_cache(git_repo_path, vec![local_data_filter(&keys)])
+            .await?
+            .into_iter()
+            .filter_map(|e| {
+                let identifier = e.tags.identifier()?.to_string();
+                if identifier.starts_with(identifier_prefix) {
+                    Some((identifier, e.content))
+                } else {
+                    None
+                }
+            })
+            .collect(),
+    )
+}
+
+pub async fn delete_local_data(git_repo_path: &Path, identifier: &str) -> Result<()> {
+    let Some(keys) = existing_local_data_keys(git_repo_path)? else {
+        return Ok(());
+    };
+    get_local_cache_database(git_repo_path)
+        .await?
+        .delete(local_data_filter(&keys).identifier(identifier))
+        .await
+        .context("failed to remove local data from cache")
+}
+
 pub async fn get_repo_ref_from_cache(
     git_repo_path: Option<&Path>,
     repo_coordinate: &Nip19Coordinate,
@@ -915,6 +1281,25 @@ pub async fn get_repo_ref_from_cache(
         }
     }
     repo_events.sort_by_key(|e| e.created_at);
+
+    if let Some(deletion) = get_repo_deletion_from_cache(git_repo_path, repo_coordinate).await? {
+        // an announcement published after the deletion revives the repository
+        if !repo_events
+            .iter()
+            .any(|e| e.pubkey.eq(&repo_coordinate.public_key) && e.created_at > deletion.created_at)
+        {
+            bail!(
+                "repository {} has been deleted by its maintainer{}",
+                repo_coordinate.identifier,
+                if deletion.content.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", deletion.content)
+                }
+            );
+        }
+    }
+
     let repo_ref = RepoRef::try_from((
         repo_events
             .first()
@@ -940,15 +1325,57 @@ pub async fn get_repo_ref_from_cache(
         }
     }
 
+    // only the trusted maintainer can archive or move the repository
+    let (archived, moved_to) = repo_events
+        .iter()
+        .filter(|e| e.pubkey.eq(&repo_coordinate.public_key))
+        .max_by_key(|e| e.created_at)
+        .and_then(|e| RepoRef::try_from((e.clone(), None)).ok())
+        .map_or((repo_ref.archived, repo_ref.moved_to.clone()), |r| {
+            (r.archived, r.moved_to)
+        });
+
     Ok(RepoRef {
         // use all maintainers from all events found, not just maintainers in the most
         // recent event
         maintainers: maintainers.iter().copied().collect::<Vec<PublicKey>>(),
         events,
+        archived,
+        moved_to,
         ..repo_ref
     })
 }
 
+/// most recent NIP-09 deletion of the repository announcement by its owner
+async fn get_repo_deletion_from_cache(
+    git_repo_path: Option<&Path>,
+    repo_coordinate: &Nip19Coordinate,
+) -> Result<Option<nostr::Event>> {
+    let Some(git_repo_path) = git_repo_path else {
+        return Ok(None);
+    };
+    let coordinate = Coordinate {
+        kind: Kind::GitRepoAnnouncement,
+        public_key: repo_coordinate.public_key,
+        identifier: repo_coordinate.identifier.clone(),
+    };
+    Ok(get_events_from_local_cache(
+        git_repo_path,
+        vec![
+            nostr::Filter::default()
+                .kind(Kind::EventDeletion)
+                .author(repo_coordinate.public_key)
+                .custom_tags(
+                    SingleLetterTag::lowercase(nostr_sdk::Alphabet::A),
+                    vec![coordinate.to_string()],
+                ),
+        ],
+    )
+    .await?
+    .into_iter()
+    .max_by_key(|e| e.created_at))
+}
+
 pub async fn get_state_from_cache(
     git_repo_path: Option<&Path>,
     repo_ref: &RepoRef,
@@ -1042,7 +1469,7 @@ async fn create_relays_request(
                 git_repo_path,
                 vec![
                     nostr::Filter::default()
-                        .kinds(vec![Kind::GitPatch])
+                        .kinds(vec![Kind::GitPatch, Kind::GitIssue])
                         .custom_tags(
                             SingleLetterTag::lowercase(nostr_sdk::Alphabet::A),
                             repo_coordinates_without_relays
@@ -1054,7 +1481,10 @@ async fn create_relays_request(
             )
             .await?
             {
-                if event_is_patch_set_root(event) || event_is_revision_root(event) {
+                if event_is_patch_set_root(event)
+                    || event_is_revision_root(event)
+                    || event_is_issue(event)
+                {
                     proposals.insert(event.id);
                     contributors.insert(event.pubkey);
                 }
@@ -1213,6 +1643,8 @@ async fn create_relays_request(
         existing_events,
         profiles_to_fetch_from_user_relays,
         user_relays_for_profiles,
+        since: None,
+        use_since_checkpoints: false,
     })
 }
 
@@ -1324,6 +1756,15 @@ async fn process_fetched_events(
                 {
                     fresh_profiles.insert(event.pubkey);
                 }
+            } else if event_is_issue(event) {
+                // issue replies and statuses are fetched alongside proposal ones
+                fresh_proposal_roots.insert(event.id);
+                report.issues.insert(event.id);
+                if !request.contributors.contains(&event.pubkey)
+                    && !fresh_profiles.contains(&event.pubkey)
+                {
+                    fresh_profiles.insert(event.pubkey);
+                }
             } else if [Kind::RelayList, Kind::Metadata].contains(&event.kind) {
                 if request.missing_contributor_profiles.contains(&event.pubkey) {
                     report.contributor_profiles.insert(event.pubkey);
@@ -1347,12 +1788,14 @@ async fn process_fetched_events(
             && !event
                 .tags
                 .event_ids()
-                .any(|id| report.proposals.contains(id))
+                .any(|id| report.proposals.contains(id) || report.issues.contains(id))
         {
             if event.kind.eq(&Kind::GitPatch) && !event_is_patch_set_root(event) {
                 report.commits.insert(event.id);
             } else if status_kinds().contains(&event.kind) {
                 report.statuses.insert(event.id);
+            } else if event.kind.eq(&Kind::Comment) {
+                report.comments.insert(event.id);
             }
         }
     }
@@ -1399,12 +1842,18 @@ pub fn consolidate_fetch_reports(reports: Vec<Result<FetchReport>>) -> FetchRepo
         for c in relay_report.proposals {
             report.proposals.insert(c);
         }
+        for c in relay_report.issues {
+            report.issues.insert(c);
+        }
         for c in relay_report.commits {
             report.commits.insert(c);
         }
         for c in relay_report.statuses {
             report.statuses.insert(c);
         }
+        for c in relay_report.comments {
+            report.comments.insert(c);
+        }
         for c in relay_report.contributor_profiles {
             report.contributor_profiles.insert(c);
         }
@@ -1427,7 +1876,7 @@ pub fn get_fetch_filters(
                 get_filter_state_events(repo_coordinates),
                 get_filter_repo_events(repo_coordinates),
                 nostr::Filter::default()
-                    .kinds(vec![Kind::GitPatch, Kind::EventDeletion])
+                    .kinds(vec![Kind::GitPatch, Kind::GitIssue, Kind::EventDeletion])
                     .custom_tags(
                         SingleLetterTag::lowercase(nostr_sdk::Alphabet::A),
                         repo_coordinates
@@ -1444,6 +1893,14 @@ pub fn get_fetch_filters(
                 nostr::Filter::default()
                     .events(proposal_ids.clone())
                     .kinds([vec![Kind::GitPatch, Kind::EventDeletion], status_kinds()].concat()),
+                // nip22 comments reference the thread root with an uppercase E tag
+                nostr::Filter::default().kind(Kind::Comment).custom_tags(
+                    SingleLetterTag::uppercase(nostr_sdk::Alphabet::E),
+                    proposal_ids
+                        .iter()
+                        .map(nostr::EventId::to_hex)
+                        .collect::<Vec<String>>(),
+                ),
             ]
         },
         if required_profiles.is_empty() {
@@ -1498,17 +1955,27 @@ pub fn get_filter_contributor_profiles(contributors: HashSet<PublicKey>) -> nost
 
 #[derive(Default)]
 pub struct FetchReport {
+    /// relay the events were fetched from. not set on consolidated reports
+    relay: Option<RelayUrl>,
     repo_coordinates_without_relays: HashSet<Nip19Coordinate>,
     updated_repo_announcements: Vec<(Nip19Coordinate, Timestamp)>,
     updated_state: Option<(Timestamp, EventId)>,
     proposals: HashSet<EventId>,
+    issues: HashSet<EventId>,
     /// commits against existing propoals
     commits: HashSet<EventId>,
     statuses: HashSet<EventId>,
+    comments: HashSet<EventId>,
     contributor_profiles: HashSet<PublicKey>,
     profile_updates: HashSet<PublicKey>,
 }
 
+impl FetchReport {
+    pub fn relay(&self) -> Option<&RelayUrl> {
+        self.relay.as_ref()
+    }
+}
+
 impl Display for FetchReport {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // report: "1 new maintainer, 1 announcement, 1 proposal, 3 commits, 2 statuses"
@@ -1545,6 +2012,13 @@ impl Display for FetchReport {
                 if self.proposals.len() > 1 { "s" } else { "" },
             ));
         }
+        if !self.issues.is_empty() {
+            display_items.push(format!(
+                "{} issue{}",
+                self.issues.len(),
+                if self.issues.len() > 1 { "s" } else { "" },
+            ));
+        }
         if !self.commits.is_empty() {
             display_items.push(format!(
                 "{} commit{}",
@@ -1559,6 +2033,13 @@ impl Display for FetchReport {
                 if self.statuses.len() > 1 { "es" } else { "" },
             ));
         }
+        if !self.comments.is_empty() {
+            display_items.push(format!(
+                "{} comment{}",
+                self.comments.len(),
+                if self.comments.len() > 1 { "s" } else { "" },
+            ));
+        }
         if !self.contributor_profiles.is_empty() {
             display_items.push(format!(
                 "{} user profile{}",
@@ -1598,6 +2079,16 @@ pub struct FetchRequest {
     existing_events: HashSet<EventId>,
     profiles_to_fetch_from_user_relays: HashMap<PublicKey, (Timestamp, Timestamp)>,
     user_relays_for_profiles: HashSet<RelayUrl>,
+    since: Option<Timestamp>,
+    use_since_checkpoints: bool,
+}
+
+#[derive(Default, Clone)]
+pub struct FetchOptions {
+    /// only request events created after this time
+    pub since: Option<Timestamp>,
+    /// ignore per-relay checkpoints and request every event
+    pub full: bool,
 }
 
 pub async fn fetching_with_report(
@@ -1607,12 +2098,17 @@ pub async fn fetching_with_report(
     trusted_maintainer_coordinate: &Nip19Coordinate,
 ) -> Result<FetchReport> {
     let term = console::Term::stderr();
+    if is_offline() {
+        term.write_line("offline: using cached events")?;
+        return Ok(FetchReport::default());
+    }
     term.write_line("fetching updates...")?;
     let (relay_reports, progress_reporter) = client
         .fetch_all(
             Some(git_repo_path),
             Some(trusted_maintainer_coordinate),
             &HashSet::new(),
+            FetchOptions::default(),
         )
         .await?;
     if !relay_reports.iter().any(std::result::Result::is_err) {
@@ -1620,9 +2116,9 @@ pub async fn fetching_with_report(
     }
     let report = consolidate_fetch_reports(relay_reports);
     if report.to_string().is_empty() {
-        println!("no updates");
+        term.write_line("no updates")?;
     } else {
-        println!("updates: {report}");
+        term.write_line(&format!("updates: {report}"))?;
     }
     Ok(report)
 }
@@ -1655,6 +2151,102 @@ pub async fn get_proposals_and_revisions_from_cache(
     Ok(proposals)
 }
 
+/// find a proposal by nevent, note, hex id, shorthand hex id or `pr/` branch
+/// name
+pub async fn get_proposal_from_cache_by_reference(
+    git_repo_path: &Path,
+    repo_ref: &RepoRef,
+    reference: &str,
+) -> Result<nostr::Event> {
+    let proposals = get_proposals_and_revisions_from_cache(git_repo_path, repo_ref.coordinates())
+        .await?
+        .into_iter()
+        .filter(|e| !event_is_revision_root(e))
+        .collect::<Vec<nostr::Event>>();
+
+    let logged_in_user = get_likely_logged_in_user(git_repo_path).await?;
+    if let Some(proposal) = proposals.iter().find(|e| {
+        is_event_proposal_root_for_branch(e, reference, logged_in_user.as_ref()).unwrap_or(false)
+    }) {
+        return Ok(proposal.clone());
+    }
+
+    if reference.len().ge(&4) {
+        if let Some(proposal) = proposals
+            .iter()
+            .find(|e| e.id.to_hex().starts_with(&reference.to_lowercase()))
+        {
+            return Ok(proposal.clone());
+        }
+    }
+
+    let event_id = match event_tag_from_nip19_or_hex(
+        reference,
+        "proposal",
+        nostr::nips::nip10::Marker::Root,
+        false,
+        false,
+    )?
+    .as_standardized()
+    {
+        Some(nostr_sdk::TagStandard::Event { event_id, .. }) => *event_id,
+        _ => bail!("{reference} is not a valid proposal reference"),
+    };
+    proposals
+        .iter()
+        .find(|e| e.id.eq(&event_id))
+        .cloned()
+        .context(format!(
+            "failed to find proposal {reference} in cache for this repository"
+        ))
+}
+
+pub async fn get_issues_from_cache(
+    git_repo_path: &Path,
+    repo_coordinates: HashSet<Nip19Coordinate>,
+) -> Result<Vec<nostr::Event>> {
+    let mut issues = get_events_from_local_cache(
+        git_repo_path,
+        vec![
+            nostr::Filter::default()
+                .kind(nostr::Kind::GitIssue)
+                .custom_tags(
+                    nostr::SingleLetterTag::lowercase(nostr_sdk::Alphabet::A),
+                    repo_coordinates
+                        .iter()
+                        .map(|c| c.coordinate.to_string())
+                        .collect::<Vec<String>>(),
+                ),
+        ],
+    )
+    .await?;
+    issues.sort_by_key(|e| e.created_at);
+    issues.reverse();
+    Ok(issues)
+}
+
+/// comments on a proposal or issue thread, oldest first
+pub async fn get_comments_from_cache(
+    git_repo_path: &Path,
+    root_ids: Vec<EventId>,
+) -> Result<Vec<nostr::Event>> {
+    let mut comments = get_events_from_local_cache(
+        git_repo_path,
+        vec![
+            nostr::Filter::default().kind(Kind::Comment).custom_tags(
+                SingleLetterTag::uppercase(nostr_sdk::Alphabet::E),
+                root_ids
+                    .iter()
+                    .map(nostr::EventId::to_hex)
+                    .collect::<Vec<String>>(),
+            ),
+        ],
+    )
+    .await?;
+    comments.sort_by_key(|e| e.created_at);
+    Ok(comments)
+}
+
 pub async fn get_all_proposal_patch_events_from_cache(
     git_repo_path: &Path,
     repo_ref: &RepoRef,
@@ -1784,6 +2376,41 @@ pub async fn send_events(
         }
     }
 
+    // delivery to repo and user relays is tracked in the outbox. fallback relays
+    // are only tracked when there are none of these
+    let tracked_relays: Vec<String> = {
+        let tracked: Vec<String> = relays
+            .iter()
+            .filter(|r| {
+                let r_clean = remove_trailing_slash(r);
+                repo_read_relays
+                    .iter()
+                    .chain(my_write_relays.iter())
+                    .any(|x| r_clean.eq(&remove_trailing_slash(x)))
+            })
+            .map(|r| (*r).to_string())
+            .collect();
+        if tracked.is_empty() {
+            relays.iter().map(|r| (*r).to_string()).collect()
+        } else {
+            tracked
+        }
+    };
+
+    if is_offline() {
+        let git_repo_path = git_repo_path
+            .context("cannot queue events while offline outside of a git repository")?;
+        add_to_outbox(git_repo_path, &events, tracked_relays).await?;
+        if !silent {
+            println!(
+                "offline: queued {} event{} in outbox. they will be published the next time ngit or git fetches online",
+                events.len(),
+                if events.len() == 1 { "" } else { "s" },
+            );
+        }
+        return Ok(());
+    }
+
     let m = if silent {
         MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
     } else {
@@ -1817,76 +2444,114 @@ pub async fn send_events(
     })?;
 
     #[allow(clippy::borrow_deref_ref)]
-    join_all(relays.iter().map(|&relay| async {
-        let relay_clean = remove_trailing_slash(relay);
-        let details = format!(
-            "{}{}{} {}",
-            if my_write_relays
-                .iter()
-                .any(|r| relay_clean.eq(&remove_trailing_slash(r)))
-            {
-                " [my-relay]"
-            } else {
-                ""
-            },
-            if repo_read_relays
-                .iter()
-                .any(|r| relay_clean.eq(&remove_trailing_slash(&r.to_string())))
-            {
-                " [repo-relay]"
-            } else {
-                ""
-            },
-            if fallback
-                .iter()
-                .any(|r| relay_clean.eq(&remove_trailing_slash(r)))
-            {
-                " [default]"
-            } else {
-                ""
-            },
-            relay_clean,
-        );
-        let pb = m.add(
-            ProgressBar::new(events.len() as u64)
-                .with_prefix(details.to_string())
-                .with_style(pb_style.clone()),
-        );
-        if animate {
-            pb.enable_steady_tick(Duration::from_millis(300));
-        }
-        pb.inc(0); // need to make pb display intially
-        let mut failed = false;
-        for event in &events {
-            match client
-                .send_event_to(git_repo_path, relay, event.clone())
-                .await
-            {
-                Ok(_) => pb.inc(1),
-                Err(e) => {
-                    pb.set_style(pb_after_style_failed.clone());
-                    pb.finish_with_message(
-                        console::style(format!(
-                            "error: {}",
-                            e.to_string()
-                                .replace("relay pool error:", "")
-                                .replace("event not published: ", "")
-                        ))
-                        .for_stderr()
-                        .red()
-                        .to_string(),
-                    );
-                    failed = true;
-                    break;
-                }
+    let delivery_reports: Vec<(String, Vec<DeliveryStatus>)> =
+        join_all(relays.iter().map(|&relay| async {
+            let relay_clean = remove_trailing_slash(relay);
+            let details = format!(
+                "{}{}{} {}",
+                if my_write_relays
+                    .iter()
+                    .any(|r| relay_clean.eq(&remove_trailing_slash(r)))
+                {
+                    " [my-relay]"
+                } else {
+                    ""
+                },
+                if repo_read_relays
+                    .iter()
+                    .any(|r| relay_clean.eq(&remove_trailing_slash(&r.to_string())))
+                {
+                    " [repo-relay]"
+                } else {
+                    ""
+                },
+                if fallback
+                    .iter()
+                    .any(|r| relay_clean.eq(&remove_trailing_slash(r)))
+                {
+                    " [default]"
+                } else {
+                    ""
+                },
+                relay_clean,
+            );
+            let pb = m.add(
+                ProgressBar::new(events.len() as u64)
+                    .with_prefix(details.to_string())
+                    .with_style(pb_style.clone()),
+            );
+            if animate {
+                pb.enable_steady_tick(Duration::from_millis(300));
+            }
+            pb.inc(0); // need to make pb display intially
+            let mut statuses = vec![];
+            for event in &events {
+                match client
+                    .send_event_to(git_repo_path, relay, event.clone())
+                    .await
+                {
+                    Ok(_) => {
+                        pb.inc(1);
+                        statuses.push(DeliveryStatus::Sent);
+                    }
+                    Err(e) => {
+                        let error = e
+                            .to_string()
+                            .replace("relay pool error:", "")
+                            .replace("event not published: ", "");
+                        pb.set_style(pb_after_style_failed.clone());
+                        pb.finish_with_message(
+                            console::style(format!("error: {error}"))
+                                .for_stderr()
+                                .red()
+                                .to_string(),
+                        );
+                        // remaining events are not attempted
+                        statuses.resize(
+                            events.len(),
+                            DeliveryStatus::Failed(error.trim().to_string()),
+                        );
+                        break;
+                    }
+                };
+            }
+            if statuses.iter().all(|s| s.eq(&DeliveryStatus::Sent)) {
+                pb.set_style(pb_after_style_succeeded.clone());
+                pb.finish_with_message("");
+            }
+            (relay.to_string(), statuses)
+        }))
+        .await;
+
+    if let Some(git_repo_path) = git_repo_path {
+        let mut undelivered = 0;
+        for (i, event) in events.iter().enumerate() {
+            let entry = OutboxEntry {
+                event: event.clone(),
+                relays: delivery_reports
+                    .iter()
+                    .filter(|(relay, _)| tracked_relays.contains(relay))
+                    .map(|(relay, statuses)| {
+                        (
+                            relay.clone(),
+                            statuses.get(i).cloned().unwrap_or(DeliveryStatus::Pending),
+                        )
+                    })
+                    .collect(),
             };
+            if !entry.is_delivered() {
+                save_outbox_entry(git_repo_path, &entry).await?;
+                undelivered += 1;
+            }
         }
-        if !failed {
-            pb.set_style(pb_after_style_succeeded.clone());
-            pb.finish_with_message("");
+        if undelivered > 0 && !silent {
+            println!(
+                "{undelivered} event{} failed to reach some repository or user relays and {} been saved to the outbox. retry with `ngit outbox retry`",
+                if undelivered == 1 { "" } else { "s" },
+                if undelivered == 1 { "has" } else { "have" },
+            );
         }
-    }))
-    .await;
+    }
     Ok(())
 }
 
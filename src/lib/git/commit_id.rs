@@ -0,0 +1,205 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{Context, Result, bail};
+use git2::Oid;
+
+/// hash function a repository uses to name its objects. set with
+/// `git init --object-format=<format>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ObjectFormat {
+    #[default]
+    Sha1,
+    Sha256,
+}
+
+impl ObjectFormat {
+    /// number of hex characters in an object id
+    pub fn hex_len(&self) -> usize {
+        match self {
+            ObjectFormat::Sha1 => 40,
+            ObjectFormat::Sha256 => 64,
+        }
+    }
+
+    fn from_hex_len(len: usize) -> Option<Self> {
+        match len {
+            40 => Some(ObjectFormat::Sha1),
+            64 => Some(ObjectFormat::Sha256),
+            _ => None,
+        }
+    }
+
+    /// the null object id, used by git to represent a missing ref
+    pub fn zero_id(&self) -> String {
+        "0".repeat(self.hex_len())
+    }
+}
+
+impl fmt::Display for ObjectFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjectFormat::Sha1 => write!(f, "sha1"),
+            ObjectFormat::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
+
+impl FromStr for ObjectFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "sha1" => Ok(ObjectFormat::Sha1),
+            "sha256" => Ok(ObjectFormat::Sha256),
+            _ => bail!("unsupported object format {s}"),
+        }
+    }
+}
+
+/// id of a commit in either object format. stored as bytes so ids compare
+/// equal regardless of the case they were written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CommitId {
+    Sha1([u8; 20]),
+    Sha256([u8; 32]),
+}
+
+impl CommitId {
+    pub fn object_format(&self) -> ObjectFormat {
+        match self {
+            CommitId::Sha1(_) => ObjectFormat::Sha1,
+            CommitId::Sha256(_) => ObjectFormat::Sha256,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            CommitId::Sha1(bytes) => bytes,
+            CommitId::Sha256(bytes) => bytes,
+        }
+    }
+
+    /// libgit2 only supports sha1 repositories so sha256 ids can't be
+    /// converted
+    pub fn to_oid(&self) -> Result<Oid> {
+        match self {
+            CommitId::Sha1(bytes) => {
+                Oid::from_bytes(bytes).context(format!("{self} failed to produce a valid Oid"))
+            }
+            CommitId::Sha256(_) => {
+                bail!("{self} is a sha256 commit id which libgit2 doesn't support yet")
+            }
+        }
+    }
+
+    /// whether `s` is a full length commit id in either object format
+    pub fn is_valid(s: &str) -> bool {
+        CommitId::from_str(s).is_ok()
+    }
+}
+
+impl From<Oid> for CommitId {
+    fn from(oid: Oid) -> Self {
+        let mut bytes = [0; 20];
+        bytes.copy_from_slice(&oid.as_bytes()[..20]);
+        CommitId::Sha1(bytes)
+    }
+}
+
+impl FromStr for CommitId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some(object_format) = ObjectFormat::from_hex_len(s.len()) else {
+            bail!("{s} is not a 40 character sha1 or 64 character sha256 commit id");
+        };
+        // from_str_radix accepts a leading '+' so each character is checked first
+        if !s.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!("{s} is not a hex commit id");
+        }
+        let bytes = (0..s.len())
+            .step_by(2)
+            .map(|i| {
+                s.get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .context(format!("{s} is not a hex commit id"))
+            })
+            .collect::<Result<Vec<u8>>>()?;
+        Ok(match object_format {
+            ObjectFormat::Sha1 => CommitId::Sha1(bytes.try_into().unwrap()),
+            ObjectFormat::Sha256 => CommitId::Sha256(bytes.try_into().unwrap()),
+        })
+    }
+}
+
+impl fmt::Display for CommitId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.as_bytes() {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA1: &str = "431b84edc0d2fa118d63faa3c2db9c73d630a5ae";
+    const SHA256: &str = "6f0c8c7c1a8b4b4e2e3a9d3f0d9c5e7b8a1f2e3d4c5b6a798897a6b5c4d3e2f1";
+
+    #[test]
+    fn sha1_round_trips() -> Result<()> {
+        let id = CommitId::from_str(SHA1)?;
+        assert_eq!(id.object_format(), ObjectFormat::Sha1);
+        assert_eq!(id.to_string(), SHA1);
+        Ok(())
+    }
+
+    #[test]
+    fn sha256_round_trips() -> Result<()> {
+        let id = CommitId::from_str(SHA256)?;
+        assert_eq!(id.object_format(), ObjectFormat::Sha256);
+        assert_eq!(id.to_string(), SHA256);
+        Ok(())
+    }
+
+    #[test]
+    fn uppercase_hex_equals_lowercase() -> Result<()> {
+        assert_eq!(
+            CommitId::from_str(&SHA1.to_uppercase())?,
+            CommitId::from_str(SHA1)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn other_lengths_rejected() {
+        assert!(!CommitId::is_valid(&SHA1[..39]));
+        assert!(!CommitId::is_valid(&format!("{SHA1}00")));
+        assert!(!CommitId::is_valid(&SHA256[..63]));
+    }
+
+    #[test]
+    fn non_hex_rejected() {
+        assert!(!CommitId::is_valid(&format!("xx{}", &SHA1[2..])));
+    }
+
+    #[test]
+    fn sign_prefixed_byte_rejected() {
+        assert!(!CommitId::is_valid(&format!("+a{}", &SHA1[2..])));
+    }
+
+    #[test]
+    fn oid_round_trips() -> Result<()> {
+        let oid = Oid::from_str(SHA1)?;
+        assert_eq!(CommitId::from(oid).to_oid()?, oid);
+        Ok(())
+    }
+
+    #[test]
+    fn sha256_to_oid_errors() -> Result<()> {
+        assert!(CommitId::from_str(SHA256)?.to_oid().is_err());
+        Ok(())
+    }
+}
@@ -1,7 +1,6 @@
 use anyhow::{Context, Result};
-use nostr_sdk::hashes::sha1::Hash as Sha1Hash;
 
-use super::{Repo, RepoActions};
+use super::{CommitId, Repo, RepoActions};
 
 /**
  * returns `(from_branch,to_branch,ahead,behind)`
@@ -10,7 +9,7 @@ pub fn identify_ahead_behind(
     git_repo: &Repo,
     from_branch: &Option<String>,
     to_branch: &Option<String>,
-) -> Result<(String, String, Vec<Sha1Hash>, Vec<Sha1Hash>)> {
+) -> Result<(String, String, Vec<CommitId>, Vec<CommitId>)> {
     let (from_branch, from_tip) = match from_branch {
         Some(name) => (
             name.to_string(),
@@ -69,7 +68,7 @@ mod tests {
     use test_utils::git::GitTestRepo;
 
     use super::*;
-    use crate::git::oid_to_sha1;
+    use crate::git::oid_to_commit_id;
 
     #[test]
     fn when_from_branch_doesnt_exist_return_error() -> Result<()> {
@@ -140,9 +139,9 @@ mod tests {
             identify_ahead_behind(&git_repo, &Some("feature".to_string()), &None)?;
 
         assert_eq!(from_branch, "feature");
-        assert_eq!(ahead, vec![oid_to_sha1(&head_oid)]);
+        assert_eq!(ahead, vec![oid_to_commit_id(&head_oid)]);
         assert_eq!(to_branch, "main");
-        assert_eq!(behind, vec![oid_to_sha1(&main_oid)]);
+        assert_eq!(behind, vec![oid_to_commit_id(&main_oid)]);
         Ok(())
     }
 
@@ -176,9 +175,9 @@ mod tests {
         )?;
 
         assert_eq!(from_branch, "feature");
-        assert_eq!(ahead, vec![oid_to_sha1(&feature_oid)]);
+        assert_eq!(ahead, vec![oid_to_commit_id(&feature_oid)]);
         assert_eq!(to_branch, "dev");
-        assert_eq!(behind, vec![oid_to_sha1(&dev_oid)]);
+        assert_eq!(behind, vec![oid_to_commit_id(&dev_oid)]);
 
         let (from_branch, to_branch, ahead, behind) =
             identify_ahead_behind(&git_repo, &Some("feature".to_string()), &None)?;
@@ -186,7 +185,10 @@ mod tests {
         assert_eq!(from_branch, "feature");
         assert_eq!(
             ahead,
-            vec![oid_to_sha1(&feature_oid), oid_to_sha1(&dev_oid_first)]
+            vec![
+                oid_to_commit_id(&feature_oid),
+                oid_to_commit_id(&dev_oid_first)
+            ]
         );
         assert_eq!(to_branch, "main");
         assert_eq!(behind, vec![]);
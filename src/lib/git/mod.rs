@@ -1,19 +1,20 @@
 use std::{
     env::current_dir,
+    io::Write,
     path::{Path, PathBuf},
 };
 
 use anyhow::{Context, Result, bail};
+pub use commit_id::{CommitId, ObjectFormat};
 use git2::{DiffOptions, Oid, Revwalk};
 pub use identify_ahead_behind::identify_ahead_behind;
-use nostr_sdk::{
-    Tags,
-    hashes::{Hash, sha1::Hash as Sha1Hash},
-};
+use nostr_sdk::Tags;
 
-use crate::git_events::{get_commit_id_from_patch, tag_value};
+use crate::git_events::{commit_msg_from_patch, get_commit_id_from_patch, tag_value};
+pub mod commit_id;
 pub mod identify_ahead_behind;
 pub mod nostr_url;
+pub mod remote;
 pub mod utils;
 
 pub struct Repo {
@@ -23,59 +24,72 @@ pub struct Repo {
 impl Repo {
     pub fn discover() -> Result<Self> {
         Ok(Self {
-            git_repo: git2::Repository::discover(current_dir()?)?,
+            git_repo: refuse_sha256(git2::Repository::discover(current_dir()?))?,
         })
     }
     pub fn from_path(path: &PathBuf) -> Result<Self> {
         Ok(Self {
-            git_repo: git2::Repository::open(path)?,
+            git_repo: refuse_sha256(git2::Repository::open(path))?,
         })
     }
 }
 
-// pub type CommitId = [u8; 7];
-// pub type Sha1 = [u8; 20];
+/// libgit2 can't read sha256 repositories yet. it either refuses to open them
+/// with an unhelpful error or, if it does open them, misreads their objects
+fn refuse_sha256(git_repo: Result<git2::Repository, git2::Error>) -> Result<git2::Repository> {
+    let is_sha256 = match &git_repo {
+        Ok(git_repo) => git_repo
+            .config()?
+            .get_string("extensions.objectformat")
+            .is_ok_and(|object_format| object_format.eq_ignore_ascii_case("sha256")),
+        Err(error) => error.message().contains("objectformat"),
+    };
+    if is_sha256 {
+        bail!("sha256 repositories are not supported by libgit2 yet");
+    }
+    Ok(git_repo?)
+}
 
 pub trait RepoActions {
     fn get_path(&self) -> Result<&Path>;
     fn get_origin_url(&self) -> Result<String>;
     fn get_remote_branch_names(&self) -> Result<Vec<String>>;
     fn get_local_branch_names(&self) -> Result<Vec<String>>;
-    fn get_origin_main_or_master_branch(&self) -> Result<(&str, Sha1Hash)>;
-    fn get_local_main_or_master_branch(&self) -> Result<(&str, Sha1Hash)>;
-    fn get_main_or_master_branch(&self) -> Result<(&str, Sha1Hash)>;
+    fn get_origin_main_or_master_branch(&self) -> Result<(&str, CommitId)>;
+    fn get_local_main_or_master_branch(&self) -> Result<(&str, CommitId)>;
+    fn get_main_or_master_branch(&self) -> Result<(&str, CommitId)>;
     fn get_checked_out_branch_name(&self) -> Result<String>;
-    fn get_tip_of_branch(&self, branch_name: &str) -> Result<Sha1Hash>;
-    fn get_commit_or_tip_of_reference(&self, reference: &str) -> Result<Sha1Hash>;
-    fn get_root_commit(&self) -> Result<Sha1Hash>;
+    fn get_tip_of_branch(&self, branch_name: &str) -> Result<CommitId>;
+    fn get_commit_or_tip_of_reference(&self, reference: &str) -> Result<CommitId>;
+    fn get_root_commit(&self) -> Result<CommitId>;
     fn does_commit_exist(&self, commit: &str) -> Result<bool>;
-    fn get_head_commit(&self) -> Result<Sha1Hash>;
-    fn get_commit_parent(&self, commit: &Sha1Hash) -> Result<Sha1Hash>;
-    fn get_commit_message(&self, commit: &Sha1Hash) -> Result<String>;
-    fn get_commit_message_summary(&self, commit: &Sha1Hash) -> Result<String>;
+    fn get_head_commit(&self) -> Result<CommitId>;
+    fn get_commit_parent(&self, commit: &CommitId) -> Result<CommitId>;
+    fn get_commit_message(&self, commit: &CommitId) -> Result<String>;
+    fn get_commit_message_summary(&self, commit: &CommitId) -> Result<String>;
     #[allow(clippy::doc_link_with_quotes)]
     /// returns vector ["name", "email", "unixtime", "offset"]
     /// eg ["joe bloggs", "joe@pm.me", "12176","-300"]
-    fn get_commit_author(&self, commit: &Sha1Hash) -> Result<Vec<String>>;
+    fn get_commit_author(&self, commit: &CommitId) -> Result<Vec<String>>;
     #[allow(clippy::doc_link_with_quotes)]
     /// returns vector ["name", "email", "unixtime", "offset"]
     /// eg ["joe bloggs", "joe@pm.me", "12176","-300"]
-    fn get_commit_comitter(&self, commit: &Sha1Hash) -> Result<Vec<String>>;
+    fn get_commit_comitter(&self, commit: &CommitId) -> Result<Vec<String>>;
     fn get_commits_ahead_behind(
         &self,
-        base_commit: &Sha1Hash,
-        latest_commit: &Sha1Hash,
-    ) -> Result<(Vec<Sha1Hash>, Vec<Sha1Hash>)>;
-    fn get_refs(&self, commit: &Sha1Hash) -> Result<Vec<String>>;
+        base_commit: &CommitId,
+        latest_commit: &CommitId,
+    ) -> Result<(Vec<CommitId>, Vec<CommitId>)>;
+    fn get_refs(&self, commit: &CommitId) -> Result<Vec<String>>;
     // including (un)staged changes and (un)tracked files
     fn has_outstanding_changes(&self) -> Result<bool>;
     fn make_patch_from_commit(
         &self,
-        commit: &Sha1Hash,
+        commit: &CommitId,
         series_count: &Option<(u64, u64)>,
     ) -> Result<String>;
-    fn extract_commit_pgp_signature(&self, commit: &Sha1Hash) -> Result<String>;
-    fn checkout(&self, ref_name: &str) -> Result<Sha1Hash>;
+    fn extract_commit_pgp_signature(&self, commit: &CommitId) -> Result<String>;
+    fn checkout(&self, ref_name: &str) -> Result<CommitId>;
     fn create_branch_at_commit(&self, branch_name: &str, commit: &str) -> Result<()>;
     fn apply_patch_chain(
         &self,
@@ -87,9 +101,29 @@ pub trait RepoActions {
         patch: &nostr::Event,
         parent_commit_id_override: Option<String>,
     ) -> Result<Oid>;
-    fn parse_starting_commits(&self, starting_commits: &str) -> Result<Vec<Sha1Hash>>;
-    fn ancestor_of(&self, decendant: &Sha1Hash, ancestor: &Sha1Hash) -> Result<bool>;
+    /// creates a commit from the patch on top of HEAD, like `git am`. when
+    /// `three_way` is set and the patch doesn't apply cleanly, falls back to a
+    /// three-way merge from the patch's parent commit.
+    fn apply_patch_to_head(&self, patch: &nostr::Event, three_way: bool) -> Result<CommitId>;
+    fn parse_starting_commits(&self, starting_commits: &str) -> Result<Vec<CommitId>>;
+    fn ancestor_of(&self, decendant: &CommitId, ancestor: &CommitId) -> Result<bool>;
+    /// replays commits on branch after `upstream` on top of `onto`. aborts and
+    /// errors if a conflict is encountered. returns new branch tip.
+    fn rebase_branch_onto(
+        &self,
+        branch_name: &str,
+        upstream: &CommitId,
+        onto: &CommitId,
+    ) -> Result<CommitId>;
+    /// packfile of objects reachable from `tips` but not from `exclude`. ids in
+    /// `exclude` that are missing locally are ignored. returns None if there
+    /// are no objects to pack.
+    fn create_pack(&self, tips: &[String], exclude: &[String]) -> Result<Option<Vec<u8>>>;
+    /// adds the objects in a packfile to the object database
+    fn import_pack(&self, pack: &[u8]) -> Result<()>;
     fn get_git_config_item(&self, item: &str, global: Option<bool>) -> Result<Option<String>>;
+    /// object format from `extensions.objectformat`. sha1 if unset
+    fn get_object_format(&self) -> Result<ObjectFormat>;
     fn save_git_config_item(&self, item: &str, value: &str, global: bool) -> Result<()>;
     fn remove_git_config_item(&self, item: &str, global: bool) -> Result<bool>;
 }
@@ -112,7 +146,7 @@ impl RepoActions for Repo {
             .to_string())
     }
 
-    fn get_origin_main_or_master_branch(&self) -> Result<(&str, Sha1Hash)> {
+    fn get_origin_main_or_master_branch(&self) -> Result<(&str, CommitId)> {
         let main_branch_name = {
             let remote_branches = self
                 .get_remote_branch_names()
@@ -135,7 +169,7 @@ impl RepoActions for Repo {
         Ok((main_branch_name, tip))
     }
 
-    fn get_local_main_or_master_branch(&self) -> Result<(&str, Sha1Hash)> {
+    fn get_local_main_or_master_branch(&self) -> Result<(&str, CommitId)> {
         let main_branch_name = {
             let local_branches = self
                 .get_local_branch_names()
@@ -158,7 +192,7 @@ impl RepoActions for Repo {
         Ok((main_branch_name, tip))
     }
 
-    fn get_main_or_master_branch(&self) -> Result<(&str, Sha1Hash)> {
+    fn get_main_or_master_branch(&self) -> Result<(&str, CommitId)> {
         if let Ok(main_tuple) = self
             .get_origin_main_or_master_branch()
             .context("the default branches (main or master) do not exist")
@@ -213,7 +247,7 @@ impl RepoActions for Repo {
             .to_string())
     }
 
-    fn get_tip_of_branch(&self, branch_name: &str) -> Result<Sha1Hash> {
+    fn get_tip_of_branch(&self, branch_name: &str) -> Result<CommitId> {
         let branch = if let Ok(branch) = self
             .git_repo
             .find_branch(branch_name, git2::BranchType::Local)
@@ -227,10 +261,12 @@ impl RepoActions for Repo {
                     "failed to find local or remote branch {branch_name}"
                 ))?
         };
-        Ok(oid_to_sha1(&branch.into_reference().peel_to_commit()?.id()))
+        Ok(oid_to_commit_id(
+            &branch.into_reference().peel_to_commit()?.id(),
+        ))
     }
 
-    fn get_commit_or_tip_of_reference(&self, sha1_or_reference: &str) -> Result<Sha1Hash> {
+    fn get_commit_or_tip_of_reference(&self, sha1_or_reference: &str) -> Result<CommitId> {
         let oid = {
             if let Ok(oid) = Oid::from_str(sha1_or_reference) {
                 self.git_repo.find_commit(oid)?;
@@ -242,18 +278,18 @@ impl RepoActions for Repo {
                     .id()
             }
         };
-        Ok(oid_to_sha1(&oid))
+        Ok(oid_to_commit_id(&oid))
     }
 
-    fn get_root_commit(&self) -> Result<Sha1Hash> {
+    fn get_root_commit(&self) -> Result<CommitId> {
         let mut revwalk = self
             .git_repo
             .revwalk()
             .context("revwalk should be created from git repo")?;
         revwalk
-            .push(sha1_to_oid(&self.get_head_commit()?)?)
+            .push(commit_id_to_oid(&self.get_head_commit()?)?)
             .context("revwalk should accept tip oid")?;
-        Ok(oid_to_sha1(
+        Ok(oid_to_commit_id(
             &revwalk
                 .last()
                 .context("revwalk from tip should be at least contain the tip oid")?
@@ -269,39 +305,39 @@ impl RepoActions for Repo {
         }
     }
 
-    fn get_head_commit(&self) -> Result<Sha1Hash> {
+    fn get_head_commit(&self) -> Result<CommitId> {
         let head = self
             .git_repo
             .head()
             .context("failed to get git repo head")?;
         let oid = head.peel_to_commit()?.id();
-        Ok(oid_to_sha1(&oid))
+        Ok(oid_to_commit_id(&oid))
     }
 
-    fn get_commit_parent(&self, commit: &Sha1Hash) -> Result<Sha1Hash> {
+    fn get_commit_parent(&self, commit: &CommitId) -> Result<CommitId> {
         let parent_oid = self
             .git_repo
-            .find_commit(sha1_to_oid(commit)?)
+            .find_commit(commit_id_to_oid(commit)?)
             .context(format!("could not find commit {commit}"))?
             .parent_id(0)
             .context(format!("could not find parent of commit {commit}"))?;
-        Ok(oid_to_sha1(&parent_oid))
+        Ok(oid_to_commit_id(&parent_oid))
     }
 
-    fn get_commit_message(&self, commit: &Sha1Hash) -> Result<String> {
+    fn get_commit_message(&self, commit: &CommitId) -> Result<String> {
         Ok(self
             .git_repo
-            .find_commit(sha1_to_oid(commit)?)
+            .find_commit(commit_id_to_oid(commit)?)
             .context(format!("could not find commit {commit}"))?
             .message_raw()
             .context("commit message has unusual characters in (not valid utf-8)")?
             .to_string())
     }
 
-    fn get_commit_message_summary(&self, commit: &Sha1Hash) -> Result<String> {
+    fn get_commit_message_summary(&self, commit: &CommitId) -> Result<String> {
         Ok(self
             .git_repo
-            .find_commit(sha1_to_oid(commit)?)
+            .find_commit(commit_id_to_oid(commit)?)
             .context(format!("could not find commit {commit}"))?
             .message_raw()
             .context("commit message has unusual characters in (not valid utf-8)")?
@@ -314,25 +350,25 @@ impl RepoActions for Repo {
             .to_string())
     }
 
-    fn get_commit_author(&self, commit: &Sha1Hash) -> Result<Vec<String>> {
+    fn get_commit_author(&self, commit: &CommitId) -> Result<Vec<String>> {
         let commit = self
             .git_repo
-            .find_commit(sha1_to_oid(commit)?)
+            .find_commit(commit_id_to_oid(commit)?)
             .context(format!("could not find commit {commit}"))?;
         let sig = commit.author();
         Ok(git_sig_to_tag_vec(&sig))
     }
 
-    fn get_commit_comitter(&self, commit: &Sha1Hash) -> Result<Vec<String>> {
+    fn get_commit_comitter(&self, commit: &CommitId) -> Result<Vec<String>> {
         let commit = self
             .git_repo
-            .find_commit(sha1_to_oid(commit)?)
+            .find_commit(commit_id_to_oid(commit)?)
             .context(format!("could not find commit {commit}"))?;
         let sig = commit.committer();
         Ok(git_sig_to_tag_vec(&sig))
     }
 
-    fn get_refs(&self, commit: &Sha1Hash) -> Result<Vec<String>> {
+    fn get_refs(&self, commit: &CommitId) -> Result<Vec<String>> {
         Ok(self
             .git_repo
             .references()?
@@ -353,15 +389,12 @@ impl RepoActions for Repo {
 
     fn make_patch_from_commit(
         &self,
-        commit: &Sha1Hash,
+        commit: &CommitId,
         series_count: &Option<(u64, u64)>,
     ) -> Result<String> {
         let c = self
             .git_repo
-            .find_commit(Oid::from_bytes(commit.as_byte_array()).context(format!(
-                "failed to convert commit_id format for {}",
-                &commit
-            ))?)
+            .find_commit(commit.to_oid()?)
             .context(format!("failed to find commit {}", &commit))?;
         let mut options = git2::EmailCreateOptions::default();
         if let Some((n, total)) = series_count {
@@ -375,11 +408,8 @@ impl RepoActions for Repo {
             .to_owned())
     }
 
-    fn extract_commit_pgp_signature(&self, commit: &Sha1Hash) -> Result<String> {
-        let oid = Oid::from_bytes(commit.as_byte_array()).context(format!(
-            "failed to convert commit_id format for {}",
-            &commit
-        ))?;
+    fn extract_commit_pgp_signature(&self, commit: &CommitId) -> Result<String> {
+        let oid = commit.to_oid()?;
 
         let (sign, _data) = self
             .git_repo
@@ -403,19 +433,19 @@ impl RepoActions for Repo {
 
     fn get_commits_ahead_behind(
         &self,
-        base_commit: &Sha1Hash,
-        latest_commit: &Sha1Hash,
-    ) -> Result<(Vec<Sha1Hash>, Vec<Sha1Hash>)> {
-        let mut ahead: Vec<Sha1Hash> = vec![];
-        let mut behind: Vec<Sha1Hash> = vec![];
+        base_commit: &CommitId,
+        latest_commit: &CommitId,
+    ) -> Result<(Vec<CommitId>, Vec<CommitId>)> {
+        let mut ahead: Vec<CommitId> = vec![];
+        let mut behind: Vec<CommitId> = vec![];
 
-        let get_revwalk = |commit: &Sha1Hash| -> Result<Revwalk> {
+        let get_revwalk = |commit: &CommitId| -> Result<Revwalk> {
             let mut revwalk = self
                 .git_repo
                 .revwalk()
                 .context("revwalk should be created from git repo")?;
             revwalk
-                .push(sha1_to_oid(commit)?)
+                .push(commit_id_to_oid(commit)?)
                 .context("revwalk should accept commit oid")?;
             Ok(revwalk)
         };
@@ -433,7 +463,7 @@ impl RepoActions for Repo {
                     true
                 } else {
                     // add commits not found in latest ancestory to 'behind' vector
-                    behind.push(oid_to_sha1(base_oid));
+                    behind.push(oid_to_commit_id(base_oid));
                     false
                 }
             }) {
@@ -455,14 +485,14 @@ impl RepoActions for Repo {
                     true
                 } else {
                     // add commits not found in base to 'ahead' vector
-                    ahead.push(oid_to_sha1(latest_oid));
+                    ahead.push(oid_to_commit_id(latest_oid));
                     false
                 }
             });
         Ok((ahead, behind))
     }
 
-    fn checkout(&self, ref_name: &str) -> Result<Sha1Hash> {
+    fn checkout(&self, ref_name: &str) -> Result<CommitId> {
         let (object, reference) = self.git_repo.revparse_ext(ref_name)?;
 
         self.git_repo.checkout_tree(&object, None)?;
@@ -475,7 +505,7 @@ impl RepoActions for Repo {
         }?;
         let oid = self.git_repo.head()?.peel_to_commit()?.id();
 
-        Ok(oid_to_sha1(&oid))
+        Ok(oid_to_commit_id(&oid))
     }
 
     fn create_branch_at_commit(&self, branch_name: &str, commit: &str) -> Result<()> {
@@ -514,7 +544,7 @@ impl RepoActions for Repo {
                 if let Ok(branch_tip) = branch_tip_result {
                     !branch_tip.to_string().eq(&commit_id)
                         && !self
-                            .ancestor_of(&branch_tip, &str_to_sha1(&commit_id).unwrap())
+                            .ancestor_of(&branch_tip, &str_to_commit_id(&commit_id).unwrap())
                             .unwrap()
                 } else {
                     true
@@ -660,7 +690,97 @@ impl RepoActions for Repo {
         self.git_repo.set_index(&mut existing_index)?;
         Ok(applied_oid)
     }
-    fn parse_starting_commits(&self, starting_commits: &str) -> Result<Vec<Sha1Hash>> {
+
+    fn apply_patch_to_head(&self, patch: &nostr::Event, three_way: bool) -> Result<CommitId> {
+        let head_commit = self
+            .git_repo
+            .head()
+            .context("failed to find HEAD")?
+            .peel_to_commit()?;
+        let head_tree = head_commit.tree()?;
+        let diff = git2::Diff::from_buffer(patch.content.as_bytes())
+            .context("event content is not a valid patch")?;
+
+        let mut index = match self.git_repo.apply_to_tree(&head_tree, &diff, None) {
+            Ok(index) => index,
+            Err(error) => {
+                if !three_way {
+                    return Err(error)
+                        .context("patch does not apply cleanly. try again with three-way merge");
+                }
+                let parent_commit_id = tag_value(patch, "parent-commit").context(
+                    "patch does not apply cleanly and cannot fallback to three-way merge as it doesnt specify a parent commit",
+                )?;
+                let parent_tree = self
+                    .git_repo
+                    .find_commit(Oid::from_str(&parent_commit_id)?)
+                    .context(format!(
+                        "patch does not apply cleanly and cannot fallback to three-way merge as parent commit {parent_commit_id} doesnt exist locally"
+                    ))?
+                    .tree()?;
+                let patched_parent_tree = self.git_repo.find_tree(
+                    self.git_repo
+                        .apply_to_tree(&parent_tree, &diff, None)
+                        .context("patch does not apply to its own parent commit")?
+                        .write_tree_to(&self.git_repo)?,
+                )?;
+                let merged = self
+                    .git_repo
+                    .merge_trees(&parent_tree, &head_tree, &patched_parent_tree, None)
+                    .context("failed to three-way merge patch")?;
+                if merged.has_conflicts() {
+                    let conflicted_paths: Vec<String> = merged
+                        .conflicts()?
+                        .filter_map(|c| {
+                            let c = c.ok()?;
+                            let entry = c.our.or(c.their).or(c.ancestor)?;
+                            Some(String::from_utf8_lossy(&entry.path).to_string())
+                        })
+                        .collect();
+                    bail!(
+                        "three-way merge of patch has conflicts in: {}",
+                        conflicted_paths.join(", ")
+                    );
+                }
+                merged
+            }
+        };
+        let tree = self
+            .git_repo
+            .find_tree(index.write_tree_to(&self.git_repo)?)?;
+
+        let author = if let Ok(author) = extract_sig_from_patch_tags(&patch.tags, "author") {
+            author
+        } else {
+            extract_sig_from_patch_content(patch)?
+        };
+        // like `git am`, the committer is the person applying the patch
+        let committer = self.git_repo.signature().unwrap_or(author.clone());
+        let message = commit_msg_from_patch(patch)?;
+        let message = message
+            .split("\n---\n")
+            .next()
+            .unwrap_or(message.as_str())
+            .trim()
+            .to_string();
+
+        let oid = self
+            .git_repo
+            .commit(
+                Some("HEAD"),
+                &author,
+                &committer,
+                &message,
+                &tree,
+                &[&head_commit],
+            )
+            .context("failed to create commit from patch")?;
+        self.git_repo
+            .checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .context("failed to update working tree to applied patch")?;
+        Ok(oid_to_commit_id(&oid))
+    }
+    fn parse_starting_commits(&self, starting_commits: &str) -> Result<Vec<CommitId>> {
         let revspec = self
             .git_repo
             .revparse(starting_commits)
@@ -668,7 +788,7 @@ impl RepoActions for Repo {
         if revspec.mode().is_no_single() {
             let (ahead, _) = self
                 .get_commits_ahead_behind(
-                    &oid_to_sha1(
+                    &oid_to_commit_id(
                         &revspec
                             .from()
                             .context("failed to get starting commit from specified value")?
@@ -683,13 +803,13 @@ impl RepoActions for Repo {
         } else if revspec.mode().is_range() {
             let (ahead, _) = self
                 .get_commits_ahead_behind(
-                    &oid_to_sha1(
+                    &oid_to_commit_id(
                         &revspec
                             .from()
                             .context("failed to get starting commit of range from specified value")?
                             .id(),
                     ),
-                    &oid_to_sha1(
+                    &oid_to_commit_id(
                         &revspec
                             .to()
                             .context("failed to get end of range commit from specified value")?
@@ -703,10 +823,10 @@ impl RepoActions for Repo {
         }
     }
 
-    fn ancestor_of(&self, decendant: &Sha1Hash, ancestor: &Sha1Hash) -> Result<bool> {
+    fn ancestor_of(&self, decendant: &CommitId, ancestor: &CommitId) -> Result<bool> {
         if let Ok(res) = self
             .git_repo
-            .graph_descendant_of(sha1_to_oid(decendant)?, sha1_to_oid(ancestor)?)
+            .graph_descendant_of(commit_id_to_oid(decendant)?, commit_id_to_oid(ancestor)?)
             .context("could not run graph_descendant_of in gitlib2")
         {
             Ok(res)
@@ -715,6 +835,110 @@ impl RepoActions for Repo {
         }
     }
 
+    fn rebase_branch_onto(
+        &self,
+        branch_name: &str,
+        upstream: &CommitId,
+        onto: &CommitId,
+    ) -> Result<CommitId> {
+        let branch = self
+            .git_repo
+            .find_reference(&format!("refs/heads/{branch_name}"))
+            .context(format!("failed to find branch {branch_name}"))?;
+        let branch = self.git_repo.reference_to_annotated_commit(&branch)?;
+        let upstream = self
+            .git_repo
+            .find_annotated_commit(commit_id_to_oid(upstream)?)?;
+        let onto = self
+            .git_repo
+            .find_annotated_commit(commit_id_to_oid(onto)?)?;
+        let signature = self
+            .git_repo
+            .signature()
+            .context("failed to get git user signature for rebased commits")?;
+
+        let mut rebase = self
+            .git_repo
+            .rebase(Some(&branch), Some(&upstream), Some(&onto), None)
+            .context("failed to start rebase")?;
+
+        while let Some(operation) = rebase.next() {
+            let operation = operation.context("failed to apply rebase operation")?;
+            if self.git_repo.index()?.has_conflicts() {
+                rebase.abort()?;
+                bail!(
+                    "rebase aborted due to conflict when applying commit {}",
+                    operation.id()
+                );
+            }
+            if let Err(error) = rebase.commit(None, &signature, None) {
+                // commit already exists in onto history
+                if error.code() != git2::ErrorCode::Applied {
+                    rebase.abort()?;
+                    return Err(error).context("failed to commit during rebase");
+                }
+            }
+        }
+        rebase
+            .finish(Some(&signature))
+            .context("failed to finish rebase")?;
+
+        self.get_tip_of_branch(branch_name)
+    }
+
+    fn create_pack(&self, tips: &[String], exclude: &[String]) -> Result<Option<Vec<u8>>> {
+        let mut revwalk = self.git_repo.revwalk()?;
+        let mut tag_objects = vec![];
+        for tip in tips {
+            let object = self
+                .git_repo
+                .find_object(Oid::from_str(tip)?, None)
+                .context(format!("failed to find object {tip}"))?;
+            if object.kind() == Some(git2::ObjectType::Tag) {
+                tag_objects.push(object.id());
+            }
+            revwalk.push(object.peel_to_commit()?.id())?;
+        }
+        for oid in exclude {
+            if let Ok(commit) = Oid::from_str(oid)
+                .and_then(|oid| self.git_repo.find_object(oid, None))
+                .and_then(|object| object.peel_to_commit())
+            {
+                revwalk.hide(commit.id())?;
+            }
+        }
+        let mut packbuilder = self.git_repo.packbuilder()?;
+        packbuilder.insert_walk(&mut revwalk)?;
+        for oid in tag_objects {
+            packbuilder.insert_object(oid, None)?;
+        }
+        if packbuilder.object_count() == 0 {
+            return Ok(None);
+        }
+        let mut buf = git2::Buf::new();
+        packbuilder.write_buf(&mut buf)?;
+        Ok(Some(buf.to_vec()))
+    }
+
+    fn import_pack(&self, pack: &[u8]) -> Result<()> {
+        let odb = self.git_repo.odb()?;
+        let mut writer = odb.packwriter()?;
+        writer
+            .write_all(pack)
+            .context("failed to write packfile to object database")?;
+        writer
+            .commit()
+            .context("failed to index packfile in object database")?;
+        Ok(())
+    }
+
+    fn get_object_format(&self) -> Result<ObjectFormat> {
+        match self.get_git_config_item("extensions.objectformat", Some(false))? {
+            Some(object_format) => object_format.to_lowercase().parse(),
+            None => Ok(ObjectFormat::Sha1),
+        }
+    }
+
     /// setting global to None will suppliment local config with global items
     /// not in local
     fn get_git_config_item(&self, item: &str, global: Option<bool>) -> Result<Option<String>> {
@@ -798,14 +1022,6 @@ impl RepoActions for Repo {
     }
 }
 
-fn oid_to_u8_20_bytes(oid: &Oid) -> [u8; 20] {
-    let b = oid.as_bytes();
-    [
-        b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7], b[8], b[9], b[10], b[11], b[12], b[13],
-        b[14], b[15], b[16], b[17], b[18], b[19],
-    ]
-}
-
 pub fn oid_to_shorthand_string(oid: Oid) -> Result<String> {
     let binding = oid.to_string();
     let b = binding.as_bytes();
@@ -822,20 +1038,18 @@ pub fn oid_to_shorthand_string(oid: Oid) -> Result<String> {
 //     .context("oid should contain 20 u8 btyes of utf8")
 // }
 
-// git2 Oid object to Sha1Hash
-pub fn oid_to_sha1(oid: &Oid) -> Sha1Hash {
-    Sha1Hash::from_byte_array(oid_to_u8_20_bytes(oid))
+// git2 Oid object to CommitId
+pub fn oid_to_commit_id(oid: &Oid) -> CommitId {
+    CommitId::from(*oid)
 }
 
-/// `Sha1Hash` to git2 `Oid` object
-pub fn sha1_to_oid(hash: &Sha1Hash) -> Result<Oid> {
-    Oid::from_bytes(hash.as_byte_array()).context("Sha1Hash bytes failed to produce a valid Oid")
+/// `CommitId` to git2 `Oid` object
+pub fn commit_id_to_oid(commit_id: &CommitId) -> Result<Oid> {
+    commit_id.to_oid()
 }
 
-pub fn str_to_sha1(s: &str) -> Result<Sha1Hash> {
-    Ok(oid_to_sha1(
-        &Oid::from_str(s).context("string is not a sha1 hash")?,
-    ))
+pub fn str_to_commit_id(s: &str) -> Result<CommitId> {
+    s.parse::<CommitId>().context("string is not a commit id")
 }
 
 fn git_sig_to_tag_vec(sig: &git2::Signature) -> Vec<String> {
@@ -847,6 +1061,25 @@ fn git_sig_to_tag_vec(sig: &git2::Signature) -> Vec<String> {
     ]
 }
 
+/// extracts author from the `From:` header of a patch created by `git
+/// format-patch`. the patch event timestamp is used as the author time.
+fn extract_sig_from_patch_content(patch: &nostr::Event) -> Result<git2::Signature<'static>> {
+    let from = patch
+        .content
+        .lines()
+        .find_map(|l| l.strip_prefix("From: "))
+        .context("patch has no author tag or 'From:' header")?;
+    let (name, email) = from
+        .rsplit_once('<')
+        .context("patch 'From:' header is incorrectly formatted")?;
+    git2::Signature::new(
+        name.trim().trim_matches('"'),
+        email.trim_end_matches('>').trim(),
+        &git2::Time::new(i64::try_from(patch.created_at.as_u64()).unwrap_or(0), 0),
+    )
+    .context("failed to create git signature")
+}
+
 fn extract_sig_from_patch_tags<'a>(tags: &'a Tags, tag_name: &str) -> Result<git2::Signature<'a>> {
     let v = tags
         .iter()
@@ -997,9 +1230,8 @@ mod tests {
         let git_repo = Repo::from_path(&test_repo.dir)?;
 
         assert_eq!(
-            // Sha1Hash::from_byte_array("bla".to_string().as_bytes()),
-            oid_to_sha1(&parent_oid),
-            git_repo.get_commit_parent(&oid_to_sha1(&child_oid))?,
+            oid_to_commit_id(&parent_oid),
+            git_repo.get_commit_parent(&oid_to_commit_id(&child_oid))?,
         );
         Ok(())
     }
@@ -1014,7 +1246,10 @@ mod tests {
 
             let git_repo = Repo::from_path(&test_repo.dir)?;
 
-            assert_eq!(message, git_repo.get_commit_message(&oid_to_sha1(&oid))?,);
+            assert_eq!(
+                message,
+                git_repo.get_commit_message(&oid_to_commit_id(&oid))?,
+            );
             Ok(())
         }
         #[test]
@@ -1050,7 +1285,7 @@ mod tests {
 
             assert_eq!(
                 summary,
-                git_repo.get_commit_message_summary(&oid_to_sha1(&oid))?,
+                git_repo.get_commit_message_summary(&oid_to_commit_id(&oid))?,
             );
             Ok(())
         }
@@ -1092,7 +1327,7 @@ mod tests {
             )?;
 
             let git_repo = Repo::from_path(&test_repo.dir)?;
-            git_repo.get_commit_author(&oid_to_sha1(&oid))
+            git_repo.get_commit_author(&oid_to_commit_id(&oid))
         }
 
         #[test]
@@ -1187,7 +1422,7 @@ mod tests {
             )?;
 
             let git_repo = Repo::from_path(&test_repo.dir)?;
-            git_repo.get_commit_comitter(&oid_to_sha1(&oid))
+            git_repo.get_commit_comitter(&oid_to_commit_id(&oid))
         }
 
         #[test]
@@ -1274,7 +1509,7 @@ mod tests {
                 libgit2 1.8.1\n\
                 \n\
                 ",
-                git_repo.make_patch_from_commit(&oid_to_sha1(&oid), &None)?,
+                git_repo.make_patch_from_commit(&oid_to_commit_id(&oid), &None)?,
             );
             Ok(())
         }
@@ -1310,7 +1545,7 @@ mod tests {
                 libgit2 1.8.1\n\
                 \n\
                 ",
-                git_repo.make_patch_from_commit(&oid_to_sha1(&oid), &Some((3, 5)))?,
+                git_repo.make_patch_from_commit(&oid_to_commit_id(&oid), &Some((3, 5)))?,
             );
             Ok(())
         }
@@ -1339,7 +1574,7 @@ mod tests {
             let git_repo = Repo::from_path(&test_repo.dir)?;
             let (name, commit_hash) = git_repo.get_main_or_master_branch()?;
             assert_eq!(name, "origin/main");
-            assert_eq!(commit_hash, oid_to_sha1(&main_origin_oid));
+            assert_eq!(commit_hash, oid_to_commit_id(&main_origin_oid));
             Ok(())
         }
 
@@ -1352,7 +1587,7 @@ mod tests {
                 let git_repo = Repo::from_path(&test_repo.dir)?;
                 let (name, commit_hash) = git_repo.get_main_or_master_branch()?;
                 assert_eq!(name, "main");
-                assert_eq!(commit_hash, oid_to_sha1(&main_oid));
+                assert_eq!(commit_hash, oid_to_commit_id(&main_oid));
                 Ok(())
             }
 
@@ -1368,8 +1603,8 @@ mod tests {
                 let git_repo = Repo::from_path(&test_repo.dir)?;
                 let (name, commit_hash) = git_repo.get_main_or_master_branch()?;
                 assert_eq!(name, "main");
-                assert_eq!(commit_hash, oid_to_sha1(&main_oid));
-                assert_ne!(commit_hash, oid_to_sha1(&feature_oid));
+                assert_eq!(commit_hash, oid_to_commit_id(&main_oid));
+                assert_ne!(commit_hash, oid_to_commit_id(&feature_oid));
                 Ok(())
             }
 
@@ -1385,8 +1620,8 @@ mod tests {
                 let git_repo = Repo::from_path(&test_repo.dir)?;
                 let (name, commit_hash) = git_repo.get_main_or_master_branch()?;
                 assert_eq!(name, "main");
-                assert_eq!(commit_hash, oid_to_sha1(&main_oid));
-                assert_ne!(commit_hash, oid_to_sha1(&master_oid));
+                assert_eq!(commit_hash, oid_to_commit_id(&main_oid));
+                assert_ne!(commit_hash, oid_to_commit_id(&master_oid));
                 Ok(())
             }
         }
@@ -1403,8 +1638,8 @@ mod tests {
             let git_repo = Repo::from_path(&test_repo.dir)?;
             let (name, commit_hash) = git_repo.get_main_or_master_branch()?;
             assert_eq!(name, "master");
-            assert_eq!(commit_hash, oid_to_sha1(&master_oid));
-            assert_ne!(commit_hash, oid_to_sha1(&feature_oid));
+            assert_eq!(commit_hash, oid_to_commit_id(&master_oid));
+            assert_ne!(commit_hash, oid_to_commit_id(&feature_oid));
             Ok(())
         }
         #[test]
@@ -1465,8 +1700,8 @@ mod tests {
 
                 let git_repo = Repo::from_path(&test_repo.dir)?;
 
-                let (ahead, behind) =
-                    git_repo.get_commits_ahead_behind(&oid_to_sha1(&oid), &oid_to_sha1(&oid))?;
+                let (ahead, behind) = git_repo
+                    .get_commits_ahead_behind(&oid_to_commit_id(&oid), &oid_to_commit_id(&oid))?;
                 assert_eq!(ahead, vec![]);
                 assert_eq!(behind, vec![]);
                 Ok(())
@@ -1489,13 +1724,16 @@ mod tests {
                 let git_repo = Repo::from_path(&test_repo.dir)?;
 
                 let (ahead, behind) = git_repo.get_commits_ahead_behind(
-                    &oid_to_sha1(&behind_2_oid),
-                    &oid_to_sha1(&feature_oid),
+                    &oid_to_commit_id(&behind_2_oid),
+                    &oid_to_commit_id(&feature_oid),
                 )?;
                 assert_eq!(ahead, vec![]);
                 assert_eq!(
                     behind,
-                    vec![oid_to_sha1(&behind_2_oid), oid_to_sha1(&behind_1_oid),],
+                    vec![
+                        oid_to_commit_id(&behind_2_oid),
+                        oid_to_commit_id(&behind_1_oid),
+                    ],
                 );
                 Ok(())
             }
@@ -1515,12 +1753,15 @@ mod tests {
                 let git_repo = Repo::from_path(&test_repo.dir)?;
 
                 let (ahead, behind) = git_repo.get_commits_ahead_behind(
-                    &oid_to_sha1(&main_oid),
-                    &oid_to_sha1(&ahead_2_oid),
+                    &oid_to_commit_id(&main_oid),
+                    &oid_to_commit_id(&ahead_2_oid),
                 )?;
                 assert_eq!(
                     ahead,
-                    vec![oid_to_sha1(&ahead_2_oid), oid_to_sha1(&ahead_1_oid),],
+                    vec![
+                        oid_to_commit_id(&ahead_2_oid),
+                        oid_to_commit_id(&ahead_1_oid),
+                    ],
                 );
                 assert_eq!(behind, vec![]);
                 Ok(())
@@ -1547,16 +1788,22 @@ mod tests {
                 let git_repo = Repo::from_path(&test_repo.dir)?;
 
                 let (ahead, behind) = git_repo.get_commits_ahead_behind(
-                    &oid_to_sha1(&behind_2_oid),
-                    &oid_to_sha1(&ahead_2_oid),
+                    &oid_to_commit_id(&behind_2_oid),
+                    &oid_to_commit_id(&ahead_2_oid),
                 )?;
                 assert_eq!(
                     ahead,
-                    vec![oid_to_sha1(&ahead_2_oid), oid_to_sha1(&ahead_1_oid)],
+                    vec![
+                        oid_to_commit_id(&ahead_2_oid),
+                        oid_to_commit_id(&ahead_1_oid)
+                    ],
                 );
                 assert_eq!(
                     behind,
-                    vec![oid_to_sha1(&behind_2_oid), oid_to_sha1(&behind_1_oid)],
+                    vec![
+                        oid_to_commit_id(&behind_2_oid),
+                        oid_to_commit_id(&behind_1_oid)
+                    ],
                 );
                 Ok(())
             }
@@ -1713,7 +1960,7 @@ mod tests {
             generate_patch_event(
                 &git_repo,
                 &git_repo.get_root_commit()?,
-                &oid_to_sha1(&original_oid),
+                &oid_to_commit_id(&original_oid),
                 Some(nostr::EventId::all_zeros()),
                 &TEST_KEY_1_SIGNER,
                 &RepoRef::try_from((generate_repo_ref_event(), None)).unwrap(),
@@ -1847,6 +2094,116 @@ mod tests {
         }
     }
 
+    mod apply_patch_to_head {
+        use test_utils::TEST_KEY_1_SIGNER;
+
+        use super::*;
+        use crate::{git_events::generate_patch_event, repo_ref::RepoRef};
+
+        static LINES: [&str; 6] = ["l1", "l2", "l3", "l4", "l5", "l6"];
+
+        fn write_lines(test_repo: &GitTestRepo, changes: &[(usize, &str)]) -> Result<()> {
+            let mut lines = LINES.to_vec();
+            for (i, line) in changes {
+                lines[*i] = line;
+            }
+            std::fs::write(test_repo.dir.join("t3.md"), lines.join("\n"))?;
+            Ok(())
+        }
+
+        // returns patch that changes the second line of t3.md
+        async fn generate_patch() -> Result<nostr::Event> {
+            let source_repo = GitTestRepo::default();
+            source_repo.populate()?;
+            write_lines(&source_repo, &[])?;
+            source_repo.stage_and_commit("add t3.md")?;
+            write_lines(&source_repo, &[(1, "l2 changed")])?;
+            let oid = source_repo.stage_and_commit("change t3.md line 2")?;
+            let git_repo = Repo::from_path(&source_repo.dir)?;
+            generate_patch_event(
+                &git_repo,
+                &git_repo.get_root_commit()?,
+                &oid_to_commit_id(&oid),
+                Some(nostr::EventId::all_zeros()),
+                &TEST_KEY_1_SIGNER,
+                &RepoRef::try_from((generate_repo_ref_event(), None)).unwrap(),
+                None,
+                None,
+                None,
+                &None,
+                &[],
+            )
+            .await
+        }
+
+        fn generate_target_repo(changes: &[(usize, &str)]) -> Result<GitTestRepo> {
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+            write_lines(&test_repo, &[])?;
+            test_repo.stage_and_commit("add t3.md")?;
+            write_lines(&test_repo, changes)?;
+            test_repo.stage_and_commit("change t3.md")?;
+            Ok(test_repo)
+        }
+
+        #[tokio::test]
+        async fn applies_on_top_of_head_when_patch_applies_cleanly() -> Result<()> {
+            let patch = generate_patch().await?;
+            let test_repo = generate_target_repo(&[(5, "l6 changed")])?;
+            let head_before = test_repo.git_repo.head()?.peel_to_commit()?.id();
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+
+            let commit = git_repo.apply_patch_to_head(&patch, false)?;
+
+            assert_eq!(git_repo.get_head_commit()?, commit);
+            assert_eq!(
+                git_repo.get_commit_parent(&commit)?,
+                oid_to_commit_id(&head_before)
+            );
+            assert_eq!(git_repo.get_commit_message(&commit)?, "change t3.md line 2");
+            assert_eq!(
+                std::fs::read_to_string(test_repo.dir.join("t3.md"))?,
+                "l1\nl2 changed\nl3\nl4\nl5\nl6 changed",
+            );
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn errors_when_patch_context_has_changed_and_no_three_way() -> Result<()> {
+            let patch = generate_patch().await?;
+            let test_repo = generate_target_repo(&[(3, "l4 changed")])?;
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+
+            assert!(git_repo.apply_patch_to_head(&patch, false).is_err());
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn three_way_merges_when_patch_context_has_changed() -> Result<()> {
+            let patch = generate_patch().await?;
+            let test_repo = generate_target_repo(&[(3, "l4 changed")])?;
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+
+            git_repo.apply_patch_to_head(&patch, true)?;
+
+            assert_eq!(
+                std::fs::read_to_string(test_repo.dir.join("t3.md"))?,
+                "l1\nl2 changed\nl3\nl4 changed\nl5\nl6",
+            );
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn three_way_errors_on_conflict() -> Result<()> {
+            let patch = generate_patch().await?;
+            let test_repo = generate_target_repo(&[(1, "l2 conflicting change")])?;
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+
+            assert!(git_repo.apply_patch_to_head(&patch, true).is_err());
+            Ok(())
+        }
+    }
+
     mod apply_patch_chain {
         use test_utils::TEST_KEY_1_SIGNER;
 
@@ -1867,7 +2224,11 @@ mod tests {
             let mut events = generate_cover_letter_and_patch_events(
                 Some(("test".to_string(), "test".to_string())),
                 &git_repo,
-                &[oid_to_sha1(&oid1), oid_to_sha1(&oid2), oid_to_sha1(&oid3)],
+                &[
+                    oid_to_commit_id(&oid1),
+                    oid_to_commit_id(&oid2),
+                    oid_to_commit_id(&oid3),
+                ],
                 &TEST_KEY_1_SIGNER,
                 &RepoRef::try_from((generate_repo_ref_event(), None)).unwrap(),
                 &None,
@@ -1938,7 +2299,7 @@ mod tests {
                     git_repo.apply_patch_chain(BRANCH_NAME, patch_events)?;
                     assert_eq!(
                         git_repo.get_tip_of_branch(BRANCH_NAME)?,
-                        oid_to_sha1(&original_repo.git_repo.head()?.peel_to_commit()?.id(),),
+                        oid_to_commit_id(&original_repo.git_repo.head()?.peel_to_commit()?.id(),),
                     );
                     Ok(())
                 }
@@ -2019,7 +2380,7 @@ mod tests {
                     git_repo.apply_patch_chain(BRANCH_NAME, patch_events)?;
                     assert_eq!(
                         git_repo.get_tip_of_branch(BRANCH_NAME)?,
-                        oid_to_sha1(&original_repo.git_repo.head()?.peel_to_commit()?.id(),),
+                        oid_to_commit_id(&original_repo.git_repo.head()?.peel_to_commit()?.id(),),
                     );
                     Ok(())
                 }
@@ -2076,7 +2437,7 @@ mod tests {
 
                     assert_eq!(
                         git_repo.get_tip_of_branch(BRANCH_NAME)?,
-                        oid_to_sha1(&original_repo.git_repo.head()?.peel_to_commit()?.id(),),
+                        oid_to_commit_id(&original_repo.git_repo.head()?.peel_to_commit()?.id(),),
                     );
                     Ok(())
                 }
@@ -2109,7 +2470,7 @@ mod tests {
 
                     assert_eq!(
                         git_repo.get_tip_of_branch(BRANCH_NAME)?,
-                        oid_to_sha1(&original_repo.git_repo.head()?.peel_to_commit()?.id(),),
+                        oid_to_commit_id(&original_repo.git_repo.head()?.peel_to_commit()?.id(),),
                     );
                     Ok(())
                 }
@@ -2214,7 +2575,9 @@ mod tests {
 
                 assert_eq!(
                     git_repo.parse_starting_commits("HEAD~1")?,
-                    vec![str_to_sha1("431b84edc0d2fa118d63faa3c2db9c73d630a5ae")?],
+                    vec![str_to_commit_id(
+                        "431b84edc0d2fa118d63faa3c2db9c73d630a5ae"
+                    )?],
                 );
                 Ok(())
             }
@@ -2227,7 +2590,9 @@ mod tests {
 
                 assert_eq!(
                     git_repo.parse_starting_commits("HEAD~1")?,
-                    vec![str_to_sha1("82ff2bcc9aa94d1bd8faee723d4c8cc190d6061c")?],
+                    vec![str_to_commit_id(
+                        "82ff2bcc9aa94d1bd8faee723d4c8cc190d6061c"
+                    )?],
                 );
                 Ok(())
             }
@@ -2245,8 +2610,8 @@ mod tests {
                 assert_eq!(
                     git_repo.parse_starting_commits("HEAD~2")?,
                     vec![
-                        str_to_sha1("431b84edc0d2fa118d63faa3c2db9c73d630a5ae")?,
-                        str_to_sha1("af474d8d271490e5c635aad337abdc050034b16a")?,
+                        str_to_commit_id("431b84edc0d2fa118d63faa3c2db9c73d630a5ae")?,
+                        str_to_commit_id("af474d8d271490e5c635aad337abdc050034b16a")?,
                     ],
                 );
                 Ok(())
@@ -2264,9 +2629,9 @@ mod tests {
                 assert_eq!(
                     git_repo.parse_starting_commits("HEAD~3")?,
                     vec![
-                        str_to_sha1("82ff2bcc9aa94d1bd8faee723d4c8cc190d6061c")?,
-                        str_to_sha1("a23e6b05aaeb7d1471b4a838b51f337d5644eeb0")?,
-                        str_to_sha1("7ab82116068982671a8111f27dc10599172334b2")?,
+                        str_to_commit_id("82ff2bcc9aa94d1bd8faee723d4c8cc190d6061c")?,
+                        str_to_commit_id("a23e6b05aaeb7d1471b4a838b51f337d5644eeb0")?,
+                        str_to_commit_id("7ab82116068982671a8111f27dc10599172334b2")?,
                     ],
                 );
                 Ok(())
@@ -2285,9 +2650,9 @@ mod tests {
                 assert_eq!(
                     git_repo.parse_starting_commits("af474d8..a23e6b0")?,
                     vec![
-                        str_to_sha1("a23e6b05aaeb7d1471b4a838b51f337d5644eeb0")?,
-                        str_to_sha1("7ab82116068982671a8111f27dc10599172334b2")?,
-                        str_to_sha1("431b84edc0d2fa118d63faa3c2db9c73d630a5ae")?,
+                        str_to_commit_id("a23e6b05aaeb7d1471b4a838b51f337d5644eeb0")?,
+                        str_to_commit_id("7ab82116068982671a8111f27dc10599172334b2")?,
+                        str_to_commit_id("431b84edc0d2fa118d63faa3c2db9c73d630a5ae")?,
                     ],
                 );
                 Ok(())
@@ -2314,8 +2679,8 @@ mod tests {
             let git_repo = Repo::from_path(&test_repo.dir)?;
 
             assert!(git_repo.ancestor_of(
-                &oid_to_sha1(&ahead_2_oid),
-                &oid_to_sha1(&from_main_in_feature_history)
+                &oid_to_commit_id(&ahead_2_oid),
+                &oid_to_commit_id(&from_main_in_feature_history)
             )?);
             Ok(())
         }
@@ -2336,7 +2701,10 @@ mod tests {
 
             let git_repo = Repo::from_path(&test_repo.dir)?;
 
-            assert!(git_repo.ancestor_of(&oid_to_sha1(&ahead_2_oid), &oid_to_sha1(&ahead_1_oid))?);
+            assert!(git_repo.ancestor_of(
+                &oid_to_commit_id(&ahead_2_oid),
+                &oid_to_commit_id(&ahead_1_oid)
+            )?);
             Ok(())
         }
 
@@ -2356,7 +2724,10 @@ mod tests {
 
             let git_repo = Repo::from_path(&test_repo.dir)?;
 
-            assert!(!git_repo.ancestor_of(&oid_to_sha1(&ahead_2_oid), &oid_to_sha1(&ahead_2_oid))?);
+            assert!(!git_repo.ancestor_of(
+                &oid_to_commit_id(&ahead_2_oid),
+                &oid_to_commit_id(&ahead_2_oid)
+            )?);
             Ok(())
         }
 
@@ -2381,10 +2752,89 @@ mod tests {
             let git_repo = Repo::from_path(&test_repo.dir)?;
 
             assert!(!git_repo.ancestor_of(
-                &oid_to_sha1(&ahead_2_oid),
-                &oid_to_sha1(&on_main_after_feature)
+                &oid_to_commit_id(&ahead_2_oid),
+                &oid_to_commit_id(&on_main_after_feature)
             )?);
             Ok(())
         }
     }
+
+    mod rebase_branch_onto {
+        use super::*;
+
+        #[test]
+        fn commits_after_upstream_are_replayed_on_onto() -> Result<()> {
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+            test_repo.git_repo.config()?.set_str("user.name", "joe")?;
+            test_repo
+                .git_repo
+                .config()?
+                .set_str("user.email", "joe@example.com")?;
+
+            // published proposal with 1 commit and a newer revision
+            test_repo.create_branch("feature")?;
+            test_repo.checkout("feature")?;
+            std::fs::write(test_repo.dir.join("t3.md"), "some content")?;
+            let old_revision_oid = test_repo.stage_and_commit("add t3.md")?;
+            // unpublished local commit
+            std::fs::write(test_repo.dir.join("t4.md"), "some content")?;
+            test_repo.stage_and_commit("add t4.md")?;
+
+            test_repo.checkout("main")?;
+            test_repo.create_branch("revision")?;
+            test_repo.checkout("revision")?;
+            std::fs::write(test_repo.dir.join("t3.md"), "some updated content")?;
+            let new_revision_oid = test_repo.stage_and_commit("add t3.md")?;
+            test_repo.checkout("feature")?;
+
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+
+            let tip = git_repo.rebase_branch_onto(
+                "feature",
+                &oid_to_commit_id(&old_revision_oid),
+                &oid_to_commit_id(&new_revision_oid),
+            )?;
+
+            assert_eq!(
+                git_repo.get_commit_parent(&tip)?,
+                oid_to_commit_id(&new_revision_oid)
+            );
+            assert_eq!(git_repo.get_commit_message(&tip)?, "add t4.md");
+            assert_eq!(git_repo.get_tip_of_branch("feature")?, tip);
+            Ok(())
+        }
+    }
+    mod create_pack {
+        use super::*;
+
+        #[test]
+        fn imported_pack_contains_commits_and_excluded_history_is_omitted() -> Result<()> {
+            let test_repo = GitTestRepo::default();
+            let first_oid = test_repo.populate()?;
+            std::fs::write(test_repo.dir.join("t3.md"), "some content")?;
+            let second_oid = test_repo.stage_and_commit("add t3.md")?;
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+
+            let full_pack = git_repo
+                .create_pack(&[second_oid.to_string()], &[])?
+                .context("pack should contain objects")?;
+            let incremental_pack = git_repo
+                .create_pack(&[second_oid.to_string()], &[first_oid.to_string()])?
+                .context("pack should contain objects")?;
+            assert!(incremental_pack.len() < full_pack.len());
+            assert!(
+                git_repo
+                    .create_pack(&[second_oid.to_string()], &[second_oid.to_string()])?
+                    .is_none()
+            );
+
+            let other_test_repo = GitTestRepo::default();
+            let other_git_repo = Repo::from_path(&other_test_repo.dir)?;
+            other_git_repo.import_pack(&full_pack)?;
+            assert!(other_git_repo.does_commit_exist(&second_oid.to_string())?);
+            assert!(other_git_repo.does_commit_exist(&first_oid.to_string())?);
+            Ok(())
+        }
+    }
 }
@@ -19,6 +19,9 @@ pub enum ServerProtocol {
     Unspecified,
     UnauthHttps, // used for read to enable non-interactive failures over https
     UnauthHttp,  // used for read to enable non-interactive failures over https
+    /// Blossom (BUD) blob server storing git packfiles. refs come from the
+    /// nostr state event
+    Blossom,
 }
 impl fmt::Display for ServerProtocol {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -32,6 +35,7 @@ impl fmt::Display for ServerProtocol {
             ServerProtocol::Unspecified => write!(f, "unsepcified"),
             ServerProtocol::UnauthHttps => write!(f, "https (unauthenticated)"),
             ServerProtocol::UnauthHttp => write!(f, "http (unauthenticated)"),
+            ServerProtocol::Blossom => write!(f, "blossom"),
         }
     }
 }
@@ -50,6 +54,7 @@ impl FromStr for ServerProtocol {
             "filesystem" => Ok(ServerProtocol::Filesystem),
             "http (unauthenticated)" => Ok(ServerProtocol::UnauthHttp),
             "https (unauthenticated)" => Ok(ServerProtocol::UnauthHttps),
+            "blossom" => Ok(ServerProtocol::Blossom),
             _ => bail!("not listed as a server protocol"),
         }
     }
@@ -354,6 +359,8 @@ impl FromStr for CloneUrl {
             "git" => ServerProtocol::Git,
             "ftp" => ServerProtocol::Ftp,
             "unspecified" => ServerProtocol::Unspecified,
+            // blossom+http is for servers without tls, eg. when run locally
+            "blossom" | "blossom+http" => ServerProtocol::Blossom,
             _ => return Err(anyhow::anyhow!("Unsupported protocol: {}", url.scheme())),
         };
 
@@ -421,6 +428,13 @@ impl CloneUrl {
                 ServerProtocol::Ftp => "ftp://",
                 ServerProtocol::Ssh => "ssh://",
                 ServerProtocol::Unspecified => "https://",
+                ServerProtocol::Blossom if self.protocol != ServerProtocol::Blossom => bail!(
+                    "Cannot convert to Blossom protocol from {:?}",
+                    self.protocol
+                ),
+                ServerProtocol::Blossom if self.original_string.starts_with("blossom+http://") =>
+                    "http://",
+                ServerProtocol::Blossom => "https://",
                 _ => bail!("unsupported protocol"),
             },
             &self.host
@@ -625,6 +639,26 @@ mod tests {
             assert_eq!(result, "github.com/user/repo.git");
         }
 
+        #[test]
+        fn format_as_blossom_uses_https_base_url() {
+            let result = "blossom://blossom.example.com"
+                .parse::<CloneUrl>()
+                .unwrap()
+                .format_as(&ServerProtocol::Blossom, &None)
+                .unwrap();
+            assert_eq!(result, "https://blossom.example.com");
+        }
+
+        #[test]
+        fn format_as_blossom_from_blossom_http_uses_http_base_url() {
+            let result = "blossom+http://localhost:3000"
+                .parse::<CloneUrl>()
+                .unwrap()
+                .format_as(&ServerProtocol::Blossom, &None)
+                .unwrap();
+            assert_eq!(result, "http://localhost:3000");
+        }
+
         mod input_all_formats_to_from_str_and_correctly_format_as_https {
             use super::*;
 
@@ -0,0 +1,339 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{Mutex, PoisonError},
+};
+
+use anyhow::{Result, anyhow};
+use auth_git2::GitAuthenticator;
+
+use super::{
+    Repo, RepoActions,
+    nostr_url::{CloneUrl, NostrUrlDecoded, ServerProtocol},
+};
+use crate::cli_interactor::LineOutput;
+
+/// held while asking for git server credentials. git servers are contacted
+/// concurrently so without it prompts would interleave
+static CREDENTIALS_PROMPT: Mutex<()> = Mutex::new(());
+
+/// `auth.credentials` that waits for any other git server's credential prompt
+/// to be answered first
+pub fn serialized_credentials<'a>(
+    auth: &'a GitAuthenticator,
+    git_config: &'a git2::Config,
+) -> impl 'a + FnMut(&str, Option<&str>, git2::CredentialType) -> Result<git2::Cred, git2::Error> {
+    let mut credentials = auth.credentials(git_config);
+    move |url, username, allowed| {
+        let _prompt = CREDENTIALS_PROMPT
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        credentials(url, username, allowed)
+    }
+}
+
+pub fn join_with_and<T: ToString>(items: &[T]) -> String {
+    match items.len() {
+        0 => String::new(),
+        1 => items[0].to_string(),
+        _ => {
+            let last_item = items.last().unwrap().to_string();
+            let rest = &items[..items.len() - 1];
+            format!(
+                "{} and {}",
+                rest.iter()
+                    .map(std::string::ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                last_item
+            )
+        }
+    }
+}
+
+/// get an ordered vector of server protocols to attempt
+pub fn get_read_protocols_to_try(
+    git_repo: &Repo,
+    server_url: &CloneUrl,
+    decoded_nostr_url: &NostrUrlDecoded,
+) -> Vec<ServerProtocol> {
+    if server_url.protocol() == ServerProtocol::Filesystem {
+        vec![(ServerProtocol::Filesystem)]
+    } else if let Some(protocol) = &decoded_nostr_url.protocol {
+        vec![protocol.clone()]
+    } else {
+        let mut list = if server_url.protocol() == ServerProtocol::Http {
+            vec![
+                ServerProtocol::UnauthHttp,
+                ServerProtocol::Ssh,
+                // note: list and fetch stop here if ssh was authenticated
+                ServerProtocol::Http,
+            ]
+        } else if server_url.protocol() == ServerProtocol::Ftp {
+            vec![ServerProtocol::Ftp, ServerProtocol::Ssh]
+        } else {
+            vec![
+                ServerProtocol::UnauthHttps,
+                ServerProtocol::Ssh,
+                // note: list and fetch stop here if ssh was authenticated
+                ServerProtocol::Https,
+            ]
+        };
+        if let Some(protocol) = get_protocol_preference(git_repo, server_url, &Direction::Fetch) {
+            if let Some(pos) = list.iter().position(|p| *p == protocol) {
+                list.remove(pos);
+                list.insert(0, protocol);
+            }
+        }
+        list
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Direction {
+    Push,
+    Fetch,
+}
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Direction::Push => write!(f, "push"),
+            Direction::Fetch => write!(f, "fetch"),
+        }
+    }
+}
+
+pub fn get_protocol_preference(
+    git_repo: &Repo,
+    server_url: &CloneUrl,
+    direction: &Direction,
+) -> Option<ServerProtocol> {
+    let server_short_name = server_url.short_name();
+    if let Ok(Some(list)) =
+        git_repo.get_git_config_item(format!("nostr.protocol-{direction}").as_str(), Some(false))
+    {
+        for item in list.split(';') {
+            let pair = item.split(',').collect::<Vec<&str>>();
+            if let Some(url) = pair.get(1) {
+                if *url == server_short_name {
+                    if let Some(protocol) = pair.first() {
+                        if let Ok(protocol) = ServerProtocol::from_str(protocol) {
+                            return Some(protocol);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+pub fn set_protocol_preference(
+    git_repo: &Repo,
+    protocol: &ServerProtocol,
+    server_url: &CloneUrl,
+    direction: &Direction,
+) -> Result<()> {
+    let server_short_name = server_url.short_name();
+    let mut new = String::new();
+    if let Some(list) =
+        git_repo.get_git_config_item(format!("nostr.protocol-{direction}").as_str(), Some(false))?
+    {
+        for item in list.split(';') {
+            let pair = item.split(',').collect::<Vec<&str>>();
+            if let Some(url) = pair.get(1) {
+                if *url != server_short_name && !item.is_empty() {
+                    new.push_str(format!("{item};").as_str());
+                }
+            }
+        }
+    }
+    new.push_str(format!("{protocol},{server_short_name};").as_str());
+
+    git_repo.save_git_config_item(
+        format!("nostr.protocol-{direction}").as_str(),
+        new.as_str(),
+        false,
+    )
+}
+
+/// to understand whether to try over another protocol
+pub fn fetch_or_list_error_is_not_authentication_failure(error: &anyhow::Error) -> bool {
+    !error_might_be_authentication_related(error)
+}
+
+pub fn error_might_be_authentication_related(error: &anyhow::Error) -> bool {
+    let error_str = error.to_string();
+    for s in [
+        "no ssh keys found",
+        "invalid or unknown remote ssh hostkey",
+        "authentication",
+        "Permission",
+        "permission",
+        "not found",
+    ] {
+        if error_str.contains(s) {
+            return true;
+        }
+    }
+    false
+}
+
+pub fn list_from_remote(
+    term: &dyn LineOutput,
+    git_repo: &Repo,
+    git_server_url: &str,
+    decoded_nostr_url: &NostrUrlDecoded, // Add this parameter
+) -> Result<HashMap<String, String>> {
+    let server_url = git_server_url.parse::<CloneUrl>()?;
+    let protocols_to_attempt = get_read_protocols_to_try(git_repo, &server_url, decoded_nostr_url);
+
+    let mut failed_protocols = vec![];
+    let mut remote_state: Option<HashMap<String, String>> = None;
+
+    for protocol in &protocols_to_attempt {
+        term.write_line(
+            format!(
+                "fetching {} ref list over {protocol}...",
+                server_url.short_name(),
+            )
+            .as_str(),
+        )?;
+
+        let formatted_url = server_url.format_as(protocol, &decoded_nostr_url.user)?;
+        let res = list_from_remote_url(
+            git_repo,
+            &formatted_url,
+            [ServerProtocol::UnauthHttps, ServerProtocol::UnauthHttp].contains(protocol),
+            term,
+        );
+
+        match res {
+            Ok(state) => {
+                remote_state = Some(state);
+                term.clear_last_lines(1)?;
+                if !failed_protocols.is_empty() {
+                    term.write_line(
+                        format!(
+                            "list: succeeded over {protocol} from {}",
+                            server_url.short_name(),
+                        )
+                        .as_str(),
+                    )?;
+                    let _ =
+                        set_protocol_preference(git_repo, protocol, &server_url, &Direction::Fetch);
+                }
+                break;
+            }
+            Err(error) => {
+                term.clear_last_lines(1)?;
+                term.write_line(
+                    format!("list: {formatted_url} failed over {protocol}: {error}").as_str(),
+                )?;
+                failed_protocols.push(protocol);
+                if protocol == &ServerProtocol::Ssh
+                    && fetch_or_list_error_is_not_authentication_failure(&error)
+                {
+                    // authenticated by failed to complete request
+                    break;
+                }
+            }
+        }
+    }
+    if let Some(remote_state) = remote_state {
+        if failed_protocols.is_empty() {
+            term.clear_last_lines(1)?;
+        }
+        Ok(remote_state)
+    } else {
+        let error = anyhow!(
+            "{} failed over {}{}",
+            server_url.short_name(),
+            join_with_and(&failed_protocols),
+            if decoded_nostr_url.protocol.is_some() {
+                " and nostr url contains protocol override so no other protocols were attempted"
+            } else {
+                ""
+            },
+        );
+        term.write_line(format!("list: {error}").as_str())?;
+        Err(error)
+    }
+}
+
+fn list_from_remote_url(
+    git_repo: &Repo,
+    git_server_remote_url: &str,
+    dont_authenticate: bool,
+    term: &dyn LineOutput,
+) -> Result<HashMap<String, String>> {
+    let git_config = git_repo.git_repo.config()?;
+
+    let mut git_server_remote = git_repo.git_repo.remote_anonymous(git_server_remote_url)?;
+    // authentication may be required
+    let auth = GitAuthenticator::default();
+    let mut remote_callbacks = git2::RemoteCallbacks::new();
+    if !dont_authenticate {
+        remote_callbacks.credentials(serialized_credentials(&auth, &git_config));
+    }
+    term.write_line("list: connecting...")?;
+    git_server_remote.connect_auth(git2::Direction::Fetch, Some(remote_callbacks), None)?;
+    term.clear_last_lines(1)?;
+    let mut state = HashMap::new();
+    for head in git_server_remote.list()? {
+        if let Some(symbolic_reference) = head.symref_target() {
+            state.insert(
+                head.name().to_string(),
+                format!("ref: {symbolic_reference}"),
+            );
+        } else {
+            state.insert(head.name().to_string(), head.oid().to_string());
+        }
+    }
+    git_server_remote.disconnect()?;
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    mod join_with_and {
+        use super::*;
+        #[test]
+        fn test_empty() {
+            let items: Vec<&str> = vec![];
+            assert_eq!(join_with_and(&items), "");
+        }
+
+        #[test]
+        fn test_single_item() {
+            let items = vec!["apple"];
+            assert_eq!(join_with_and(&items), "apple");
+        }
+
+        #[test]
+        fn test_two_items() {
+            let items = vec!["apple", "banana"];
+            assert_eq!(join_with_and(&items), "apple and banana");
+        }
+
+        #[test]
+        fn test_three_items() {
+            let items = vec!["apple", "banana", "cherry"];
+            assert_eq!(join_with_and(&items), "apple, banana and cherry");
+        }
+
+        #[test]
+        fn test_four_items() {
+            let items = vec!["apple", "banana", "cherry", "date"];
+            assert_eq!(join_with_and(&items), "apple, banana, cherry and date");
+        }
+
+        #[test]
+        fn test_multiple_items() {
+            let items = vec!["one", "two", "three", "four", "five"];
+            assert_eq!(join_with_and(&items), "one, two, three, four and five");
+        }
+    }
+}
@@ -1,16 +1,16 @@
-use std::{str::FromStr, sync::Arc};
+use std::{path::Path, str::FromStr, sync::Arc};
 
 use anyhow::{Context, Result, bail};
 use nostr::nips::{nip01::Coordinate, nip10::Marker, nip19::Nip19};
 use nostr_sdk::{
-    Event, EventBuilder, EventId, FromBech32, Kind, NostrSigner, PublicKey, Tag, TagKind,
-    TagStandard, hashes::sha1::Hash as Sha1Hash,
+    Alphabet, Event, EventBuilder, EventId, FromBech32, Kind, NostrSigner, PublicKey,
+    SingleLetterTag, Tag, TagKind, TagStandard,
 };
 
 use crate::{
     cli_interactor::{Interactor, InteractorPrompt, PromptInputParms},
-    client::sign_event,
-    git::{Repo, RepoActions},
+    client::{get_events_from_local_cache, sign_event},
+    git::{CommitId, Repo, RepoActions},
     repo_ref::RepoRef,
 };
 
@@ -29,8 +29,13 @@ pub fn get_commit_id_from_patch(event: &Event) -> Result<String> {
 
     if value.is_ok() {
         value
-    } else if event.content.starts_with("From ") && event.content.len().gt(&45) {
-        Ok(event.content[5..45].to_string())
+    } else if let Some(commit_id) = event
+        .content
+        .strip_prefix("From ")
+        .and_then(|content| content.split_whitespace().next())
+        .filter(|commit_id| CommitId::is_valid(commit_id))
+    {
+        Ok(commit_id.to_string())
     } else {
         bail!("event is not a patch")
     }
@@ -86,8 +91,8 @@ pub fn patch_supports_commit_ids(event: &Event) -> bool {
 #[allow(clippy::too_many_lines)]
 pub async fn generate_patch_event(
     git_repo: &Repo,
-    root_commit: &Sha1Hash,
-    commit: &Sha1Hash,
+    root_commit: &CommitId,
+    commit: &CommitId,
     thread_event_id: Option<nostr::EventId>,
     signer: &Arc<dyn NostrSigner>,
     repo_ref: &RepoRef,
@@ -253,6 +258,60 @@ pub async fn generate_patch_event(
     .context("failed to sign event")
 }
 
+/// resolves `--in-reply-to` references into the root of the proposal being
+/// revised, if the first reference is one, and mention tags for the rest
+pub async fn get_root_proposal_id_and_mentions_from_in_reply_to(
+    git_repo_path: &Path,
+    in_reply_to: &[String],
+) -> Result<(Option<String>, Vec<nostr::Tag>)> {
+    let root_proposal_id = if let Some(first) = in_reply_to.first() {
+        match event_tag_from_nip19_or_hex(first, "in-reply-to", Marker::Root, true, false)?
+            .as_standardized()
+        {
+            Some(nostr_sdk::TagStandard::Event {
+                event_id,
+                relay_url: _,
+                marker: _,
+                public_key: _,
+                uppercase: false,
+            }) => {
+                let events = get_events_from_local_cache(
+                    git_repo_path,
+                    vec![nostr::Filter::new().id(*event_id)],
+                )
+                .await?;
+
+                if let Some(first) = events.iter().find(|e| e.id.eq(event_id)) {
+                    if event_is_patch_set_root(first) {
+                        Some(event_id.to_string())
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    } else {
+        return Ok((None, vec![]));
+    };
+
+    let mut mention_tags = vec![];
+    for (i, reply_to) in in_reply_to.iter().enumerate() {
+        if i.ne(&0) || root_proposal_id.is_none() {
+            mention_tags.push(
+                event_tag_from_nip19_or_hex(reply_to, "in-reply-to", Marker::Mention, true, false)
+                    .context(format!(
+                        "{reply_to} in 'in-reply-to' not a valid nostr reference"
+                    ))?,
+            );
+        }
+    }
+
+    Ok((root_proposal_id, mention_tags))
+}
+
 pub fn event_tag_from_nip19_or_hex(
     reference: &str,
     reference_name: &str,
@@ -330,7 +389,7 @@ pub fn event_tag_from_nip19_or_hex(
 pub async fn generate_cover_letter_and_patch_events(
     cover_letter_title_description: Option<(String, String)>,
     git_repo: &Repo,
-    commits: &[Sha1Hash],
+    commits: &[CommitId],
     signer: &Arc<dyn NostrSigner>,
     repo_ref: &RepoRef,
     root_proposal_id: &Option<String>,
@@ -645,6 +704,258 @@ pub fn is_event_proposal_root_for_branch(
     }) && !event_is_revision_root(e))
 }
 
+pub fn event_is_issue(event: &Event) -> bool {
+    event.kind.eq(&Kind::GitIssue)
+}
+
+pub fn get_issue_title(event: &Event) -> String {
+    if let Ok(subject) = tag_value(event, "subject") {
+        subject
+    } else {
+        event
+            .content
+            .split('\n')
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
+pub fn get_issue_labels(event: &Event) -> Vec<String> {
+    event.tags.hashtags().map(ToString::to_string).collect()
+}
+
+pub async fn generate_issue_event(
+    signer: &Arc<dyn NostrSigner>,
+    repo_ref: &RepoRef,
+    title: &str,
+    description: &str,
+    labels: &[String],
+) -> Result<Event> {
+    sign_event(
+        EventBuilder::new(Kind::GitIssue, description).tags(
+            [
+                repo_ref
+                    .coordinates()
+                    .iter()
+                    .map(|c| {
+                        Tag::from_standardized(TagStandard::Coordinate {
+                            coordinate: c.coordinate.clone(),
+                            relay_url: repo_ref.relays.first().cloned(),
+                            uppercase: false,
+                        })
+                    })
+                    .collect::<Vec<Tag>>(),
+                vec![
+                    Tag::from_standardized(TagStandard::Reference(repo_ref.root_commit.clone())),
+                    Tag::custom(
+                        TagKind::Custom(std::borrow::Cow::Borrowed("subject")),
+                        vec![title.to_string()],
+                    ),
+                    Tag::custom(
+                        TagKind::Custom(std::borrow::Cow::Borrowed("alt")),
+                        vec![format!("git issue: {title}")],
+                    ),
+                ],
+                labels.iter().map(Tag::hashtag).collect(),
+                repo_ref
+                    .maintainers
+                    .iter()
+                    .map(|pk| Tag::public_key(*pk))
+                    .collect(),
+            ]
+            .concat(),
+        ),
+        signer,
+        "issue".to_string(),
+    )
+    .await
+}
+
+/// NIP-22 comment on a proposal, patch or issue. uppercase tags reference the
+/// thread root and lowercase tags the event being replied to, which is the
+/// root for top level comments.
+pub async fn generate_comment_event(
+    signer: &Arc<dyn NostrSigner>,
+    repo_ref: &RepoRef,
+    root: &Event,
+    parent: Option<&Event>,
+    content: &str,
+) -> Result<Event> {
+    let relay_hint = repo_ref
+        .relays
+        .first()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let parent = parent.unwrap_or(root);
+    sign_event(
+        EventBuilder::new(Kind::Comment, content).tags(
+            [
+                vec![
+                    Tag::custom(
+                        TagKind::SingleLetter(SingleLetterTag::uppercase(Alphabet::E)),
+                        vec![root.id.to_hex(), relay_hint.clone(), root.pubkey.to_hex()],
+                    ),
+                    Tag::custom(
+                        TagKind::SingleLetter(SingleLetterTag::uppercase(Alphabet::K)),
+                        vec![root.kind.as_u16().to_string()],
+                    ),
+                    Tag::custom(
+                        TagKind::SingleLetter(SingleLetterTag::uppercase(Alphabet::P)),
+                        vec![root.pubkey.to_hex()],
+                    ),
+                    Tag::custom(
+                        TagKind::SingleLetter(SingleLetterTag::lowercase(Alphabet::E)),
+                        vec![parent.id.to_hex(), relay_hint, parent.pubkey.to_hex()],
+                    ),
+                    Tag::custom(
+                        TagKind::SingleLetter(SingleLetterTag::lowercase(Alphabet::K)),
+                        vec![parent.kind.as_u16().to_string()],
+                    ),
+                    Tag::public_key(parent.pubkey),
+                ],
+                repo_ref
+                    .coordinates()
+                    .iter()
+                    .map(|c| {
+                        Tag::from_standardized(TagStandard::Coordinate {
+                            coordinate: c.coordinate.clone(),
+                            relay_url: repo_ref.relays.first().cloned(),
+                            uppercase: false,
+                        })
+                    })
+                    .collect::<Vec<Tag>>(),
+            ]
+            .concat(),
+        ),
+        signer,
+        "comment".to_string(),
+    )
+    .await
+}
+
+pub fn get_comment_root_id(event: &Event) -> Result<EventId> {
+    Ok(EventId::parse(
+        event
+            .tags
+            .iter()
+            .find(|t| t.as_slice().len() > 1 && t.as_slice()[0].eq("E"))
+            .context("comment has no uppercase E tag referencing the thread root")?
+            .as_slice()[1]
+            .as_str(),
+    )?)
+}
+
+pub fn get_comment_parent_id(event: &Event) -> Result<EventId> {
+    Ok(EventId::parse(
+        event
+            .tags
+            .iter()
+            .find(|t| t.as_slice().len() > 1 && t.as_slice()[0].eq("e"))
+            .context("comment has no e tag referencing its parent")?
+            .as_slice()[1]
+            .as_str(),
+    )?)
+}
+
+/// status event for a proposal or issue. `extra_tags` is used for the
+/// applied / merged commit tags.
+pub async fn generate_status_event(
+    signer: &Arc<dyn NostrSigner>,
+    repo_ref: &RepoRef,
+    status: Kind,
+    root: &Event,
+    extra_tags: Vec<Tag>,
+) -> Result<Event> {
+    let mut public_keys = repo_ref.maintainers.clone();
+    if !public_keys.contains(&root.pubkey) {
+        public_keys.push(root.pubkey);
+    }
+    if !status_kinds().contains(&status) {
+        bail!("{status} is not a git status kind")
+    }
+    let status_name = get_status_name(status);
+    sign_event(
+        EventBuilder::new(status, String::new()).tags(
+            [
+                vec![
+                    Tag::custom(
+                        TagKind::Custom(std::borrow::Cow::Borrowed("alt")),
+                        vec![format!(
+                            "git {} marked as {status_name}",
+                            if event_is_issue(root) {
+                                "issue"
+                            } else {
+                                "proposal"
+                            }
+                        )],
+                    ),
+                    Tag::from_standardized(TagStandard::Event {
+                        event_id: root.id,
+                        relay_url: repo_ref.relays.first().cloned(),
+                        marker: Some(Marker::Root),
+                        public_key: None,
+                        uppercase: false,
+                    }),
+                ],
+                public_keys.iter().map(|pk| Tag::public_key(*pk)).collect(),
+                repo_ref
+                    .coordinates()
+                    .iter()
+                    .map(|c| {
+                        Tag::from_standardized(TagStandard::Coordinate {
+                            coordinate: c.coordinate.clone(),
+                            relay_url: repo_ref.relays.first().cloned(),
+                            uppercase: false,
+                        })
+                    })
+                    .collect::<Vec<Tag>>(),
+                vec![Tag::from_standardized(TagStandard::Reference(
+                    repo_ref.root_commit.clone(),
+                ))],
+                extra_tags,
+            ]
+            .concat(),
+        ),
+        signer,
+        format!("{status_name} status"),
+    )
+    .await
+}
+
+pub fn get_status_name(status: Kind) -> &'static str {
+    if status.eq(&Kind::GitStatusApplied) {
+        "applied"
+    } else if status.eq(&Kind::GitStatusClosed) {
+        "closed"
+    } else if status.eq(&Kind::GitStatusDraft) {
+        "draft"
+    } else {
+        "open"
+    }
+}
+
+/// returns the kind of the most recent status event issued by the author or
+/// a maintainer, defaulting to open
+pub fn get_status_from_events(root: &Event, statuses: &[Event], maintainers: &[PublicKey]) -> Kind {
+    let mut statuses = statuses
+        .iter()
+        .filter(|e| {
+            status_kinds().contains(&e.kind)
+                && (e.pubkey.eq(&root.pubkey) || maintainers.contains(&e.pubkey))
+                && e.tags
+                    .iter()
+                    .any(|t| t.as_slice().len() > 1 && t.as_slice()[1].eq(&root.id.to_string()))
+        })
+        .collect::<Vec<&Event>>();
+    statuses.sort_by_key(|e| e.created_at);
+    if let Some(e) = statuses.last() {
+        e.kind
+    } else {
+        Kind::GitStatusOpen
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -754,4 +1065,92 @@ mod tests {
             }
         }
     }
+
+    mod get_issue_title {
+        use super::*;
+
+        #[test]
+        fn uses_subject_tag() -> Result<()> {
+            let event = EventBuilder::new(Kind::GitIssue, "first line\nmore")
+                .tags([Tag::custom(
+                    TagKind::Custom(std::borrow::Cow::Borrowed("subject")),
+                    vec!["the subject".to_string()],
+                )])
+                .sign_with_keys(&nostr::Keys::generate())?;
+            assert_eq!(get_issue_title(&event), "the subject");
+            Ok(())
+        }
+
+        #[test]
+        fn falls_back_to_first_line_of_content() -> Result<()> {
+            let event = EventBuilder::new(Kind::GitIssue, "first line\nmore")
+                .sign_with_keys(&nostr::Keys::generate())?;
+            assert_eq!(get_issue_title(&event), "first line");
+            Ok(())
+        }
+    }
+
+    mod get_status_from_events {
+        use super::*;
+
+        fn status(kind: Kind, root: &Event, keys: &nostr::Keys, secs: u64) -> Result<Event> {
+            Ok(EventBuilder::new(kind, "")
+                .tags([Tag::event(root.id)])
+                .custom_created_at(nostr::Timestamp::from(secs))
+                .sign_with_keys(keys)?)
+        }
+
+        #[test]
+        fn defaults_to_open() -> Result<()> {
+            let root =
+                EventBuilder::new(Kind::GitIssue, "").sign_with_keys(&nostr::Keys::generate())?;
+            assert_eq!(get_status_from_events(&root, &[], &[]), Kind::GitStatusOpen);
+            Ok(())
+        }
+
+        #[test]
+        fn most_recent_status_from_author_used() -> Result<()> {
+            let author = nostr::Keys::generate();
+            let root = EventBuilder::new(Kind::GitIssue, "").sign_with_keys(&author)?;
+            let statuses = vec![
+                status(Kind::GitStatusOpen, &root, &author, 20)?,
+                status(Kind::GitStatusClosed, &root, &author, 10)?,
+            ];
+            assert_eq!(
+                get_status_from_events(&root, &statuses, &[]),
+                Kind::GitStatusOpen
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn status_from_maintainer_used() -> Result<()> {
+            let maintainer = nostr::Keys::generate();
+            let root =
+                EventBuilder::new(Kind::GitIssue, "").sign_with_keys(&nostr::Keys::generate())?;
+            let statuses = vec![status(Kind::GitStatusClosed, &root, &maintainer, 10)?];
+            assert_eq!(
+                get_status_from_events(&root, &statuses, &[maintainer.public_key()]),
+                Kind::GitStatusClosed
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn status_from_other_users_ignored() -> Result<()> {
+            let root =
+                EventBuilder::new(Kind::GitIssue, "").sign_with_keys(&nostr::Keys::generate())?;
+            let statuses = vec![status(
+                Kind::GitStatusClosed,
+                &root,
+                &nostr::Keys::generate(),
+                10,
+            )?];
+            assert_eq!(
+                get_status_from_events(&root, &statuses, &[]),
+                Kind::GitStatusOpen
+            );
+            Ok(())
+        }
+    }
 }
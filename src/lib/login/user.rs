@@ -9,7 +9,7 @@ use serde::{self, Deserialize, Serialize};
 use crate::client::Client;
 #[cfg(test)]
 use crate::client::MockConnect;
-use crate::client::{Connect, get_event_from_global_cache};
+use crate::client::{Connect, FetchOptions, get_event_from_global_cache};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct UserRef {
@@ -69,7 +69,12 @@ pub async fn get_user_details(
                 let term = console::Term::stderr();
                 term.write_line("searching for profile updates...")?;
                 let (reports, progress_reporter) = client
-                    .fetch_all(git_repo_path, None, &HashSet::from_iter(vec![*public_key]))
+                    .fetch_all(
+                        git_repo_path,
+                        None,
+                        &HashSet::from_iter(vec![*public_key]),
+                        FetchOptions::default(),
+                    )
                     .await?;
                 if !reports.iter().any(|r| r.is_err()) {
                     progress_reporter.clear()?;
@@ -91,7 +96,12 @@ pub async fn get_user_details(
             let term = console::Term::stderr();
             term.write_line("searching for profile...")?;
             let (_, progress_reporter) = client
-                .fetch_all(git_repo_path, None, &HashSet::from_iter(vec![*public_key]))
+                .fetch_all(
+                    git_repo_path,
+                    None,
+                    &HashSet::from_iter(vec![*public_key]),
+                    FetchOptions::default(),
+                )
                 .await?;
             if let Ok(user_ref) = get_user_ref_from_cache(git_repo_path, public_key).await {
                 progress_reporter.clear()?;
@@ -131,6 +141,14 @@ pub async fn get_user_ref_from_cache(
     })
 }
 
+/// name from the cached profile, falling back to the npub
+pub async fn get_display_name(git_repo_path: Option<&Path>, public_key: &PublicKey) -> String {
+    match get_user_ref_from_cache(git_repo_path, public_key).await {
+        Ok(user_ref) => user_ref.metadata.name,
+        Err(_) => public_key.to_bech32().unwrap_or_default(),
+    }
+}
+
 pub fn extract_user_metadata(
     public_key: &nostr::PublicKey,
     events: &[nostr::Event],
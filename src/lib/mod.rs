@@ -1,9 +1,11 @@
+pub mod blossom;
 pub mod cli;
 pub mod cli_interactor;
 pub mod client;
 pub mod git;
 pub mod git_events;
 pub mod login;
+pub mod outbox;
 pub mod repo_ref;
 pub mod repo_state;
 pub mod sub_commands;
@@ -0,0 +1,325 @@
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+
+use anyhow::Result;
+use nostr::Event;
+use serde::{Deserialize, Serialize};
+
+use crate::client::{
+    Connect, delete_local_data, get_local_data, save_event_in_global_cache,
+    save_event_in_local_cache, save_local_data,
+};
+
+static OUTBOX_IDENTIFIER_PREFIX: &str = "ngit-outbox/";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Sent,
+    Failed(String),
+}
+
+impl Display for DeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeliveryStatus::Pending => write!(f, "pending"),
+            DeliveryStatus::Sent => write!(f, "sent"),
+            DeliveryStatus::Failed(error) => write!(f, "failed: {error}"),
+        }
+    }
+}
+
+/// signed event that hasn't yet reached all of the relays it is meant for
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OutboxEntry {
+    pub event: Event,
+    pub relays: BTreeMap<String, DeliveryStatus>,
+}
+
+impl OutboxEntry {
+    pub fn is_delivered(&self) -> bool {
+        self.relays.values().all(|s| s.eq(&DeliveryStatus::Sent))
+    }
+
+    pub fn undelivered_relays(&self) -> Vec<String> {
+        self.relays
+            .iter()
+            .filter(|(_, s)| !s.eq(&&DeliveryStatus::Sent))
+            .map(|(r, _)| r.clone())
+            .collect()
+    }
+}
+
+fn outbox_identifier(event: &Event) -> String {
+    format!("{OUTBOX_IDENTIFIER_PREFIX}{}", event.id)
+}
+
+/// saves entry in outbox, or removes it if it has been delivered to all relays.
+/// the event is added to the local cache so it is visible to other commands
+pub async fn save_outbox_entry(git_repo_path: &Path, entry: &OutboxEntry) -> Result<()> {
+    if entry.is_delivered() {
+        return remove_from_outbox(git_repo_path, &entry.event).await;
+    }
+    save_local_data(
+        git_repo_path,
+        &outbox_identifier(&entry.event),
+        &serde_json::to_string(entry)?,
+    )
+    .await?;
+    save_event_in_local_cache(git_repo_path, &entry.event).await?;
+    if entry.event.kind.eq(&nostr::Kind::GitRepoAnnouncement) {
+        save_event_in_global_cache(Some(git_repo_path), &entry.event).await?;
+    }
+    Ok(())
+}
+
+/// queue events to be published to relays later
+pub async fn add_to_outbox(
+    git_repo_path: &Path,
+    events: &[Event],
+    relays: Vec<String>,
+) -> Result<()> {
+    for event in events {
+        save_outbox_entry(
+            git_repo_path,
+            &OutboxEntry {
+                event: event.clone(),
+                relays: relays
+                    .iter()
+                    .map(|r| (r.clone(), DeliveryStatus::Pending))
+                    .collect(),
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// oldest first. entries that can't be parsed are skipped with a warning
+pub async fn get_outbox(git_repo_path: &Path) -> Result<Vec<OutboxEntry>> {
+    let mut entries = vec![];
+    for (identifier, content) in get_local_data(git_repo_path, OUTBOX_IDENTIFIER_PREFIX).await? {
+        match serde_json::from_str::<OutboxEntry>(&content) {
+            Ok(entry) => entries.push(entry),
+            Err(error) => {
+                eprintln!("WARNING: skipping unreadable outbox entry {identifier}: {error}")
+            }
+        }
+    }
+    entries.sort_by_key(|e| e.event.created_at);
+    Ok(entries)
+}
+
+pub async fn remove_from_outbox(git_repo_path: &Path, event: &Event) -> Result<()> {
+    delete_local_data(git_repo_path, &outbox_identifier(event)).await
+}
+
+/// retries each entry on the relays it hasn't yet reached and records the
+/// outcome. returns the entries that are still undelivered
+pub async fn retry_outbox_entries<C: Connect + Sync>(
+    client: &C,
+    git_repo_path: &Path,
+    entries: Vec<OutboxEntry>,
+) -> Result<Vec<OutboxEntry>> {
+    let mut undelivered = vec![];
+    for mut entry in entries {
+        for relay in entry.undelivered_relays() {
+            let status = match client
+                .send_event_to(Some(git_repo_path), &relay, entry.event.clone())
+                .await
+            {
+                Ok(_) => DeliveryStatus::Sent,
+                Err(error) => DeliveryStatus::Failed(
+                    error
+                        .to_string()
+                        .replace("relay pool error:", "")
+                        .replace("event not published: ", "")
+                        .trim()
+                        .to_string(),
+                ),
+            };
+            entry.relays.insert(relay, status);
+        }
+        save_outbox_entry(git_repo_path, &entry).await?;
+        if !entry.is_delivered() {
+            undelivered.push(entry);
+        }
+    }
+    Ok(undelivered)
+}
+
+/// attempt to publish queued and previously undelivered events
+pub async fn publish_outbox<C: Connect + Sync>(client: &C, git_repo_path: &Path) -> Result<()> {
+    let entries = get_outbox(git_repo_path).await?;
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let term = console::Term::stderr();
+    let count = entries.len();
+    term.write_line(&format!(
+        "publishing {count} event{} from outbox...",
+        if count == 1 { "" } else { "s" },
+    ))?;
+    let undelivered = retry_outbox_entries(client, git_repo_path, entries).await?;
+    if undelivered.is_empty() {
+        term.write_line(&format!(
+            "published {count} event{} from outbox",
+            if count == 1 { "" } else { "s" },
+        ))?;
+    } else {
+        term.write_line(&format!(
+            "{} of {count} events in outbox are still undelivered to some relays. see `ngit outbox list`",
+            undelivered.len(),
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::{TEST_KEY_1_KEYS, git::GitTestRepo};
+
+    use super::*;
+
+    fn generate_event(content: &str) -> Result<Event> {
+        Ok(nostr::EventBuilder::text_note(content).sign_with_keys(&TEST_KEY_1_KEYS)?)
+    }
+
+    #[tokio::test]
+    async fn queued_events_are_listed_and_can_be_removed() -> Result<()> {
+        let test_repo = GitTestRepo::default();
+        let git_repo_path = test_repo.dir.as_path();
+        let event_1 = generate_event("one")?;
+        let event_2 = generate_event("two")?;
+
+        add_to_outbox(
+            git_repo_path,
+            &[event_1.clone(), event_2.clone()],
+            vec!["ws://localhost:8051".to_string()],
+        )
+        .await?;
+
+        let outbox = get_outbox(git_repo_path).await?;
+        assert_eq!(outbox.len(), 2);
+        assert_eq!(
+            outbox[0].undelivered_relays(),
+            vec!["ws://localhost:8051".to_string()]
+        );
+
+        remove_from_outbox(git_repo_path, &event_1).await?;
+        let outbox = get_outbox(git_repo_path).await?;
+        assert_eq!(outbox.len(), 1);
+        assert_eq!(outbox[0].event.id, event_2.id);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn per_relay_status_is_persisted_and_delivered_entries_are_removed() -> Result<()> {
+        let test_repo = GitTestRepo::default();
+        let git_repo_path = test_repo.dir.as_path();
+        let event = generate_event("one")?;
+
+        let mut entry = OutboxEntry {
+            event: event.clone(),
+            relays: BTreeMap::from([
+                ("ws://localhost:8051".to_string(), DeliveryStatus::Sent),
+                (
+                    "ws://localhost:8052".to_string(),
+                    DeliveryStatus::Failed("timeout".to_string()),
+                ),
+            ]),
+        };
+        save_outbox_entry(git_repo_path, &entry).await?;
+
+        let outbox = get_outbox(git_repo_path).await?;
+        assert_eq!(outbox.len(), 1);
+        assert_eq!(
+            outbox[0].relays.get("ws://localhost:8052"),
+            Some(&DeliveryStatus::Failed("timeout".to_string()))
+        );
+        assert_eq!(
+            outbox[0].undelivered_relays(),
+            vec!["ws://localhost:8052".to_string()]
+        );
+
+        entry
+            .relays
+            .insert("ws://localhost:8052".to_string(), DeliveryStatus::Sent);
+        save_outbox_entry(git_repo_path, &entry).await?;
+        assert!(get_outbox(git_repo_path).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn entries_signed_by_another_key_are_ignored() -> Result<()> {
+        let test_repo = GitTestRepo::default();
+        let git_repo_path = test_repo.dir.as_path();
+        let event = generate_event("one")?;
+        let genuine = generate_event("two")?;
+        add_to_outbox(
+            git_repo_path,
+            &[genuine.clone()],
+            vec!["ws://localhost:8051".to_string()],
+        )
+        .await?;
+
+        let forged = nostr::EventBuilder::new(
+            nostr::Kind::ApplicationSpecificData,
+            serde_json::to_string(&OutboxEntry {
+                event: event.clone(),
+                relays: BTreeMap::from([(
+                    "ws://localhost:8051".to_string(),
+                    DeliveryStatus::Pending,
+                )]),
+            })?,
+        )
+        .tag(nostr::Tag::identifier(outbox_identifier(&event)))
+        .sign_with_keys(&TEST_KEY_1_KEYS)?;
+        save_event_in_local_cache(git_repo_path, &forged).await?;
+
+        let outbox = get_outbox(git_repo_path).await?;
+        assert_eq!(outbox.len(), 1);
+        assert_eq!(outbox[0].event.id, genuine.id);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unreadable_entries_are_skipped() -> Result<()> {
+        let test_repo = GitTestRepo::default();
+        let git_repo_path = test_repo.dir.as_path();
+        let event = generate_event("one")?;
+        add_to_outbox(
+            git_repo_path,
+            &[event.clone()],
+            vec!["ws://localhost:8051".to_string()],
+        )
+        .await?;
+        save_local_data(
+            git_repo_path,
+            &format!("{OUTBOX_IDENTIFIER_PREFIX}unreadable"),
+            "not an outbox entry",
+        )
+        .await?;
+
+        let outbox = get_outbox(git_repo_path).await?;
+        assert_eq!(outbox.len(), 1);
+        assert_eq!(outbox[0].event.id, event.id);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reading_empty_outbox_doesnt_create_local_data_key() -> Result<()> {
+        let test_repo = GitTestRepo::default();
+        let git_repo_path = test_repo.dir.as_path();
+
+        assert!(get_outbox(git_repo_path).await?.is_empty());
+        assert!(
+            test_repo
+                .git_repo
+                .config()?
+                .get_string("nostr.local-data-key")
+                .is_err()
+        );
+        Ok(())
+    }
+}
@@ -21,9 +21,11 @@ use crate::{
     cli_interactor::{
         Interactor, InteractorPrompt, PromptChoiceParms, PromptConfirmParms, PromptInputParms,
     },
-    client::{Connect, consolidate_fetch_reports, get_repo_ref_from_cache, sign_event},
+    client::{
+        Connect, FetchOptions, consolidate_fetch_reports, get_repo_ref_from_cache, sign_event,
+    },
     git::{
-        Repo, RepoActions,
+        CommitId, Repo, RepoActions,
         nostr_url::{NostrUrlDecoded, use_nip05_git_config_cache_to_find_nip05_from_public_key},
     },
     login::user::get_user_details,
@@ -42,6 +44,12 @@ pub struct RepoRef {
     pub trusted_maintainer: PublicKey,
     pub events: HashMap<Nip19Coordinate, nostr::Event>,
     pub nostr_git_url: Option<NostrUrlDecoded>,
+    /// repository this was forked from
+    pub fork_of: Option<Nip19Coordinate>,
+    /// maintainer no longer accepts contributions
+    pub archived: bool,
+    /// nostr url of the repository's new home
+    pub moved_to: Option<String>,
 }
 
 impl TryFrom<(nostr::Event, Option<PublicKey>)> for RepoRef {
@@ -65,6 +73,9 @@ impl TryFrom<(nostr::Event, Option<PublicKey>)> for RepoRef {
             trusted_maintainer: trusted_maintainer.unwrap_or(event.pubkey),
             events: HashMap::new(),
             nostr_git_url: None,
+            fork_of: None,
+            archived: false,
+            moved_to: None,
         };
 
         for tag in event.tags.iter() {
@@ -78,21 +89,19 @@ impl TryFrom<(nostr::Event, Option<PublicKey>)> for RepoRef {
                 [t, web @ ..] if t == "web" => {
                     r.web = web.to_vec();
                 }
-                [t, commit_id]
-                    if t == "r"
-                        && commit_id.len() == 40
-                        && git2::Oid::from_str(commit_id).is_ok() =>
-                {
+                [t, commit_id] if t == "r" && CommitId::is_valid(commit_id) => {
                     r.root_commit = commit_id.clone();
                 }
                 [t, commit_id, marker]
-                    if t == "r"
-                        && marker == "euc"
-                        && commit_id.len() == 40
-                        && git2::Oid::from_str(commit_id).is_ok() =>
+                    if t == "r" && marker == "euc" && CommitId::is_valid(commit_id) =>
                 {
                     r.root_commit = commit_id.clone();
                 }
+                [t, coordinate, relay, marker] if t == "a" && marker == "fork" => {
+                    r.fork_of = parse_fork_coordinate(coordinate, relay);
+                }
+                [t, ..] if t == "archived" => r.archived = true,
+                [t, url, ..] if t == "moved-to" => r.moved_to = Some(url.clone()),
                 [t, relays @ ..] if t == "relays" => {
                     for relay in relays {
                         if let Ok(relay_url) = RelayUrl::parse(relay) {
@@ -136,6 +145,22 @@ impl TryFrom<(nostr::Event, Option<PublicKey>)> for RepoRef {
     }
 }
 
+/// parses the `kind:pubkey:identifier` value of an `a` tag
+fn parse_fork_coordinate(coordinate: &str, relay: &str) -> Option<Nip19Coordinate> {
+    let mut parts = coordinate.splitn(3, ':');
+    let kind = parts.next()?.parse::<u16>().ok()?;
+    let public_key = PublicKey::from_hex(parts.next()?).ok()?;
+    let identifier = parts.next()?.to_string();
+    Some(Nip19Coordinate {
+        coordinate: Coordinate {
+            kind: Kind::from(kind),
+            public_key,
+            identifier,
+        },
+        relays: RelayUrl::parse(relay).into_iter().collect(),
+    })
+}
+
 impl RepoRef {
     pub async fn to_event(&self, signer: &Arc<dyn NostrSigner>) -> Result<nostr::Event> {
         sign_event(
@@ -190,6 +215,43 @@ impl RepoRef {
                             vec![format!("git repository: {}", self.name.clone())],
                         ),
                     ],
+                    if let Some(fork_of) = &self.fork_of {
+                        vec![Tag::custom(
+                            nostr::TagKind::Custom(std::borrow::Cow::Borrowed("a")),
+                            vec![
+                                format!(
+                                    "{}:{}:{}",
+                                    fork_of.kind.as_u16(),
+                                    fork_of.public_key,
+                                    fork_of.identifier
+                                ),
+                                fork_of
+                                    .relays
+                                    .first()
+                                    .map(std::string::ToString::to_string)
+                                    .unwrap_or_default(),
+                                "fork".to_string(),
+                            ],
+                        )]
+                    } else {
+                        vec![]
+                    },
+                    if self.archived {
+                        vec![Tag::custom(
+                            nostr::TagKind::Custom(std::borrow::Cow::Borrowed("archived")),
+                            Vec::<String>::new(),
+                        )]
+                    } else {
+                        vec![]
+                    },
+                    if let Some(moved_to) = &self.moved_to {
+                        vec![Tag::custom(
+                            nostr::TagKind::Custom(std::borrow::Cow::Borrowed("moved-to")),
+                            vec![moved_to.clone()],
+                        )]
+                    } else {
+                        vec![]
+                    },
                     // code languages and hashtags
                 ]
                 .concat(),
@@ -200,6 +262,24 @@ impl RepoRef {
         .await
         .context("failed to create repository reference event")
     }
+    /// warning to show users when the maintainer has archived or moved the
+    /// repository
+    pub fn archived_warning(&self) -> Option<String> {
+        if let Some(moved_to) = &self.moved_to {
+            Some(format!(
+                "repository {} has moved to {moved_to}",
+                self.identifier
+            ))
+        } else if self.archived {
+            Some(format!(
+                "repository {} is archived and no longer accepts contributions",
+                self.identifier
+            ))
+        } else {
+            None
+        }
+    }
+
     /// coordinates without relay hints
     pub fn coordinates(&self) -> HashSet<Nip19Coordinate> {
         let mut res = HashSet::new();
@@ -432,6 +512,7 @@ async fn get_repo_coordinate_from_user_prompt(
                     Some(git_repo_path),
                     Some(&coordinate),
                     &HashSet::from_iter(vec![coordinate.public_key]),
+                    FetchOptions::default(),
                 )
                 .await?;
             let relay_errs = relay_reports.iter().any(std::result::Result::is_err);
@@ -573,6 +654,9 @@ mod tests {
             maintainers: vec![TEST_KEY_1_KEYS.public_key(), TEST_KEY_2_KEYS.public_key()],
             events: HashMap::new(),
             nostr_git_url: None,
+            fork_of: None,
+            archived: false,
+            moved_to: None,
         }
         .to_event(&TEST_KEY_1_SIGNER)
         .await
@@ -619,6 +703,24 @@ mod tests {
             )
         }
 
+        #[tokio::test]
+        async fn root_commit_can_be_sha256() {
+            use nostr::JsonUtil;
+
+            let sha256 = "6f0c8c7c1a8b4b4e2e3a9d3f0d9c5e7b8a1f2e3d4c5b6a798897a6b5c4d3e2f1";
+            let event = nostr::Event::from_json(
+                create()
+                    .await
+                    .as_json()
+                    .replace("5e664e5a7845cd1373c79f580ca4fe29ab5b34d2", sha256),
+            )
+            .unwrap();
+            assert_eq!(
+                RepoRef::try_from((event, None)).unwrap().root_commit,
+                sha256,
+            )
+        }
+
         mod root_commit_is_empty_if_no_r_tag_which_is_sha1_format {
             use nostr::JsonUtil;
 
@@ -708,6 +810,49 @@ mod tests {
                 vec![TEST_KEY_1_KEYS.public_key(), TEST_KEY_2_KEYS.public_key()],
             )
         }
+
+        #[tokio::test]
+        async fn fork_of_is_none_without_fork_tag() {
+            assert_eq!(
+                RepoRef::try_from((create().await, None)).unwrap().fork_of,
+                None,
+            )
+        }
+
+        #[tokio::test]
+        async fn archived_and_moved_to_round_trip() {
+            let mut repo_ref = RepoRef::try_from((create().await, None)).unwrap();
+            assert!(!repo_ref.archived);
+            assert_eq!(repo_ref.moved_to, None);
+            repo_ref.archived = true;
+            repo_ref.moved_to = Some("nostr://npub123/example".to_string());
+            let event = repo_ref.to_event(&TEST_KEY_1_SIGNER).await.unwrap();
+            let repo_ref = RepoRef::try_from((event, None)).unwrap();
+            assert!(repo_ref.archived);
+            assert_eq!(
+                repo_ref.moved_to,
+                Some("nostr://npub123/example".to_string())
+            );
+        }
+
+        #[tokio::test]
+        async fn fork_of_round_trips() {
+            let upstream = Nip19Coordinate {
+                coordinate: Coordinate {
+                    kind: Kind::GitRepoAnnouncement,
+                    public_key: TEST_KEY_2_KEYS.public_key(),
+                    identifier: "upstream".to_string(),
+                },
+                relays: vec![RelayUrl::parse("ws://relay1.io").unwrap()],
+            };
+            let mut repo_ref = RepoRef::try_from((create().await, None)).unwrap();
+            repo_ref.fork_of = Some(upstream.clone());
+            let event = repo_ref.to_event(&TEST_KEY_1_SIGNER).await.unwrap();
+            assert_eq!(
+                RepoRef::try_from((event, None)).unwrap().fork_of,
+                Some(upstream),
+            )
+        }
     }
 
     mod to_event {
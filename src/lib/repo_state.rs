@@ -1,33 +1,141 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Arc,
+};
 
 use anyhow::{Context, Result};
 use git2::Oid;
+use nostr::PublicKey;
+use nostr_sdk::{EventBuilder, NostrSigner, Tag, TagKind};
+
+use crate::{
+    client::{STATE_KIND, get_local_data, save_local_data, sign_event},
+    git::{CommitId, Repo},
+};
+
+/// state event tag listing the sha256 of a packfile stored on blossom git
+/// servers
+pub static BLOSSOM_PACK_TAG: &str = "blossom-pack";
+/// state event tag listing a ref whose update in this state is intentionally
+/// not a fast-forward of the previous state
+pub static FORCE_PUSHED_TAG: &str = "force-pushed";
+static VERIFIED_STATE_IDENTIFIER_PREFIX: &str = "ngit-verified-state/";
 
 pub struct RepoState {
     pub identifier: String,
+    /// newest value of each ref across maintainers
     pub state: HashMap<String, String>,
+    /// oldest first
+    pub blossom_packs: Vec<String>,
+    pub force_pushed: Vec<String>,
+    /// newest
     pub event: nostr::Event,
+    /// newest event from each maintainer, newest first
+    pub events: Vec<nostr::Event>,
+}
+
+/// outcome of [`RepoState::verify`]
+#[derive(Default)]
+pub struct StateVerification {
+    /// reasons the state shouldn't be trusted
+    pub failures: Vec<String>,
+    /// branches rewritten without a force-pushed tag. only ngit adds the tag
+    /// so other clients' force pushes are reported rather than refused
+    pub warnings: Vec<String>,
+}
+
+/// a branch that maintainers' state events point at divergent commits
+pub struct StateConflict {
+    pub name: String,
+    /// newest first
+    pub values: Vec<(PublicKey, String)>,
+}
+
+struct EventState {
+    state: HashMap<String, String>,
+    blossom_packs: Vec<String>,
+    force_pushed: Vec<String>,
+}
+
+fn parse_state_event(event: &nostr::Event) -> EventState {
+    let mut state = HashMap::new();
+    let mut blossom_packs = vec![];
+    let mut force_pushed = vec![];
+    for tag in event.tags.iter() {
+        if let Some(name) = tag.as_slice().first() {
+            if name.eq(BLOSSOM_PACK_TAG) {
+                if let Some(sha256) = tag.as_slice().get(1) {
+                    blossom_packs.push(sha256.to_owned());
+                }
+            } else if name.eq(FORCE_PUSHED_TAG) {
+                if let Some(ref_name) = tag.as_slice().get(1) {
+                    force_pushed.push(ref_name.to_owned());
+                }
+            } else if ["refs/heads/", "refs/tags", "HEAD"]
+                .iter()
+                .any(|s| name.starts_with(*s))
+            {
+                if let Some(value) = tag.as_slice().get(1) {
+                    if CommitId::is_valid(value) || value.contains("ref: refs/") {
+                        state.insert(name.to_owned(), value.to_owned());
+                    }
+                }
+            }
+        }
+    }
+    EventState {
+        state,
+        blossom_packs,
+        force_pushed,
+    }
 }
 
 impl RepoState {
+    /// merges the newest state event of each maintainer, taking the newest
+    /// value of each ref. a ref missing from a maintainer's newest event has
+    /// only been deleted if one of their older events included it
     pub fn try_from(mut state_events: Vec<nostr::Event>) -> Result<Self> {
-        state_events.sort_by_key(|e| e.created_at);
-        let event = state_events.first().context("no state events")?;
+        state_events.sort_by_key(|e| std::cmp::Reverse(e.created_at));
+        let mut events: Vec<nostr::Event> = vec![];
+        let mut superseded: Vec<nostr::Event> = vec![];
+        for event in state_events {
+            if events.iter().any(|e| e.pubkey.eq(&event.pubkey)) {
+                superseded.push(event);
+            } else {
+                events.push(event);
+            }
+        }
+        let event = events.first().context("no state events")?.clone();
+
         let mut state = HashMap::new();
-        for tag in event.tags.iter() {
-            if let Some(name) = tag.as_slice().first() {
-                if ["refs/heads/", "refs/tags", "HEAD"]
-                    .iter()
-                    .any(|s| name.starts_with(*s))
-                {
-                    if let Some(value) = tag.as_slice().get(1) {
-                        if Oid::from_str(value).is_ok() || value.contains("ref: refs/") {
-                            state.insert(name.to_owned(), value.to_owned());
-                        }
-                    }
+        let mut blossom_packs: Vec<String> = vec![];
+        let mut force_pushed: Vec<String> = vec![];
+        for e in events.iter().rev() {
+            let event_state = parse_state_event(e);
+            let previously_published: HashSet<String> = superseded
+                .iter()
+                .filter(|older| older.pubkey.eq(&e.pubkey))
+                .flat_map(|older| parse_state_event(older).state.into_keys())
+                .collect();
+            state.retain(|name, _| {
+                event_state.state.contains_key(name) || !previously_published.contains(name)
+            });
+            force_pushed.retain(|name| state.contains_key(name));
+            for (name, value) in event_state.state {
+                force_pushed.retain(|n| n.ne(&name));
+                if event_state.force_pushed.contains(&name) {
+                    force_pushed.push(name.clone());
+                }
+                state.insert(name, value);
+            }
+            for sha256 in event_state.blossom_packs {
+                if !blossom_packs.contains(&sha256) {
+                    blossom_packs.push(sha256);
                 }
             }
         }
+
         Ok(RepoState {
             identifier: event
                 .tags
@@ -35,7 +143,598 @@ impl RepoState {
                 .context("existing event must have an identifier")?
                 .to_string(),
             state,
+            blossom_packs,
+            force_pushed,
+            event,
+            events,
+        })
+    }
+
+    pub async fn build(
+        identifier: String,
+        state: HashMap<String, String>,
+        blossom_packs: Vec<String>,
+        force_pushed: Vec<String>,
+        signer: &Arc<dyn NostrSigner>,
+    ) -> Result<RepoState> {
+        let mut tags = vec![Tag::identifier(identifier.clone())];
+        for (name, value) in &state {
+            tags.push(Tag::custom(
+                TagKind::Custom(name.into()),
+                vec![value.clone()],
+            ));
+        }
+        for sha256 in &blossom_packs {
+            tags.push(Tag::custom(
+                TagKind::Custom(BLOSSOM_PACK_TAG.into()),
+                vec![sha256.clone()],
+            ));
+        }
+        for name in &force_pushed {
+            tags.push(Tag::custom(
+                TagKind::Custom(FORCE_PUSHED_TAG.into()),
+                vec![name.clone()],
+            ));
+        }
+        let event = sign_event(
+            EventBuilder::new(STATE_KIND, "").tags(tags),
+            signer,
+            "git state".to_string(),
+        )
+        .await?;
+        Ok(RepoState {
+            identifier,
+            state,
+            blossom_packs,
+            force_pushed,
             event: event.clone(),
+            events: vec![event],
         })
     }
+
+    /// refs in each maintainer's newest state event, newest first
+    pub fn maintainer_states(&self) -> Vec<(PublicKey, HashMap<String, String>)> {
+        self.events
+            .iter()
+            .map(|e| (e.pubkey, parse_state_event(e).state))
+            .collect()
+    }
+
+    /// branches that maintainers point at commits where neither is an
+    /// ancestor of the other. commits missing from `git_repo` are treated as
+    /// divergent
+    pub fn conflicts(&self, git_repo: &Repo) -> Vec<StateConflict> {
+        let maintainer_states = self.maintainer_states();
+        let mut names: Vec<&String> = self
+            .state
+            .keys()
+            .filter(|name| name.starts_with("refs/heads/"))
+            .collect();
+        names.sort();
+        let mut conflicts = vec![];
+        for name in names {
+            let values: Vec<(PublicKey, String)> = maintainer_states
+                .iter()
+                .filter_map(|(public_key, state)| {
+                    state.get(name).map(|value| (*public_key, value.clone()))
+                })
+                .collect();
+            let divergent = values.iter().any(|(_, a)| {
+                values
+                    .iter()
+                    .any(|(_, b)| !is_same_line_of_history(git_repo, a, b))
+            });
+            if divergent {
+                conflicts.push(StateConflict {
+                    name: name.clone(),
+                    values,
+                });
+            }
+        }
+        conflicts
+    }
+
+    /// checks the state was published by maintainers and that its refs exist.
+    /// objects must already have been fetched from the git servers so that any
+    /// ref missing from `git_repo` is missing from every git server. branches
+    /// that aren't fast-forwards of `previous_state` and aren't marked
+    /// force-pushed are returned as warnings.
+    pub fn verify(
+        &self,
+        git_repo: &Repo,
+        maintainers: &[PublicKey],
+        previous_state: Option<&HashMap<String, String>>,
+    ) -> Result<StateVerification> {
+        let mut failures = vec![];
+        let mut warnings = vec![];
+        for event in &self.events {
+            if !maintainers.contains(&event.pubkey) {
+                failures.push(format!(
+                    "state event author {} isn't a listed maintainer",
+                    event.pubkey
+                ));
+            }
+        }
+        let odb = git_repo.git_repo.odb()?;
+        let mut names: Vec<&String> = self.state.keys().collect();
+        names.sort();
+        for name in names {
+            let value = &self.state[name];
+            if value.starts_with("ref: ") {
+                continue;
+            }
+            let oid = match value.parse::<CommitId>().and_then(|id| id.to_oid()) {
+                Ok(oid) => oid,
+                Err(error) => {
+                    failures.push(format!("{name} {value} couldn't be checked: {error}"));
+                    continue;
+                }
+            };
+            if !odb.exists(oid) {
+                failures.push(format!("{name} {value} isn't available on any git server"));
+                continue;
+            }
+            if !name.starts_with("refs/heads/") || self.force_pushed.contains(name) {
+                continue;
+            }
+            if let Some(Ok(previous_oid)) = previous_state
+                .and_then(|previous_state| previous_state.get(name))
+                .map(|previous_value| Oid::from_str(previous_value))
+            {
+                if previous_oid != oid
+                    && !git_repo
+                        .git_repo
+                        .graph_descendant_of(oid, previous_oid)
+                        .unwrap_or(false)
+                {
+                    warnings.push(format!(
+                        "{name} {value} isn't a fast-forward of previously verified {previous_oid}"
+                    ));
+                }
+            }
+        }
+        Ok(StateVerification { failures, warnings })
+    }
+}
+
+/// branches in both states whose new value isn't a descendant of the existing
+/// one
+pub fn get_non_fast_forward_refs(
+    git_repo: &Repo,
+    existing_state: &HashMap<String, String>,
+    new_state: &HashMap<String, String>,
+) -> Vec<String> {
+    let mut names: Vec<String> = new_state
+        .iter()
+        .filter(|(name, new_value)| {
+            name.starts_with("refs/heads/")
+                && existing_state.get(*name).is_some_and(|existing_value| {
+                    existing_value.ne(*new_value)
+                        && !Oid::from_str(existing_value)
+                            .and_then(|existing_oid| {
+                                git_repo
+                                    .git_repo
+                                    .graph_descendant_of(Oid::from_str(new_value)?, existing_oid)
+                            })
+                            .unwrap_or(false)
+                })
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+fn is_same_line_of_history(git_repo: &Repo, a: &str, b: &str) -> bool {
+    if a.eq(b) {
+        return true;
+    }
+    let (Ok(a), Ok(b)) = (Oid::from_str(a), Oid::from_str(b)) else {
+        return false;
+    };
+    git_repo.git_repo.graph_descendant_of(a, b).unwrap_or(false)
+        || git_repo.git_repo.graph_descendant_of(b, a).unwrap_or(false)
+}
+
+fn verified_state_identifier(identifier: &str) -> String {
+    format!("{VERIFIED_STATE_IDENTIFIER_PREFIX}{identifier}")
+}
+
+/// refs from the last state event that passed verification
+pub async fn get_last_verified_state(
+    git_repo_path: &Path,
+    identifier: &str,
+) -> Result<Option<HashMap<String, String>>> {
+    let identifier = verified_state_identifier(identifier);
+    match get_local_data(git_repo_path, VERIFIED_STATE_IDENTIFIER_PREFIX)
+        .await?
+        .into_iter()
+        .find(|(i, _)| i.eq(&identifier))
+    {
+        Some((_, content)) => Ok(Some(
+            serde_json::from_str(&content).context("failed to parse last verified state")?,
+        )),
+        None => Ok(None),
+    }
+}
+
+pub async fn save_verified_state(git_repo_path: &Path, repo_state: &RepoState) -> Result<()> {
+    save_local_data(
+        git_repo_path,
+        &verified_state_identifier(&repo_state.identifier),
+        &serde_json::to_string(&repo_state.state)?,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr::Timestamp;
+    use test_utils::{TEST_KEY_1_KEYS, TEST_KEY_2_KEYS, git::GitTestRepo};
+
+    use super::*;
+    use crate::client::STATE_KIND;
+
+    fn state_event(
+        keys: &nostr::Keys,
+        refs: &[(&str, String)],
+        force_pushed: &[&str],
+    ) -> nostr::Event {
+        state_event_created_at(keys, refs, force_pushed, Timestamp::now())
+    }
+
+    fn state_event_created_at(
+        keys: &nostr::Keys,
+        refs: &[(&str, String)],
+        force_pushed: &[&str],
+        created_at: Timestamp,
+    ) -> nostr::Event {
+        let mut tags = vec![Tag::identifier("example")];
+        for (name, value) in refs {
+            tags.push(Tag::custom(
+                TagKind::Custom((*name).into()),
+                vec![value.clone()],
+            ));
+        }
+        for name in force_pushed {
+            tags.push(Tag::custom(
+                TagKind::Custom(FORCE_PUSHED_TAG.into()),
+                vec![(*name).to_string()],
+            ));
+        }
+        EventBuilder::new(STATE_KIND, "")
+            .tags(tags)
+            .custom_created_at(created_at)
+            .sign_with_keys(keys)
+            .unwrap()
+    }
+
+    mod try_from {
+        use super::*;
+
+        #[test]
+        fn newest_value_of_each_ref_used_across_maintainers() -> Result<()> {
+            let repo_state = RepoState::try_from(vec![
+                state_event_created_at(
+                    &TEST_KEY_1_KEYS,
+                    &[
+                        ("refs/heads/main", "1".repeat(40)),
+                        ("refs/heads/feature", "2".repeat(40)),
+                    ],
+                    &[],
+                    Timestamp::from(10),
+                ),
+                state_event_created_at(
+                    &TEST_KEY_2_KEYS,
+                    &[
+                        ("refs/heads/main", "3".repeat(40)),
+                        ("refs/heads/feature", "2".repeat(40)),
+                    ],
+                    &["refs/heads/main"],
+                    Timestamp::from(20),
+                ),
+                // superseded by maintainer's newer event
+                state_event_created_at(
+                    &TEST_KEY_2_KEYS,
+                    &[("refs/heads/old", "4".repeat(40))],
+                    &[],
+                    Timestamp::from(5),
+                ),
+            ])?;
+            assert_eq!(
+                repo_state.state,
+                HashMap::from([
+                    ("refs/heads/main".to_string(), "3".repeat(40)),
+                    ("refs/heads/feature".to_string(), "2".repeat(40)),
+                ])
+            );
+            assert_eq!(repo_state.force_pushed, vec!["refs/heads/main"]);
+            assert_eq!(repo_state.event.pubkey, TEST_KEY_2_KEYS.public_key());
+            assert_eq!(repo_state.events.len(), 2);
+            Ok(())
+        }
+
+        #[test]
+        fn ref_missing_from_newer_event_is_deleted_by_maintainer_who_published_it() -> Result<()> {
+            let repo_state = RepoState::try_from(vec![
+                state_event_created_at(
+                    &TEST_KEY_1_KEYS,
+                    &[
+                        ("refs/heads/main", "1".repeat(40)),
+                        ("refs/heads/feature", "2".repeat(40)),
+                    ],
+                    &["refs/heads/feature"],
+                    Timestamp::from(10),
+                ),
+                state_event_created_at(
+                    &TEST_KEY_2_KEYS,
+                    &[
+                        ("refs/heads/main", "1".repeat(40)),
+                        ("refs/heads/feature", "2".repeat(40)),
+                    ],
+                    &[],
+                    Timestamp::from(15),
+                ),
+                state_event_created_at(
+                    &TEST_KEY_2_KEYS,
+                    &[("refs/heads/main", "1".repeat(40))],
+                    &[],
+                    Timestamp::from(20),
+                ),
+            ])?;
+            assert_eq!(
+                repo_state.state,
+                HashMap::from([("refs/heads/main".to_string(), "1".repeat(40))])
+            );
+            assert!(repo_state.force_pushed.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn branches_owned_by_different_maintainers_are_both_kept() -> Result<()> {
+            let repo_state = RepoState::try_from(vec![
+                state_event_created_at(
+                    &TEST_KEY_1_KEYS,
+                    &[
+                        ("refs/heads/main", "1".repeat(40)),
+                        ("refs/heads/feature-a", "2".repeat(40)),
+                    ],
+                    &[],
+                    Timestamp::from(10),
+                ),
+                state_event_created_at(
+                    &TEST_KEY_2_KEYS,
+                    &[
+                        ("refs/heads/main", "3".repeat(40)),
+                        ("refs/heads/feature-b", "4".repeat(40)),
+                    ],
+                    &[],
+                    Timestamp::from(20),
+                ),
+            ])?;
+            assert_eq!(
+                repo_state.state,
+                HashMap::from([
+                    ("refs/heads/main".to_string(), "3".repeat(40)),
+                    ("refs/heads/feature-a".to_string(), "2".repeat(40)),
+                    ("refs/heads/feature-b".to_string(), "4".repeat(40)),
+                ])
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn sha256_values_kept_and_invalid_values_dropped() -> Result<()> {
+            let repo_state = RepoState::try_from(vec![state_event(
+                &TEST_KEY_1_KEYS,
+                &[
+                    ("refs/heads/main", "a".repeat(64)),
+                    ("refs/heads/short", "b".repeat(39)),
+                ],
+                &[],
+            )])?;
+            assert_eq!(
+                repo_state.state,
+                HashMap::from([("refs/heads/main".to_string(), "a".repeat(64))])
+            );
+            Ok(())
+        }
+    }
+
+    mod conflicts {
+        use super::*;
+
+        #[test]
+        fn reported_when_maintainers_diverge_but_not_when_one_is_behind() -> Result<()> {
+            let test_repo = GitTestRepo::default();
+            let base = test_repo.populate()?;
+            test_repo.create_branch("other")?;
+            std::fs::write(test_repo.dir.join("ahead.md"), "some content")?;
+            let ahead = test_repo.stage_and_commit("ahead.md")?;
+            test_repo.checkout("other")?;
+            std::fs::write(test_repo.dir.join("divergent.md"), "some content")?;
+            let divergent = test_repo.stage_and_commit("divergent.md")?;
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+
+            let repo_state = RepoState::try_from(vec![
+                state_event_created_at(
+                    &TEST_KEY_1_KEYS,
+                    &[
+                        ("refs/heads/main", ahead.to_string()),
+                        ("refs/heads/other", base.to_string()),
+                    ],
+                    &[],
+                    Timestamp::from(10),
+                ),
+                state_event_created_at(
+                    &TEST_KEY_2_KEYS,
+                    &[
+                        ("refs/heads/main", divergent.to_string()),
+                        ("refs/heads/other", ahead.to_string()),
+                    ],
+                    &[],
+                    Timestamp::from(20),
+                ),
+            ])?;
+            let conflicts = repo_state.conflicts(&git_repo);
+            assert_eq!(conflicts.len(), 1);
+            assert_eq!(conflicts[0].name, "refs/heads/main");
+            assert_eq!(
+                conflicts[0].values,
+                vec![
+                    (TEST_KEY_2_KEYS.public_key(), divergent.to_string()),
+                    (TEST_KEY_1_KEYS.public_key(), ahead.to_string()),
+                ]
+            );
+            Ok(())
+        }
+    }
+
+    mod verify {
+        use super::*;
+
+        #[test]
+        fn passes_when_refs_exist_and_author_is_maintainer() -> Result<()> {
+            let test_repo = GitTestRepo::default();
+            let tip = test_repo.populate()?;
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+            let repo_state = RepoState::try_from(vec![state_event(
+                &TEST_KEY_1_KEYS,
+                &[
+                    ("HEAD", "ref: refs/heads/main".to_string()),
+                    ("refs/heads/main", tip.to_string()),
+                ],
+                &[],
+            )])?;
+            assert!(
+                repo_state
+                    .verify(&git_repo, &[TEST_KEY_1_KEYS.public_key()], None)?
+                    .failures
+                    .is_empty()
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn fails_when_author_isnt_maintainer() -> Result<()> {
+            let test_repo = GitTestRepo::default();
+            let tip = test_repo.populate()?;
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+            let repo_state = RepoState::try_from(vec![state_event(
+                &TEST_KEY_2_KEYS,
+                &[("refs/heads/main", tip.to_string())],
+                &[],
+            )])?;
+            assert_eq!(
+                repo_state
+                    .verify(&git_repo, &[TEST_KEY_1_KEYS.public_key()], None)?
+                    .failures
+                    .len(),
+                1
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn fails_when_ref_is_missing_from_git_servers() -> Result<()> {
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+            let repo_state = RepoState::try_from(vec![state_event(
+                &TEST_KEY_1_KEYS,
+                &[(
+                    "refs/heads/main",
+                    "431b84edc0d2fa118d63faa3c2db9c73d630a5ae".to_string(),
+                )],
+                &[],
+            )])?;
+            assert_eq!(
+                repo_state
+                    .verify(&git_repo, &[TEST_KEY_1_KEYS.public_key()], None)?
+                    .failures
+                    .len(),
+                1
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn fails_on_sha256_values() -> Result<()> {
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+            let repo_state = RepoState::try_from(vec![state_event(
+                &TEST_KEY_1_KEYS,
+                &[("refs/heads/main", "a".repeat(64))],
+                &[],
+            )])?;
+            let verification =
+                repo_state.verify(&git_repo, &[TEST_KEY_1_KEYS.public_key()], None)?;
+            assert_eq!(verification.failures.len(), 1);
+            assert!(verification.warnings.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn warns_when_branch_rewritten_without_force_pushed_tag() -> Result<()> {
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+            test_repo.create_branch("rewritten")?;
+            std::fs::write(test_repo.dir.join("previous.md"), "some content")?;
+            let previous_tip = test_repo.stage_and_commit("previous.md")?;
+            test_repo.checkout("rewritten")?;
+            std::fs::write(test_repo.dir.join("rewritten.md"), "some content")?;
+            let rewritten_tip = test_repo.stage_and_commit("rewritten.md")?;
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+            let previous_state =
+                HashMap::from([("refs/heads/main".to_string(), previous_tip.to_string())]);
+            let maintainers = [TEST_KEY_1_KEYS.public_key()];
+
+            let repo_state = RepoState::try_from(vec![state_event(
+                &TEST_KEY_1_KEYS,
+                &[("refs/heads/main", rewritten_tip.to_string())],
+                &[],
+            )])?;
+            let verification = repo_state.verify(&git_repo, &maintainers, Some(&previous_state))?;
+            assert!(verification.failures.is_empty());
+            assert_eq!(verification.warnings.len(), 1);
+
+            let repo_state = RepoState::try_from(vec![state_event(
+                &TEST_KEY_1_KEYS,
+                &[("refs/heads/main", rewritten_tip.to_string())],
+                &["refs/heads/main"],
+            )])?;
+            let verification = repo_state.verify(&git_repo, &maintainers, Some(&previous_state))?;
+            assert!(verification.failures.is_empty());
+            assert!(verification.warnings.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn passes_when_branch_fast_forwards() -> Result<()> {
+            let test_repo = GitTestRepo::default();
+            let previous_tip = test_repo.populate()?;
+            std::fs::write(test_repo.dir.join("next.md"), "some content")?;
+            let tip = test_repo.stage_and_commit("next.md")?;
+            let git_repo = Repo::from_path(&test_repo.dir)?;
+            let previous_state =
+                HashMap::from([("refs/heads/main".to_string(), previous_tip.to_string())]);
+            let repo_state = RepoState::try_from(vec![state_event(
+                &TEST_KEY_1_KEYS,
+                &[("refs/heads/main", tip.to_string())],
+                &[],
+            )])?;
+            assert!(
+                repo_state
+                    .verify(
+                        &git_repo,
+                        &[TEST_KEY_1_KEYS.public_key()],
+                        Some(&previous_state)
+                    )?
+                    .warnings
+                    .is_empty()
+            );
+            Ok(())
+        }
+    }
 }
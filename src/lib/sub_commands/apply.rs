@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use nostr::{ToBech32, nips::nip19::Nip19Event};
+
+use crate::{
+    client,
+    client::{
+        Client, Connect, fetching_with_report, get_all_proposal_patch_events_from_cache,
+        get_proposal_from_cache_by_reference, get_repo_ref_from_cache,
+    },
+    git::{Repo, RepoActions},
+    git_events::{commit_msg_from_patch_oneliner, get_most_recent_patch_with_ancestors},
+    repo_ref::{RepoRef, get_repo_coordinates_when_remote_unknown},
+    sub_commands::list::check_clean,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct SubCommandArgs {
+    /// proposal nevent, note, hex id, shorthand id or `pr/` branch name
+    pub(crate) proposal: String,
+    /// branch or commit to checkout and apply the patches onto. defaults to
+    /// the current branch
+    #[arg(long)]
+    pub(crate) base: Option<String>,
+    /// fall back to a three-way merge for patches that don't apply cleanly
+    #[arg(long = "3way", action)]
+    pub(crate) three_way: bool,
+}
+
+pub async fn launch(args: &SubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let client = Client::default();
+
+    let repo_coordinates = get_repo_coordinates_when_remote_unknown(&git_repo, &client).await?;
+
+    fetching_with_report(git_repo_path, &client, &repo_coordinates).await?;
+
+    let repo_ref = get_repo_ref_from_cache(Some(git_repo_path), &repo_coordinates).await?;
+
+    let proposal =
+        get_proposal_from_cache_by_reference(git_repo_path, &repo_ref, &args.proposal).await?;
+
+    let patches = get_most_recent_patch_with_ancestors(
+        get_all_proposal_patch_events_from_cache(git_repo_path, &repo_ref, &proposal.id).await?,
+    )
+    .context("failed to find any patches on this proposal")?;
+
+    if let Some(base) = &args.base {
+        check_clean(&git_repo)?;
+        git_repo
+            .checkout(base)
+            .context(format!("failed to checkout base '{base}'"))?;
+    }
+
+    apply_patches_to_current_branch(&git_repo, &repo_ref, patches, args.three_way)
+}
+
+/// applies patches (newest first, as returned by
+/// `get_most_recent_patch_with_ancestors`) as new commits on top of HEAD
+pub(crate) fn apply_patches_to_current_branch(
+    git_repo: &Repo,
+    repo_ref: &RepoRef,
+    mut patches: Vec<nostr::Event>,
+    three_way: bool,
+) -> Result<()> {
+    check_clean(git_repo)?;
+    patches.reverse();
+    let total = patches.len();
+    println!(
+        "applying {total} patch{} to current branch",
+        if total == 1 { "" } else { "es" }
+    );
+    for (i, patch) in patches.iter().enumerate() {
+        let title = commit_msg_from_patch_oneliner(patch).unwrap_or_default();
+        if let Err(error) = git_repo.apply_patch_to_head(patch, three_way) {
+            let nevent = Nip19Event {
+                event_id: patch.id,
+                relays: repo_ref.relays.first().cloned().into_iter().collect(),
+                author: Some(patch.pubkey),
+                kind: Some(patch.kind),
+            }
+            .to_bech32()?;
+            if i > 0 {
+                println!("{i} patches were applied before this one and remain on the branch");
+            }
+            if !three_way {
+                println!("try again with --3way to fall back to a three-way merge");
+            }
+            return Err(error.context(format!(
+                "failed to apply patch {}/{total} '{title}' {nevent}",
+                i + 1
+            )));
+        }
+        println!("applied {}/{total} '{title}'", i + 1);
+    }
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result, bail};
+
+use crate::{
+    client,
+    client::{
+        Client, Connect, fetching_with_report, get_all_proposal_patch_events_from_cache,
+        get_proposal_from_cache_by_reference, get_repo_ref_from_cache,
+    },
+    git::{Repo, RepoActions, str_to_commit_id},
+    git_events::{
+        event_to_cover_letter, get_commit_id_from_patch, get_most_recent_patch_with_ancestors,
+        patch_supports_commit_ids, tag_value,
+    },
+    repo_ref::get_repo_coordinates_when_remote_unknown,
+    sub_commands::list::check_clean,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct SubCommandArgs {
+    /// proposal nevent, note, hex id, shorthand id or `pr/` branch name
+    pub(crate) proposal: String,
+    /// discard unpublished changes on the local proposal branch and reset it
+    /// to the latest revision
+    #[arg(long, action, conflicts_with = "rebase")]
+    pub(crate) force: bool,
+    /// replay unpublished commits on the local proposal branch on top of the
+    /// latest revision
+    #[arg(long, action)]
+    pub(crate) rebase: bool,
+}
+
+#[allow(clippy::too_many_lines)]
+pub async fn launch(args: &SubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let client = Client::default();
+
+    let repo_coordinates = get_repo_coordinates_when_remote_unknown(&git_repo, &client).await?;
+
+    fetching_with_report(git_repo_path, &client, &repo_coordinates).await?;
+
+    let repo_ref = get_repo_ref_from_cache(Some(git_repo_path), &repo_coordinates).await?;
+
+    let proposal =
+        get_proposal_from_cache_by_reference(git_repo_path, &repo_ref, &args.proposal).await?;
+
+    let cover_letter = event_to_cover_letter(&proposal)
+        .context("failed to extract proposal details from proposal root event")?;
+    let branch_name = cover_letter.get_branch_name_with_pr_prefix_and_shorthand_id()?;
+
+    let commits_events: Vec<nostr::Event> =
+        get_all_proposal_patch_events_from_cache(git_repo_path, &repo_ref, &proposal.id).await?;
+
+    let most_recent_proposal_patch_chain =
+        get_most_recent_patch_with_ancestors(commits_events.clone())
+            .context("failed to find any patches on this proposal")?;
+
+    if most_recent_proposal_patch_chain
+        .iter()
+        .any(|event| !patch_supports_commit_ids(event))
+    {
+        bail!(
+            "'{}' is a 'patch only' proposal and cannot be checked out as a branch. use `ngit apply` to apply it to the current branch instead",
+            cover_letter.title
+        );
+    }
+
+    check_clean(&git_repo)?;
+
+    let proposal_base_commit = str_to_commit_id(&tag_value(
+        most_recent_proposal_patch_chain
+            .last()
+            .context("there should be at least one patch as we have already checked for this")?,
+        "parent-commit",
+    )?)
+    .context("failed to get valid parent commit id from patch")?;
+
+    let (main_branch_name, master_tip) = git_repo.get_main_or_master_branch()?;
+
+    if !git_repo.does_commit_exist(&proposal_base_commit.to_string())? {
+        bail!(
+            "the proposal parent commit doesnt exist in your local repository. your '{main_branch_name}' branch may not be up-to-date. run `git pull` and try again."
+        );
+    }
+
+    let proposal_tip =
+        str_to_commit_id(
+            &get_commit_id_from_patch(most_recent_proposal_patch_chain.first().context(
+                "there should be at least one patch as we have already checked for this",
+            )?)
+            .context("failed to get valid commit_id from patch")?,
+        )
+        .context("failed to get valid commit_id from patch")?;
+
+    let (_, proposal_behind_main) =
+        git_repo.get_commits_ahead_behind(&master_tip, &proposal_base_commit)?;
+    let chain_length = most_recent_proposal_patch_chain.len();
+
+    let branch_exists = git_repo
+        .get_local_branch_names()
+        .context("gitlib2 will not show a list of local branch names")?
+        .contains(&branch_name);
+
+    // branch doesnt exist
+    if !branch_exists {
+        git_repo
+            .apply_patch_chain(&branch_name, most_recent_proposal_patch_chain)
+            .context("failed to apply patch chain")?;
+        println!(
+            "checked out proposal as '{branch_name}' branch ({chain_length} ahead {} behind '{main_branch_name}')",
+            proposal_behind_main.len(),
+        );
+        return Ok(());
+    }
+
+    let local_branch_tip = git_repo.get_tip_of_branch(&branch_name)?;
+
+    // up-to-date
+    if proposal_tip.eq(&local_branch_tip) {
+        git_repo.checkout(&branch_name)?;
+        println!(
+            "checked out up-to-date proposal branch '{branch_name}' ({chain_length} ahead {} behind '{main_branch_name}')",
+            proposal_behind_main.len(),
+        );
+        return Ok(());
+    }
+
+    let (local_ahead_of_main, local_behind_main) =
+        git_repo.get_commits_ahead_behind(&master_tip, &local_branch_tip)?;
+
+    // new appendments to proposal
+    if let Some(index) = most_recent_proposal_patch_chain.iter().position(|patch| {
+        get_commit_id_from_patch(patch)
+            .unwrap_or_default()
+            .eq(&local_branch_tip.to_string())
+    }) {
+        git_repo.checkout(&branch_name)?;
+        git_repo
+            .apply_patch_chain(&branch_name, most_recent_proposal_patch_chain)
+            .context("failed to apply patch chain")?;
+        println!(
+            "checked out proposal branch '{branch_name}' and applied {index} appendments ({chain_length} ahead {} behind '{main_branch_name}')",
+            proposal_behind_main.len(),
+        );
+        return Ok(());
+    }
+
+    let published_commit_ids: HashSet<String> = commits_events
+        .iter()
+        .filter_map(|patch| get_commit_id_from_patch(patch).ok())
+        .collect();
+
+    let unpublished_changes =
+        // tip of local in proposal history (new, amended or rebased version but no
+        // local changes)
+        if published_commit_ids.contains(&local_branch_tip.to_string()) {
+            false
+        }
+        // tip of proposal in branch in history (local appendments made to
+        // up-to-date proposal)
+        else if git_repo.ancestor_of(&local_branch_tip, &proposal_tip)? {
+            let (local_ahead_of_proposal, _) = git_repo
+                .get_commits_ahead_behind(&proposal_tip, &local_branch_tip)
+                .context("failed to get commits ahead behind for proposal tip and local branch tip")?;
+            if !args.force {
+                git_repo.checkout(&branch_name)?;
+                println!(
+                    "checked out proposal branch '{branch_name}' with {} unpublished commits on top of the latest revision ({} ahead {} behind '{main_branch_name}')",
+                    local_ahead_of_proposal.len(),
+                    local_ahead_of_main.len(),
+                    local_behind_main.len(),
+                );
+                return Ok(());
+            }
+            true
+        } else {
+            true
+        };
+
+    if unpublished_changes && args.rebase {
+        // the most recent commit in the local branch that has been published as
+        // part of any revision of the proposal
+        let Some(upstream) = local_ahead_of_main
+            .iter()
+            .find(|commit| published_commit_ids.contains(&commit.to_string()))
+        else {
+            bail!(
+                "cannot rebase as no published proposal commits were found in local branch '{branch_name}'. use --force to discard your local changes or rebase manually"
+            );
+        };
+        git_repo.checkout(&branch_name)?;
+        let (unpublished_commits, _) =
+            git_repo.get_commits_ahead_behind(upstream, &local_branch_tip)?;
+        git_repo
+            .rebase_branch_onto(&branch_name, upstream, &proposal_tip)
+            .context(format!(
+                "failed to rebase unpublished commits onto the latest revision. local branch '{branch_name}' is unchanged"
+            ))?;
+        println!(
+            "checked out latest revision of proposal as '{branch_name}' and rebased {} unpublished commits on top",
+            unpublished_commits.len(),
+        );
+        return Ok(());
+    }
+
+    if unpublished_changes && !args.force {
+        // if tip of proposal commits exist (were once part of branch but have been
+        // amended and git clean up job hasn't removed them)
+        if git_repo.does_commit_exist(&proposal_tip.to_string())? {
+            println!(
+                "you have previously applied the latest version of the proposal ({chain_length} ahead {} behind '{main_branch_name}') but your local proposal branch has amended or rebased it ({} ahead {} behind '{main_branch_name}')",
+                proposal_behind_main.len(),
+                local_ahead_of_main.len(),
+                local_behind_main.len(),
+            );
+        } else {
+            println!(
+                "your local proposal branch ({} ahead {} behind '{main_branch_name}') has conflicting changes with the latest published proposal ({chain_length} ahead {} behind '{main_branch_name}')",
+                local_ahead_of_main.len(),
+                local_behind_main.len(),
+                proposal_behind_main.len(),
+            );
+        }
+        bail!(
+            "local branch '{branch_name}' has unpublished changes. use --rebase to replay them on top of the latest revision or --force to discard them"
+        );
+    }
+
+    git_repo.create_branch_at_commit(&branch_name, &proposal_base_commit.to_string())?;
+    git_repo.checkout(&branch_name)?;
+    git_repo
+        .apply_patch_chain(&branch_name, most_recent_proposal_patch_chain)
+        .context("failed to apply patch chain")?;
+    println!(
+        "checked out latest version of proposal as '{branch_name}' ({chain_length} ahead {} behind '{main_branch_name}'), replacing {} version ({} ahead {} behind '{main_branch_name}')",
+        proposal_behind_main.len(),
+        if unpublished_changes {
+            "unpublished"
+        } else {
+            "old"
+        },
+        local_ahead_of_main.len(),
+        local_behind_main.len(),
+    );
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use console::Style;
+use nostr::{ToBech32, nips::nip10::Marker};
+use nostr_sdk::Kind;
+
+use crate::{
+    cli::{Cli, extract_signer_cli_arguments},
+    cli_interactor::{Interactor, InteractorPrompt, PromptInputParms},
+    client,
+    client::{
+        Client, Connect, fetching_with_report, get_comments_from_cache,
+        get_events_from_local_cache, get_repo_ref_from_cache, send_events,
+    },
+    git::{Repo, RepoActions},
+    git_events::{
+        commit_msg_from_patch_oneliner, event_is_patch_set_root, event_is_revision_root,
+        event_tag_from_nip19_or_hex, generate_comment_event, get_comment_parent_id,
+        get_comment_root_id, get_event_root, get_issue_title,
+    },
+    login::{self, user::get_user_ref_from_cache},
+    repo_ref::get_repo_coordinates_when_remote_unknown,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct SubCommandArgs {
+    /// proposal, patch or comment to reply to as nevent, note or hex id
+    pub(crate) reference: String,
+    /// comment text
+    #[clap(short, long)]
+    pub(crate) message: Option<String>,
+}
+
+pub async fn launch(cli_args: &Cli, args: &SubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let mut client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let mut client = Client::default();
+
+    let repo_coordinates = get_repo_coordinates_when_remote_unknown(&git_repo, &client).await?;
+
+    fetching_with_report(git_repo_path, &client, &repo_coordinates).await?;
+
+    let repo_ref = get_repo_ref_from_cache(Some(git_repo_path), &repo_coordinates).await?;
+
+    let target = get_event_by_reference(git_repo_path, &args.reference).await?;
+
+    let (root, parent) = get_thread_root_and_parent(git_repo_path, target).await?;
+
+    if let Some(parent) = &parent {
+        println!(
+            "replying to {} in '{}'",
+            if parent.kind.eq(&Kind::Comment) {
+                "comment".to_string()
+            } else {
+                format!(
+                    "patch '{}'",
+                    commit_msg_from_patch_oneliner(parent).unwrap_or_default()
+                )
+            },
+            thread_title(&root),
+        );
+    } else {
+        println!("commenting on '{}'", thread_title(&root));
+    }
+
+    let message = if let Some(message) = &args.message {
+        message.clone()
+    } else {
+        Interactor::default().input(PromptInputParms::default().with_prompt("comment"))?
+    };
+    if message.trim().is_empty() {
+        bail!("comment cannot be empty");
+    }
+
+    let (signer, user_ref, _) = login::login_or_signup(
+        &Some(&git_repo),
+        &extract_signer_cli_arguments(cli_args).unwrap_or(None),
+        &cli_args.password,
+        Some(&client),
+        true,
+    )
+    .await?;
+
+    client.set_signer(signer.clone()).await;
+
+    let event =
+        generate_comment_event(&signer, &repo_ref, &root, parent.as_ref(), &message).await?;
+
+    println!("posting comment...");
+
+    send_events(
+        &client,
+        Some(git_repo_path),
+        vec![event],
+        user_ref.relays.write(),
+        repo_ref.relays.clone(),
+        !cli_args.disable_cli_spinners,
+        false,
+    )
+    .await
+}
+
+async fn get_event_by_reference(git_repo_path: &Path, reference: &str) -> Result<nostr::Event> {
+    let tag =
+        event_tag_from_nip19_or_hex(reference, "proposal or patch", Marker::Root, false, false)?;
+    let Some(nostr_sdk::TagStandard::Event { event_id, .. }) = tag.as_standardized() else {
+        bail!("not a valid proposal, patch or comment event reference");
+    };
+    get_events_from_local_cache(git_repo_path, vec![nostr::Filter::new().id(*event_id)])
+        .await?
+        .first()
+        .cloned()
+        .context(format!(
+            "failed to find {reference} in cache. is it a proposal or patch for this repository?"
+        ))
+}
+
+/// a comment's thread root is always the proposal or revision root. replies
+/// to individual patches or other comments reference them as the parent.
+async fn get_thread_root_and_parent(
+    git_repo_path: &Path,
+    target: nostr::Event,
+) -> Result<(nostr::Event, Option<nostr::Event>)> {
+    let root_id = if event_is_patch_set_root(&target) || target.kind.eq(&Kind::GitIssue) {
+        return Ok((target, None));
+    } else if target.kind.eq(&Kind::GitPatch) {
+        get_event_root(&target)?
+    } else if target.kind.eq(&Kind::Comment) {
+        get_comment_root_id(&target)?
+    } else {
+        bail!("can only comment on proposals, patches, issues and existing comments");
+    };
+    let root = get_events_from_local_cache(git_repo_path, vec![nostr::Filter::new().id(root_id)])
+        .await?
+        .first()
+        .cloned()
+        .context("failed to find thread root in cache")?;
+    Ok((root, Some(target)))
+}
+
+fn thread_title(root: &nostr::Event) -> String {
+    if root.kind.eq(&Kind::GitIssue) {
+        get_issue_title(root)
+    } else {
+        commit_msg_from_patch_oneliner(root).unwrap_or_default()
+    }
+}
+
+/// print comments made on the proposal, its revisions or its patches
+pub async fn print_comment_thread(
+    git_repo_path: &Path,
+    proposal: &nostr::Event,
+    patches: &[nostr::Event],
+) -> Result<()> {
+    let root_ids = [
+        vec![proposal.id],
+        patches
+            .iter()
+            .filter(|e| event_is_revision_root(e))
+            .map(|e| e.id)
+            .collect(),
+    ]
+    .concat();
+    let comments = get_comments_from_cache(git_repo_path, root_ids.clone()).await?;
+    if comments.is_empty() {
+        return Ok(());
+    }
+    let dim = Style::new().color256(247);
+    println!(
+        "{} comment{}:",
+        comments.len(),
+        if comments.len() > 1 { "s" } else { "" }
+    );
+    for comment in &comments {
+        let author = if let Ok(user_ref) =
+            get_user_ref_from_cache(Some(git_repo_path), &comment.pubkey).await
+        {
+            user_ref.metadata.name
+        } else {
+            comment
+                .pubkey
+                .to_bech32()?
+                .chars()
+                .take(12)
+                .collect::<String>()
+        };
+        let context = if let Ok(parent_id) = get_comment_parent_id(comment) {
+            if root_ids.contains(&parent_id) {
+                String::new()
+            } else if let Some(patch) = patches.iter().find(|p| p.id.eq(&parent_id)) {
+                format!(
+                    " on '{}'",
+                    commit_msg_from_patch_oneliner(patch).unwrap_or_default()
+                )
+            } else {
+                format!(" replying to {}", &parent_id.to_hex()[..8])
+            }
+        } else {
+            String::new()
+        };
+        println!(
+            "  {}",
+            dim.apply_to(format!("{} {author}{context}", &comment.id.to_hex()[..8]))
+        );
+        for line in comment.content.trim().lines() {
+            println!("    {line}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{Context, Result, bail};
+use nostr_sdk::RelayUrl;
+
+use crate::{
+    cli::{Cli, extract_signer_cli_arguments},
+    cli_interactor::{Interactor, InteractorPrompt, PromptInputParms},
+    client,
+    client::{Client, Connect, fetching_with_report, get_repo_ref_from_cache, send_events},
+    git::{Repo, RepoActions, nostr_url::NostrUrlDecoded},
+    login,
+    repo_ref::RepoRef,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct SubCommandArgs {
+    /// nostr:// url of the repository to fork
+    pub(crate) url: String,
+    /// directory to clone into. defaults to the repository identifier
+    pub(crate) directory: Option<String>,
+    #[clap(short, long)]
+    /// shortname of the fork. defaults to the upstream identifier
+    pub(crate) identifier: Option<String>,
+    #[clap(short, long)]
+    /// name of the fork. defaults to the upstream name
+    pub(crate) title: Option<String>,
+    #[clap(long)]
+    /// git server url users can clone the fork from. can be used multiple
+    /// times
+    pub(crate) clone_url: Vec<String>,
+    #[clap(short, long, value_parser, num_args = 1..)]
+    /// relays for the fork. defaults to the upstream relays
+    pub(crate) relays: Vec<String>,
+}
+
+pub async fn launch(cli_args: &Cli, args: &SubCommandArgs) -> Result<()> {
+    let upstream_url = NostrUrlDecoded::parse_and_resolve(&args.url, &None)
+        .await
+        .context(format!("{} is not a valid nostr git url", args.url))?;
+
+    let directory = PathBuf::from(
+        args.directory
+            .clone()
+            .unwrap_or(upstream_url.coordinate.identifier.clone()),
+    );
+    if directory.exists() {
+        bail!("destination path {} already exists", directory.display());
+    }
+
+    println!("cloning upstream repository...");
+    // libgit2 doesn't support remote helpers so git is used to clone via
+    // git-remote-nostr
+    let status = std::process::Command::new("git")
+        .arg("clone")
+        .arg(&args.url)
+        .arg(&directory)
+        .status()
+        .context("failed to run git clone")?;
+    if !status.success() {
+        bail!("failed to clone {}", args.url);
+    }
+
+    let git_repo = Repo::from_path(&directory).context(format!(
+        "failed to open cloned repository at {}",
+        directory.display()
+    ))?;
+
+    // don't leave a clone behind if the fork can't be announced
+    let (upstream, fork) = match announce_fork(cli_args, args, &git_repo, &upstream_url).await {
+        Ok(res) => res,
+        Err(error) => {
+            drop(git_repo);
+            let _ = std::fs::remove_dir_all(&directory);
+            return Err(error);
+        }
+    };
+
+    git_repo
+        .git_repo
+        .remote_rename("origin", "upstream")
+        .context("failed to rename origin remote to upstream")?;
+    let fork_url = fork.to_nostr_git_url(&Some(&git_repo)).to_string();
+    git_repo
+        .git_repo
+        .remote("origin", &fork_url)
+        .context("failed to add fork as origin remote")?;
+
+    println!("forked into {}", directory.display());
+    println!("origin: {fork_url}");
+    println!("upstream: {}", upstream.to_nostr_git_url(&Some(&git_repo)));
+    println!(
+        "push your branches with `git push origin` and send them upstream as PRs with `ngit send`, selecting the upstream repository"
+    );
+    Ok(())
+}
+
+/// returns the upstream and the fork once its announcement is published
+async fn announce_fork(
+    cli_args: &Cli,
+    args: &SubCommandArgs,
+    git_repo: &Repo,
+    upstream_url: &NostrUrlDecoded,
+) -> Result<(RepoRef, RepoRef)> {
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let mut client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let mut client = Client::default();
+
+    fetching_with_report(git_repo_path, &client, &upstream_url.coordinate).await?;
+    let upstream = get_repo_ref_from_cache(Some(git_repo_path), &upstream_url.coordinate).await?;
+
+    let (signer, user_ref, _) = login::login_or_signup(
+        &Some(git_repo),
+        &extract_signer_cli_arguments(cli_args).unwrap_or(None),
+        &cli_args.password,
+        Some(&client),
+        true,
+    )
+    .await?;
+
+    if upstream.maintainers.contains(&user_ref.public_key) {
+        bail!(
+            "you are already a maintainer of {}. push to it directly instead of forking",
+            upstream.identifier
+        );
+    }
+
+    let git_server = if args.clone_url.is_empty() {
+        println!(
+            "your fork needs a git server to store its git objects. only you need write access to it."
+        );
+        Interactor::default()
+            .input(
+                PromptInputParms::default()
+                    .with_prompt("git server remote url(s) for your fork (space seperated)"),
+            )?
+            .split(' ')
+            .filter(|url| !url.is_empty())
+            .map(std::string::ToString::to_string)
+            .collect()
+    } else {
+        args.clone_url.clone()
+    };
+
+    let relays = if args.relays.is_empty() {
+        upstream.relays.clone()
+    } else {
+        args.relays
+            .iter()
+            .map(|r| RelayUrl::parse(r).context(format!("{r} is not a valid relay url")))
+            .collect::<Result<Vec<RelayUrl>>>()?
+    };
+
+    // commits on top of the upstream tip are unique to the fork
+    let earliest_unique_commit = git_repo
+        .get_head_commit()
+        .context("failed to get the tip of the cloned repository")?
+        .to_string();
+
+    let fork = RepoRef {
+        identifier: args
+            .identifier
+            .clone()
+            .unwrap_or(upstream.identifier.clone()),
+        name: args.title.clone().unwrap_or(upstream.name.clone()),
+        description: upstream.description.clone(),
+        root_commit: earliest_unique_commit,
+        git_server,
+        web: vec![],
+        relays,
+        maintainers: vec![user_ref.public_key],
+        trusted_maintainer: user_ref.public_key,
+        events: HashMap::new(),
+        nostr_git_url: None,
+        fork_of: Some(upstream.coordinate_with_hint()),
+        archived: false,
+        moved_to: None,
+    };
+    let repo_event = fork.to_event(&signer).await?;
+
+    client.set_signer(signer).await;
+
+    println!("publishing fork announcement...");
+
+    send_events(
+        &client,
+        Some(git_repo_path),
+        vec![repo_event],
+        user_ref.relays.write(),
+        fork.relays.clone(),
+        !cli_args.disable_cli_spinners,
+        false,
+    )
+    .await?;
+
+    Ok((upstream, fork))
+}
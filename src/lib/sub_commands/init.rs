@@ -20,7 +20,7 @@ use crate::{
         Client, Connect, MockClient, fetching_with_report, get_repo_ref_from_cache, send_events,
     },
     git::{
-        Repo, RepoActions,
+        CommitId, Repo, RepoActions,
         nostr_url::{NostrUrlDecoded, convert_clone_url_to_https, save_nip05_to_git_config_cache},
     },
     login,
@@ -38,7 +38,8 @@ pub struct SubCommandArgs {
     /// optional description
     description: Option<String>,
     #[clap(long)]
-    /// git server url users can clone from
+    /// git server url users can clone from. use blossom://<host> to store git
+    /// objects on a blossom blob server
     clone_url: Vec<String>,
     #[clap(short, long, value_parser, num_args = 1..)]
     /// homepage
@@ -430,8 +431,8 @@ pub async fn launch(cli_args: &Cli, args: &SubCommandArgs) -> Result<()> {
             } else {
                 println!("commit id not formatted correctly");
             }
-            if earliest_unique_commit.len().ne(&40) {
-                println!("commit id must be 40 characters long");
+            if !CommitId::is_valid(&earliest_unique_commit) {
+                println!("commit id must be 40 (sha1) or 64 (sha256) characters long");
             }
         }
     };
@@ -450,6 +451,13 @@ pub async fn launch(cli_args: &Cli, args: &SubCommandArgs) -> Result<()> {
         maintainers: maintainers.clone(),
         events: HashMap::new(),
         nostr_git_url: None,
+        fork_of: repo_ref
+            .as_ref()
+            .and_then(|repo_ref| repo_ref.fork_of.clone()),
+        archived: repo_ref.as_ref().is_some_and(|repo_ref| repo_ref.archived),
+        moved_to: repo_ref
+            .as_ref()
+            .and_then(|repo_ref| repo_ref.moved_to.clone()),
     };
     let repo_event = repo_ref.to_event(&signer).await?;
 
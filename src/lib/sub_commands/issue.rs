@@ -0,0 +1,485 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use console::Style;
+use nostr::{
+    ToBech32,
+    nips::{nip10::Marker, nip19::Nip19Event},
+};
+use nostr_sdk::Kind;
+
+use crate::{
+    cli::{Cli, extract_signer_cli_arguments},
+    cli_interactor::{Interactor, InteractorPrompt, PromptInputParms},
+    client,
+    client::{
+        Client, Connect, fetching_with_report, get_comments_from_cache,
+        get_events_from_local_cache, get_issues_from_cache, get_repo_ref_from_cache, send_events,
+    },
+    git::{Repo, RepoActions},
+    git_events::{
+        event_tag_from_nip19_or_hex, generate_comment_event, generate_issue_event,
+        generate_status_event, get_comment_parent_id, get_issue_labels, get_issue_title,
+        get_status_from_events, status_kinds,
+    },
+    login::{self, user::get_display_name},
+    repo_ref::{RepoRef, get_repo_coordinates_when_remote_unknown},
+};
+
+#[derive(Debug, clap::Args)]
+pub struct NewSubCommandArgs {
+    /// issue title
+    #[clap(short, long)]
+    pub(crate) title: Option<String>,
+    /// issue description
+    #[clap(short, long)]
+    pub(crate) description: Option<String>,
+    /// label to apply. can be used multiple times
+    #[clap(short, long)]
+    pub(crate) label: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ListSubCommandArgs {
+    /// include closed issues
+    #[arg(long, action)]
+    pub(crate) all: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct IssueRefSubCommandArgs {
+    /// issue nevent, note, hex id or shorthand id shown by `ngit issue list`
+    pub(crate) id: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct CommentSubCommandArgs {
+    /// issue nevent, note, hex id or shorthand id shown by `ngit issue list`
+    pub(crate) id: String,
+    /// comment text
+    #[clap(short, long)]
+    pub(crate) message: Option<String>,
+    /// reply to an existing comment rather than the issue itself
+    #[clap(long)]
+    pub(crate) in_reply_to: Option<String>,
+}
+
+pub async fn launch_new(cli_args: &Cli, args: &NewSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let mut client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let mut client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    let title = if let Some(title) = &args.title {
+        title.clone()
+    } else {
+        Interactor::default().input(PromptInputParms::default().with_prompt("title"))?
+    };
+    if title.trim().is_empty() {
+        bail!("issue title cannot be empty");
+    }
+    let description = if let Some(description) = &args.description {
+        description.clone()
+    } else {
+        Interactor::default().input(
+            PromptInputParms::default()
+                .with_prompt("description")
+                .optional(),
+        )?
+    };
+
+    let (signer, user_ref, _) = login::login_or_signup(
+        &Some(&git_repo),
+        &extract_signer_cli_arguments(cli_args).unwrap_or(None),
+        &cli_args.password,
+        Some(&client),
+        true,
+    )
+    .await?;
+
+    client.set_signer(signer.clone()).await;
+
+    let event = generate_issue_event(&signer, &repo_ref, &title, &description, &args.label).await?;
+
+    println!("posting issue...");
+
+    send_events(
+        &client,
+        Some(git_repo_path),
+        vec![event.clone()],
+        user_ref.relays.write(),
+        repo_ref.relays.clone(),
+        !cli_args.disable_cli_spinners,
+        false,
+    )
+    .await?;
+
+    let event_bech32 = if let Some(relay) = repo_ref.relays.first() {
+        Nip19Event {
+            event_id: event.id,
+            relays: vec![relay.clone()],
+            author: None,
+            kind: None,
+        }
+        .to_bech32()?
+    } else {
+        event.id.to_bech32()?
+    };
+    let dim = Style::new().color256(247);
+    println!(
+        "{}",
+        dim.apply_to(format!(
+            "view in gitworkshop.dev: https://gitworkshop.dev/repo/{}/issues/{}",
+            repo_ref.coordinate_with_hint().to_bech32()?,
+            &event_bech32,
+        ))
+    );
+    println!(
+        "{}",
+        dim.apply_to(format!(
+            "view in another client:  https://njump.me/{}",
+            &event_bech32,
+        ))
+    );
+    Ok(())
+}
+
+pub async fn launch_list(args: &ListSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    let issues = get_issues_from_cache(git_repo_path, repo_ref.coordinates()).await?;
+    if issues.is_empty() {
+        println!("no issues found... create one? try `ngit issue new`");
+        return Ok(());
+    }
+
+    let statuses = get_statuses_from_cache(git_repo_path, &issues).await?;
+
+    let dim = Style::new().color256(247);
+    let mut printed = 0;
+    for issue in &issues {
+        let status = get_status_from_events(issue, &statuses, &repo_ref.maintainers);
+        if !args.all && status.eq(&Kind::GitStatusClosed) {
+            continue;
+        }
+        let labels = get_issue_labels(issue);
+        println!(
+            "{} {} {}{} {}",
+            dim.apply_to(&issue.id.to_hex()[..8]),
+            status_name(status),
+            get_issue_title(issue),
+            if labels.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    " {}",
+                    Style::new().magenta().apply_to(
+                        labels
+                            .iter()
+                            .map(|l| format!("[{l}]"))
+                            .collect::<Vec<String>>()
+                            .join(" ")
+                    )
+                )
+            },
+            dim.apply_to(format!(
+                "by {}",
+                get_display_name(Some(git_repo_path), &issue.pubkey).await
+            )),
+        );
+        printed += 1;
+    }
+    if printed == 0 {
+        println!("no open issues. use --all to include closed issues");
+    }
+    Ok(())
+}
+
+pub async fn launch_view(args: &IssueRefSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    let issue = find_issue_in_cache(git_repo_path, &repo_ref, &args.id).await?;
+    let statuses = get_statuses_from_cache(git_repo_path, &[issue.clone()]).await?;
+    let dim = Style::new().color256(247);
+
+    println!("{}", get_issue_title(&issue));
+    println!(
+        "{}",
+        dim.apply_to(format!(
+            "{} opened by {} {}",
+            status_name(get_status_from_events(
+                &issue,
+                &statuses,
+                &repo_ref.maintainers
+            )),
+            get_display_name(Some(git_repo_path), &issue.pubkey).await,
+            issue.created_at.to_human_datetime(),
+        ))
+    );
+    let labels = get_issue_labels(&issue);
+    if !labels.is_empty() {
+        println!("labels: {}", labels.join(", "));
+    }
+    if !issue.content.trim().is_empty() {
+        println!("\n{}", issue.content.trim());
+    }
+
+    let comments = get_comments_from_cache(git_repo_path, vec![issue.id]).await?;
+    for comment in &comments {
+        println!(
+            "\n{}",
+            dim.apply_to(format!(
+                "{} {} {}",
+                &comment.id.to_hex()[..8],
+                get_display_name(Some(git_repo_path), &comment.pubkey).await,
+                if let Ok(parent_id) = get_comment_parent_id(comment) {
+                    if parent_id.eq(&issue.id) {
+                        String::new()
+                    } else {
+                        format!("replying to {}", &parent_id.to_hex()[..8])
+                    }
+                } else {
+                    String::new()
+                },
+            ))
+        );
+        println!("{}", comment.content.trim());
+    }
+    Ok(())
+}
+
+pub async fn launch_comment(cli_args: &Cli, args: &CommentSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let mut client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let mut client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    let issue = find_issue_in_cache(git_repo_path, &repo_ref, &args.id).await?;
+
+    let parent = if let Some(reference) = &args.in_reply_to {
+        let comments = get_comments_from_cache(git_repo_path, vec![issue.id]).await?;
+        Some(
+            if let Some(comment) = find_by_id_prefix(&comments, reference, "comment")? {
+                comment.clone()
+            } else {
+                find_event_by_reference(git_repo_path, reference, "comment")
+                    .await
+                    .context(format!("failed to find comment {reference} in cache"))?
+            },
+        )
+    } else {
+        None
+    };
+
+    let message = if let Some(message) = &args.message {
+        message.clone()
+    } else {
+        Interactor::default().input(PromptInputParms::default().with_prompt("comment"))?
+    };
+    if message.trim().is_empty() {
+        bail!("comment cannot be empty");
+    }
+
+    let (signer, user_ref, _) = login::login_or_signup(
+        &Some(&git_repo),
+        &extract_signer_cli_arguments(cli_args).unwrap_or(None),
+        &cli_args.password,
+        Some(&client),
+        true,
+    )
+    .await?;
+
+    client.set_signer(signer.clone()).await;
+
+    let event =
+        generate_comment_event(&signer, &repo_ref, &issue, parent.as_ref(), &message).await?;
+
+    println!("posting comment...");
+
+    send_events(
+        &client,
+        Some(git_repo_path),
+        vec![event],
+        user_ref.relays.write(),
+        repo_ref.relays.clone(),
+        !cli_args.disable_cli_spinners,
+        false,
+    )
+    .await
+}
+
+pub async fn launch_set_status(
+    cli_args: &Cli,
+    args: &IssueRefSubCommandArgs,
+    status: Kind,
+) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let mut client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let mut client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    let issue = find_issue_in_cache(git_repo_path, &repo_ref, &args.id).await?;
+    let statuses = get_statuses_from_cache(git_repo_path, &[issue.clone()]).await?;
+
+    if get_status_from_events(&issue, &statuses, &repo_ref.maintainers).eq(&status) {
+        println!("issue is already {}", status_name(status));
+        return Ok(());
+    }
+
+    let (signer, user_ref, _) = login::login_or_signup(
+        &Some(&git_repo),
+        &extract_signer_cli_arguments(cli_args).unwrap_or(None),
+        &cli_args.password,
+        Some(&client),
+        true,
+    )
+    .await?;
+
+    if !user_ref.public_key.eq(&issue.pubkey)
+        && !repo_ref.maintainers.contains(&user_ref.public_key)
+    {
+        bail!("only the issue author or a repository maintainer can change its status");
+    }
+
+    client.set_signer(signer.clone()).await;
+
+    let event = generate_status_event(&signer, &repo_ref, status, &issue, vec![]).await?;
+
+    println!("marking issue as {}...", status_name(status));
+
+    send_events(
+        &client,
+        Some(git_repo_path),
+        vec![event],
+        user_ref.relays.write(),
+        repo_ref.relays.clone(),
+        !cli_args.disable_cli_spinners,
+        false,
+    )
+    .await
+}
+
+async fn fetch_and_get_repo_ref(
+    git_repo: &Repo,
+    #[cfg(test)] client: &crate::client::MockConnect,
+    #[cfg(not(test))] client: &Client,
+) -> Result<RepoRef> {
+    let git_repo_path = git_repo.get_path()?;
+    let repo_coordinates = get_repo_coordinates_when_remote_unknown(git_repo, client).await?;
+    fetching_with_report(git_repo_path, client, &repo_coordinates).await?;
+    get_repo_ref_from_cache(Some(git_repo_path), &repo_coordinates).await
+}
+
+async fn find_issue_in_cache(
+    git_repo_path: &Path,
+    repo_ref: &RepoRef,
+    reference: &str,
+) -> Result<nostr::Event> {
+    let issues = get_issues_from_cache(git_repo_path, repo_ref.coordinates()).await?;
+    if let Some(issue) = find_by_id_prefix(&issues, reference, "issue")? {
+        return Ok(issue.clone());
+    }
+    let event = find_event_by_reference(git_repo_path, reference, "issue").await?;
+    if !issues.iter().any(|e| e.id.eq(&event.id)) {
+        bail!("{reference} is not an issue for this repository");
+    }
+    Ok(event)
+}
+
+/// the event whose id starts with `prefix`. prefixes shorter than 4
+/// characters aren't matched
+fn find_by_id_prefix<'a>(
+    events: &'a [nostr::Event],
+    prefix: &str,
+    reference_name: &str,
+) -> Result<Option<&'a nostr::Event>> {
+    if prefix.len() < 4 {
+        return Ok(None);
+    }
+    let prefix = prefix.to_lowercase();
+    let matches: Vec<&nostr::Event> = events
+        .iter()
+        .filter(|e| e.id.to_hex().starts_with(&prefix))
+        .collect();
+    match matches.as_slice() {
+        [] => Ok(None),
+        [event] => Ok(Some(event)),
+        _ => bail!("{prefix} matches more than one {reference_name}. use a longer prefix"),
+    }
+}
+
+async fn find_event_by_reference(
+    git_repo_path: &Path,
+    reference: &str,
+    reference_name: &str,
+) -> Result<nostr::Event> {
+    let tag = event_tag_from_nip19_or_hex(reference, reference_name, Marker::Root, false, false)?;
+    let Some(nostr_sdk::TagStandard::Event { event_id, .. }) = tag.as_standardized() else {
+        bail!("not a valid {reference_name} event reference");
+    };
+    get_events_from_local_cache(git_repo_path, vec![nostr::Filter::new().id(*event_id)])
+        .await?
+        .first()
+        .cloned()
+        .context(format!(
+            "failed to find {reference_name} {reference} in cache"
+        ))
+}
+
+async fn get_statuses_from_cache(
+    git_repo_path: &Path,
+    roots: &[nostr::Event],
+) -> Result<Vec<nostr::Event>> {
+    get_events_from_local_cache(
+        git_repo_path,
+        vec![
+            nostr::Filter::default()
+                .kinds(status_kinds())
+                .events(roots.iter().map(|e| e.id)),
+        ],
+    )
+    .await
+}
+
+fn status_name(status: Kind) -> &'static str {
+    if status.eq(&Kind::GitStatusClosed) {
+        "closed"
+    } else if status.eq(&Kind::GitStatusApplied) {
+        "resolved"
+    } else if status.eq(&Kind::GitStatusDraft) {
+        "draft"
+    } else {
+        "open"
+    }
+}
@@ -1,7 +1,9 @@
 use std::{io::Write, ops::Add};
 
 use anyhow::{Context, Result, bail};
-use nostr_sdk::Kind;
+use nostr::{ToBech32, nips::nip19::Nip19Event};
+use nostr_sdk::{Kind, PublicKey};
+use serde::Serialize;
 
 use crate::{
     cli_interactor::{Interactor, InteractorPrompt, PromptChoiceParms, PromptConfirmParms},
@@ -11,17 +13,78 @@ use crate::{
         get_all_proposal_patch_events_from_cache, get_events_from_local_cache,
         get_proposals_and_revisions_from_cache, get_repo_ref_from_cache,
     },
-    git::{Repo, RepoActions, str_to_sha1},
+    git::{Repo, RepoActions, str_to_commit_id},
     git_events::{
         commit_msg_from_patch_oneliner, event_is_revision_root, event_to_cover_letter,
-        get_commit_id_from_patch, get_most_recent_patch_with_ancestors, patch_supports_commit_ids,
-        status_kinds, tag_value,
+        get_commit_id_from_patch, get_most_recent_patch_with_ancestors, get_status_name,
+        patch_supports_commit_ids, status_kinds, tag_value,
     },
-    repo_ref::get_repo_coordinates_when_remote_unknown,
+    login::user::get_user_ref_from_cache,
+    repo_ref::{RepoRef, get_repo_coordinates_when_remote_unknown},
+    sub_commands::{apply::apply_patches_to_current_branch, comment::print_comment_thread},
 };
 
+#[derive(Debug, clap::Args)]
+pub struct SubCommandArgs {
+    /// only list proposals with this status. prints without prompting
+    #[arg(long, value_enum)]
+    pub(crate) status: Option<ProposalStatus>,
+    /// only list proposals by this author (npub or hex). prints without
+    /// prompting
+    #[arg(long)]
+    pub(crate) author: Option<String>,
+    /// print proposals as json without prompting
+    #[arg(long, action)]
+    pub(crate) json: bool,
+    /// maximum number of proposals to print. prints without prompting
+    #[arg(long)]
+    pub(crate) limit: Option<usize>,
+    /// when applying to the current branch, fall back to a three-way merge
+    /// for patches that don't apply cleanly
+    #[arg(long = "3way", action)]
+    pub(crate) three_way: bool,
+}
+
+impl SubCommandArgs {
+    fn is_non_interactive(&self) -> bool {
+        self.status.is_some() || self.author.is_some() || self.json || self.limit.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProposalStatus {
+    Open,
+    Draft,
+    Closed,
+    Applied,
+}
+
+impl ProposalStatus {
+    fn kind(self) -> Kind {
+        match self {
+            ProposalStatus::Open => Kind::GitStatusOpen,
+            ProposalStatus::Draft => Kind::GitStatusDraft,
+            ProposalStatus::Closed => Kind::GitStatusClosed,
+            ProposalStatus::Applied => Kind::GitStatusApplied,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ProposalSummary {
+    id: String,
+    nevent: String,
+    title: String,
+    author: String,
+    author_name: Option<String>,
+    status: String,
+    commit_count: usize,
+    branch_name: Option<String>,
+    created_at: u64,
+}
+
 #[allow(clippy::too_many_lines)]
-pub async fn launch() -> Result<()> {
+pub async fn launch(args: &SubCommandArgs) -> Result<()> {
     let git_repo = Repo::discover().context("failed to find a git repository")?;
     let git_repo_path = git_repo.get_path()?;
 
@@ -44,7 +107,11 @@ pub async fn launch() -> Result<()> {
     let proposals_and_revisions: Vec<nostr::Event> =
         get_proposals_and_revisions_from_cache(git_repo_path, repo_ref.coordinates()).await?;
     if proposals_and_revisions.is_empty() {
-        println!("no proposals found... create one? try `ngit send`");
+        if args.json {
+            println!("[]");
+        } else {
+            println!("no proposals found... create one? try `ngit send`");
+        }
         return Ok(());
     }
 
@@ -74,22 +141,12 @@ pub async fn launch() -> Result<()> {
         .cloned()
         .collect();
 
+    if args.is_non_interactive() {
+        return print_proposals(git_repo_path, &repo_ref, &proposals, &statuses, args).await;
+    }
+
     for proposal in &proposals {
-        let status = if let Some(e) = statuses
-            .iter()
-            .filter(|e| {
-                status_kinds().contains(&e.kind)
-                    && e.tags.iter().any(|t| {
-                        t.as_slice().len() > 1 && t.as_slice()[1].eq(&proposal.id.to_string())
-                    })
-            })
-            .collect::<Vec<&nostr::Event>>()
-            .first()
-        {
-            e.kind
-        } else {
-            Kind::GitStatusOpen
-        };
+        let status = get_proposal_status(proposal, &statuses);
         if status.eq(&Kind::GitStatusOpen) {
             open_proposals.push(proposal);
         } else if status.eq(&Kind::GitStatusClosed) {
@@ -205,6 +262,13 @@ pub async fn launch() -> Result<()> {
             }
             return Ok(());
         };
+
+        print_comment_thread(
+            git_repo_path,
+            proposals_for_status[selected_index],
+            &commits_events,
+        )
+        .await?;
         // for commit in &most_recent_proposal_patch_chain {
         //     println!("recent_event: {:?}", commit.as_json());
         // }
@@ -227,7 +291,7 @@ pub async fn launch() -> Result<()> {
                     .with_default(0)
                     .with_choices(vec![
                         "learn why 'patch only' proposals can't be checked out".to_string(),
-                        format!("apply to current branch with `git am`"),
+                        format!("apply to current branch"),
                         format!("download to ./patches"),
                         "back".to_string(),
                     ]),
@@ -253,7 +317,12 @@ pub async fn launch() -> Result<()> {
                     )?;
                     continue;
                 }
-                1 => launch_git_am_with_patches(most_recent_proposal_patch_chain),
+                1 => apply_patches_to_current_branch(
+                    &git_repo,
+                    &repo_ref,
+                    most_recent_proposal_patch_chain,
+                    args.three_way,
+                ),
                 2 => save_patches_to_dir(most_recent_proposal_patch_chain, &git_repo),
                 3 => continue,
                 _ => {
@@ -276,7 +345,7 @@ pub async fn launch() -> Result<()> {
             .get_checked_out_branch_name()?
             .eq(&cover_letter.get_branch_name_with_pr_prefix_and_shorthand_id()?);
 
-        let proposal_base_commit = str_to_sha1(&tag_value(
+        let proposal_base_commit = str_to_commit_id(&tag_value(
             most_recent_proposal_patch_chain.last().context(
                 "there should be at least one patch as we have already checked for this",
             )?,
@@ -294,13 +363,18 @@ pub async fn launch() -> Result<()> {
                     format!(
                         "manually run `git pull` on '{main_branch_name}' and select proposal again"
                     ),
-                    format!("apply to current branch with `git am`"),
+                    format!("apply to current branch"),
                     format!("download to ./patches"),
                     "back".to_string(),
                 ],
             ))? {
                 0 | 3 => continue,
-                1 => launch_git_am_with_patches(most_recent_proposal_patch_chain),
+                1 => apply_patches_to_current_branch(
+                    &git_repo,
+                    &repo_ref,
+                    most_recent_proposal_patch_chain,
+                    args.three_way,
+                ),
                 2 => save_patches_to_dir(most_recent_proposal_patch_chain, &git_repo),
                 _ => {
                     bail!("unexpected choice")
@@ -308,7 +382,7 @@ pub async fn launch() -> Result<()> {
             };
         }
 
-        let proposal_tip = str_to_sha1(
+        let proposal_tip = str_to_commit_id(
             &get_commit_id_from_patch(most_recent_proposal_patch_chain.first().context(
                 "there should be at least one patch as we have already checked for this",
             )?)
@@ -328,7 +402,7 @@ pub async fn launch() -> Result<()> {
                     most_recent_proposal_patch_chain.len(),
                     proposal_behind_main.len(),
                 ),
-                format!("apply to current branch with `git am`"),
+                format!("apply to current branch"),
                 format!("download to ./patches"),
                 "back".to_string(),
             ]))? {
@@ -347,7 +421,12 @@ pub async fn launch() -> Result<()> {
                     );
                     Ok(())
                 }
-                1 => launch_git_am_with_patches(most_recent_proposal_patch_chain),
+                1 => apply_patches_to_current_branch(
+                    &git_repo,
+                    &repo_ref,
+                    most_recent_proposal_patch_chain,
+                    args.three_way,
+                ),
                 2 => save_patches_to_dir(most_recent_proposal_patch_chain, &git_repo),
                 3 => continue,
                 _ => {
@@ -385,7 +464,7 @@ pub async fn launch() -> Result<()> {
                             most_recent_proposal_patch_chain.len(),
                             proposal_behind_main.len(),
                         ),
-                        format!("apply to current branch with `git am`"),
+                        format!("apply to current branch"),
                         format!("download to ./patches"),
                         "back".to_string(),
                     ]),
@@ -401,7 +480,12 @@ pub async fn launch() -> Result<()> {
                     );
                     Ok(())
                 }
-                1 => launch_git_am_with_patches(most_recent_proposal_patch_chain),
+                1 => apply_patches_to_current_branch(
+                    &git_repo,
+                    &repo_ref,
+                    most_recent_proposal_patch_chain,
+                    args.three_way,
+                ),
                 2 => save_patches_to_dir(most_recent_proposal_patch_chain, &git_repo),
                 3 => continue,
                 _ => {
@@ -424,7 +508,7 @@ pub async fn launch() -> Result<()> {
                     .with_default(0)
                     .with_choices(vec![
                         format!("checkout proposal branch and apply {} appendments", &index,),
-                        format!("apply to current branch with `git am`"),
+                        format!("apply to current branch"),
                         format!("download to ./patches"),
                         "back".to_string(),
                     ]),
@@ -448,7 +532,12 @@ pub async fn launch() -> Result<()> {
                     );
                     Ok(())
                 }
-                1 => launch_git_am_with_patches(most_recent_proposal_patch_chain),
+                1 => apply_patches_to_current_branch(
+                    &git_repo,
+                    &repo_ref,
+                    most_recent_proposal_patch_chain,
+                    args.three_way,
+                ),
                 2 => save_patches_to_dir(most_recent_proposal_patch_chain, &git_repo),
                 3 => continue,
                 _ => {
@@ -478,7 +567,7 @@ pub async fn launch() -> Result<()> {
                     .with_choices(vec![
                         format!("checkout and overwrite existing proposal branch"),
                         format!("checkout existing outdated proposal branch"),
-                        format!("apply to current branch with `git am`"),
+                        format!("apply to current branch"),
                         format!("download to ./patches"),
                         "back".to_string(),
                     ]),
@@ -520,7 +609,12 @@ pub async fn launch() -> Result<()> {
                     );
                     Ok(())
                 }
-                2 => launch_git_am_with_patches(most_recent_proposal_patch_chain),
+                2 => apply_patches_to_current_branch(
+                    &git_repo,
+                    &repo_ref,
+                    most_recent_proposal_patch_chain,
+                    args.three_way,
+                ),
                 3 => save_patches_to_dir(most_recent_proposal_patch_chain, &git_repo),
                 4 => continue,
                 _ => {
@@ -617,7 +711,7 @@ pub async fn launch() -> Result<()> {
                 .with_choices(vec![
                     format!("checkout local branch with unpublished changes"),
                     format!("discard unpublished changes and checkout new revision",),
-                    format!("apply to current branch with `git am`"),
+                    format!("apply to current branch"),
                     format!("download to ./patches"),
                     "back".to_string(),
                 ]),
@@ -658,7 +752,12 @@ pub async fn launch() -> Result<()> {
                 );
                 Ok(())
             }
-            2 => launch_git_am_with_patches(most_recent_proposal_patch_chain),
+            2 => apply_patches_to_current_branch(
+                &git_repo,
+                &repo_ref,
+                most_recent_proposal_patch_chain,
+                args.three_way,
+            ),
             3 => save_patches_to_dir(most_recent_proposal_patch_chain, &git_repo),
             4 => continue,
             _ => {
@@ -668,34 +767,112 @@ pub async fn launch() -> Result<()> {
     }
 }
 
-fn launch_git_am_with_patches(mut patches: Vec<nostr::Event>) -> Result<()> {
-    println!("applying to current branch with `git am`");
-    // TODO: add PATCH x/n to appended patches
-    patches.reverse();
+/// statuses must be sorted newest first
+fn get_proposal_status(proposal: &nostr::Event, statuses: &[nostr::Event]) -> Kind {
+    if let Some(e) = statuses.iter().find(|e| {
+        status_kinds().contains(&e.kind)
+            && e.tags
+                .iter()
+                .any(|t| t.as_slice().len() > 1 && t.as_slice()[1].eq(&proposal.id.to_string()))
+    }) {
+        e.kind
+    } else {
+        Kind::GitStatusOpen
+    }
+}
 
-    let mut am = std::process::Command::new("git")
-        .arg("am")
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::inherit())
-        .stderr(std::process::Stdio::inherit())
-        .spawn()
-        .context("failed to spawn git am")?;
-
-    let stdin = am
-        .stdin
-        .as_mut()
-        .context("git am process failed to take stdin")?;
-
-    for patch in patches {
-        stdin
-            .write(format!("{}\n\n", patch.content).as_bytes())
-            .context("failed to write patch content into git am stdin buffer")?;
+async fn print_proposals(
+    git_repo_path: &std::path::Path,
+    repo_ref: &RepoRef,
+    proposals: &[nostr::Event],
+    statuses: &[nostr::Event],
+    args: &SubCommandArgs,
+) -> Result<()> {
+    let author = if let Some(author) = &args.author {
+        Some(PublicKey::parse(author).context(format!("invalid author {author}"))?)
+    } else {
+        None
+    };
+
+    let mut summaries = vec![];
+    for proposal in proposals {
+        if let Some(author) = author {
+            if !proposal.pubkey.eq(&author) {
+                continue;
+            }
+        }
+        let status = get_proposal_status(proposal, statuses);
+        if let Some(selected_status) = args.status {
+            if !status.eq(&selected_status.kind()) {
+                continue;
+            }
+        }
+        if let Some(limit) = args.limit {
+            if summaries.len() >= limit {
+                break;
+            }
+        }
+        let cover_letter = event_to_cover_letter(proposal).ok();
+        let commit_count = if let Ok(commits_events) =
+            get_all_proposal_patch_events_from_cache(git_repo_path, repo_ref, &proposal.id).await
+        {
+            get_most_recent_patch_with_ancestors(commits_events)
+                .map(|chain| chain.len())
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        summaries.push(ProposalSummary {
+            id: proposal.id.to_hex(),
+            nevent: Nip19Event {
+                event_id: proposal.id,
+                relays: repo_ref.relays.first().cloned().into_iter().collect(),
+                author: Some(proposal.pubkey),
+                kind: Some(proposal.kind),
+            }
+            .to_bech32()?,
+            title: if let Some(cl) = &cover_letter {
+                cl.title.clone()
+            } else {
+                commit_msg_from_patch_oneliner(proposal).unwrap_or_default()
+            },
+            author: proposal.pubkey.to_bech32()?,
+            author_name: get_user_ref_from_cache(Some(git_repo_path), &proposal.pubkey)
+                .await
+                .ok()
+                .map(|user_ref| user_ref.metadata.name),
+            status: get_status_name(status).to_string(),
+            commit_count,
+            branch_name: cover_letter
+                .and_then(|cl| cl.get_branch_name_with_pr_prefix_and_shorthand_id().ok()),
+            created_at: proposal.created_at.as_u64(),
+        });
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summaries).context("failed to serialize proposals")?
+        );
+        return Ok(());
+    }
+    if summaries.is_empty() {
+        println!("no matching proposals found");
+    }
+    for summary in summaries {
+        println!(
+            "{} {:<7} {} ({} commit{}) by {} {}",
+            &summary.id[..8],
+            summary.status,
+            summary.title,
+            summary.commit_count,
+            if summary.commit_count == 1 { "" } else { "s" },
+            summary
+                .author_name
+                .unwrap_or(summary.author.chars().take(12).collect()),
+            summary.branch_name.unwrap_or_default(),
+        );
     }
-    stdin.flush()?;
-    let output = am
-        .wait_with_output()
-        .context("failed to read git am stdout")?;
-    print!("{:?}", output.stdout);
     Ok(())
 }
 
@@ -734,7 +911,7 @@ fn save_patches_to_dir(mut patches: Vec<nostr::Event>, git_repo: &Repo) -> Resul
     Ok(())
 }
 
-fn check_clean(git_repo: &Repo) -> Result<()> {
+pub(crate) fn check_clean(git_repo: &Repo) -> Result<()> {
     if git_repo.has_outstanding_changes()? {
         bail!(
             "failed to pull proposal branch when repository is not clean. discard or stash (un)staged changes and try again."
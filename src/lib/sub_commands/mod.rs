@@ -1,6 +1,17 @@
+pub mod apply;
+pub mod checkout;
+pub mod comment;
 pub mod export_keys;
+pub mod fork;
 pub mod init;
+pub mod issue;
 pub mod list;
 pub mod login;
 pub mod logout;
+pub mod outbox;
+pub mod repo;
+pub mod search;
 pub mod send;
+pub mod state;
+pub mod status;
+pub mod sync;
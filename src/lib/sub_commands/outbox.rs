@@ -0,0 +1,123 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use console::Style;
+use nostr_sdk::Timestamp;
+
+use crate::{
+    client::{Client, Connect},
+    git::{Repo, RepoActions},
+    outbox::{OutboxEntry, get_outbox, remove_from_outbox, retry_outbox_entries},
+};
+
+#[derive(Debug, clap::Args)]
+pub struct EventIdsSubCommandArgs {
+    /// event ids or id prefixes. defaults to all events in the outbox
+    pub(crate) ids: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct DropSubCommandArgs {
+    /// event ids or id prefixes to remove from the outbox
+    #[arg(required_unless_present = "all")]
+    pub(crate) ids: Vec<String>,
+    /// remove all events from the outbox
+    #[arg(long, action, conflicts_with = "ids")]
+    pub(crate) all: bool,
+}
+
+pub async fn launch_list() -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let entries = get_outbox(git_repo.get_path()?).await?;
+    if entries.is_empty() {
+        println!("outbox is empty");
+        return Ok(());
+    }
+    let dim = Style::new().color256(247);
+    for entry in &entries {
+        println!(
+            "{} kind {} {}",
+            &entry.event.id.to_hex()[..8],
+            entry.event.kind.as_u16(),
+            dim.apply_to(format!(
+                "created {}s ago",
+                Timestamp::now()
+                    .as_u64()
+                    .saturating_sub(entry.event.created_at.as_u64())
+            )),
+        );
+        for (relay, status) in &entry.relays {
+            println!("  {relay} {status}");
+        }
+    }
+    Ok(())
+}
+
+pub async fn launch_retry(args: &EventIdsSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+    let entries = select_entries(git_repo_path, &args.ids).await?;
+    if entries.is_empty() {
+        println!("outbox is empty");
+        return Ok(());
+    }
+
+    #[cfg(test)]
+    let client = <crate::client::MockConnect as crate::client::Connect>::default();
+    #[cfg(not(test))]
+    let client = Client::default();
+
+    let count = entries.len();
+    println!(
+        "retrying {count} event{}...",
+        if count == 1 { "" } else { "s" }
+    );
+    let undelivered = retry_outbox_entries(&client, git_repo_path, entries).await?;
+    if undelivered.is_empty() {
+        println!("all events delivered");
+        return Ok(());
+    }
+    for entry in &undelivered {
+        println!("{} still undelivered:", &entry.event.id.to_hex()[..8]);
+        for relay in entry.undelivered_relays() {
+            println!("  {relay} {}", entry.relays[&relay]);
+        }
+    }
+    Ok(())
+}
+
+pub async fn launch_drop(args: &DropSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+    let entries = select_entries(git_repo_path, &args.ids).await?;
+    for entry in &entries {
+        remove_from_outbox(git_repo_path, &entry.event).await?;
+    }
+    println!(
+        "removed {} event{} from outbox",
+        entries.len(),
+        if entries.len() == 1 { "" } else { "s" }
+    );
+    Ok(())
+}
+
+/// all entries when `ids` is empty
+async fn select_entries(git_repo_path: &Path, ids: &[String]) -> Result<Vec<OutboxEntry>> {
+    let entries = get_outbox(git_repo_path).await?;
+    if ids.is_empty() {
+        return Ok(entries);
+    }
+    let mut selected = vec![];
+    for id in ids {
+        let matches: Vec<&OutboxEntry> = entries
+            .iter()
+            .filter(|e| e.event.id.to_hex().starts_with(&id.to_lowercase()))
+            .collect();
+        match matches.as_slice() {
+            [] => bail!("no event in outbox matches {id}"),
+            [entry] => selected.push((*entry).clone()),
+            _ => bail!("{id} matches more than one event in outbox. use a longer prefix"),
+        }
+    }
+    Ok(selected)
+}
@@ -0,0 +1,594 @@
+use anyhow::{Context, Result, bail};
+use console::Style;
+use nostr::ToBech32;
+use nostr_sdk::{EventBuilder, Kind, PublicKey, RelayUrl, Tag, TagKind, Timestamp};
+use serde::Serialize;
+
+use crate::{
+    cli::{Cli, extract_signer_cli_arguments},
+    cli_interactor::{Interactor, InteractorPrompt, PromptConfirmParms},
+    client,
+    client::{
+        Client, Connect, STATE_KIND, fetching_with_report, get_repo_ref_from_cache,
+        get_state_from_cache, send_events, sign_event,
+    },
+    git::{Repo, RepoActions, nostr_url::NostrUrlDecoded},
+    login::{self, get_curent_user, user::get_user_ref_from_cache},
+    repo_ref::{
+        RepoRef, get_repo_config_from_yaml, get_repo_coordinates_when_remote_unknown,
+        save_repo_config_to_yaml,
+    },
+};
+
+#[derive(Debug, clap::Args)]
+pub struct EditSubCommandArgs {
+    /// new name of repository
+    #[clap(long)]
+    pub(crate) name: Option<String>,
+    /// new description
+    #[clap(long)]
+    pub(crate) description: Option<String>,
+    /// git server url users can clone from. can be used multiple times
+    #[clap(long)]
+    pub(crate) add_clone_url: Vec<String>,
+    /// git server url to stop listing. can be used multiple times
+    #[clap(long)]
+    pub(crate) remove_clone_url: Vec<String>,
+    /// homepage to add. can be used multiple times
+    #[clap(long)]
+    pub(crate) add_web: Vec<String>,
+    /// homepage to remove. can be used multiple times
+    #[clap(long)]
+    pub(crate) remove_web: Vec<String>,
+    /// relay contributors push patches and comments to. can be used multiple
+    /// times
+    #[clap(long)]
+    pub(crate) add_relay: Vec<String>,
+    /// relay to stop listing. can be used multiple times
+    #[clap(long)]
+    pub(crate) remove_relay: Vec<String>,
+    /// npub of a maintainer to add. can be used multiple times
+    #[clap(long)]
+    pub(crate) add_maintainer: Vec<String>,
+    /// npub of a maintainer to remove. can be used multiple times
+    #[clap(long)]
+    pub(crate) remove_maintainer: Vec<String>,
+    /// usually root commit but will be more recent commit for forks
+    #[clap(long)]
+    pub(crate) earliest_unique_commit: Option<String>,
+    /// also rewrite maintainers.yaml with the updated maintainers and relays
+    #[arg(long, action)]
+    pub(crate) update_maintainers_yaml: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ArchiveSubCommandArgs {
+    /// nostr url of the repository's new home
+    #[clap(long)]
+    pub(crate) moved_to: Option<String>,
+    /// mark the repository as active again
+    #[arg(long, action, conflicts_with = "moved_to")]
+    pub(crate) undo: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct DeleteSubCommandArgs {
+    /// reason shown to users who try to fetch the repository
+    #[clap(long)]
+    pub(crate) reason: Option<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ShowSubCommandArgs {
+    /// print repository details as json
+    #[arg(long, action)]
+    pub(crate) json: bool,
+}
+
+#[derive(Serialize)]
+struct RepoSummary {
+    identifier: String,
+    name: String,
+    description: String,
+    naddr: String,
+    nostr_url: String,
+    maintainers: Vec<MaintainerSummary>,
+    relays: Vec<String>,
+    clone_urls: Vec<String>,
+    web: Vec<String>,
+    earliest_unique_commit: String,
+    fork_of: Option<String>,
+    archived: bool,
+    moved_to: Option<String>,
+    state_created_at: Option<u64>,
+    user_role: String,
+}
+
+#[derive(Serialize)]
+struct MaintainerSummary {
+    npub: String,
+    name: Option<String>,
+    trusted: bool,
+    has_announcement: bool,
+}
+
+pub async fn launch_show(args: &ShowSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    let mut maintainers = vec![];
+    for public_key in &repo_ref.maintainers {
+        maintainers.push(MaintainerSummary {
+            npub: public_key.to_bech32()?,
+            name: get_user_ref_from_cache(Some(git_repo_path), public_key)
+                .await
+                .ok()
+                .map(|user_ref| user_ref.metadata.name),
+            trusted: repo_ref.trusted_maintainer.eq(public_key),
+            has_announcement: repo_ref.events.keys().any(|c| c.public_key.eq(public_key)),
+        });
+    }
+
+    let summary = RepoSummary {
+        identifier: repo_ref.identifier.clone(),
+        name: repo_ref.name.clone(),
+        description: repo_ref.description.clone(),
+        naddr: repo_ref.coordinate_with_hint().to_bech32()?,
+        nostr_url: repo_ref.to_nostr_git_url(&Some(&git_repo)).to_string(),
+        maintainers,
+        relays: repo_ref
+            .relays
+            .iter()
+            .map(std::string::ToString::to_string)
+            .collect(),
+        clone_urls: repo_ref.git_server.clone(),
+        web: repo_ref.web.clone(),
+        earliest_unique_commit: repo_ref.root_commit.clone(),
+        fork_of: repo_ref
+            .fork_of
+            .as_ref()
+            .map(ToBech32::to_bech32)
+            .transpose()?,
+        archived: repo_ref.archived,
+        moved_to: repo_ref.moved_to.clone(),
+        state_created_at: get_state_from_cache(Some(git_repo_path), &repo_ref)
+            .await
+            .ok()
+            .map(|state| state.event.created_at.as_u64()),
+        user_role: match get_curent_user(&git_repo)? {
+            Some(public_key) if repo_ref.maintainers.contains(&public_key) => "maintainer",
+            Some(_) => "contributor",
+            None => "not logged in",
+        }
+        .to_string(),
+    };
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summary)
+                .context("failed to serialize repository details")?
+        );
+        return Ok(());
+    }
+
+    let dim = Style::new().color256(247);
+    let title_style = Style::new().bold();
+    println!(
+        "{} {}",
+        title_style.apply_to(&summary.name),
+        dim.apply_to(format!("({})", summary.identifier))
+    );
+    if !summary.description.is_empty() {
+        println!("{}", summary.description);
+    }
+    println!("naddr: {}", summary.naddr);
+    println!("nostr url: {}", summary.nostr_url);
+    println!("maintainers:");
+    for maintainer in &summary.maintainers {
+        println!(
+            "  {}{}{}",
+            maintainer
+                .name
+                .as_ref()
+                .map_or(String::new(), |name| format!("{name} ")),
+            maintainer.npub,
+            dim.apply_to(match (maintainer.trusted, maintainer.has_announcement) {
+                (true, _) => " (trusted)",
+                (false, true) => "",
+                (false, false) => " (no announcement)",
+            }),
+        );
+    }
+    for (title, items) in [
+        ("relays", &summary.relays),
+        ("clone urls", &summary.clone_urls),
+        ("web", &summary.web),
+    ] {
+        println!("{title}:");
+        for item in items.iter().filter(|item| !item.is_empty()) {
+            println!("  {item}");
+        }
+    }
+    println!("earliest unique commit: {}", summary.earliest_unique_commit);
+    if let Some(fork_of) = &summary.fork_of {
+        println!("fork of: {fork_of}");
+    }
+    if let Some(warning) = repo_ref.archived_warning() {
+        println!("{}", Style::new().yellow().apply_to(warning));
+    }
+    println!(
+        "state: {}",
+        summary
+            .state_created_at
+            .map_or("no state event".to_string(), |created_at| format!(
+                "updated {}s ago",
+                Timestamp::now().as_u64().saturating_sub(created_at)
+            ))
+    );
+    println!("your role: {}", summary.user_role);
+    Ok(())
+}
+
+#[allow(clippy::too_many_lines)]
+pub async fn launch_edit(cli_args: &Cli, args: &EditSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let mut client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let mut client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    let mut edited = repo_ref.clone();
+    if let Some(name) = &args.name {
+        edited.name.clone_from(name);
+    }
+    if let Some(description) = &args.description {
+        edited.description.clone_from(description);
+    }
+    edit_list(
+        &mut edited.git_server,
+        &args.add_clone_url,
+        &args.remove_clone_url,
+        "clone url",
+    )?;
+    edit_list(&mut edited.web, &args.add_web, &args.remove_web, "web url")?;
+    edit_list(
+        &mut edited.relays,
+        &parse_relays(&args.add_relay)?,
+        &parse_relays(&args.remove_relay)?,
+        "relay",
+    )?;
+    edit_list(
+        &mut edited.maintainers,
+        &parse_public_keys(&args.add_maintainer)?,
+        &parse_public_keys(&args.remove_maintainer)?,
+        "maintainer",
+    )?;
+    if let Some(commit) = &args.earliest_unique_commit {
+        if !git_repo.does_commit_exist(commit).unwrap_or(false) {
+            bail!("earliest unique commit {commit} does not exist in the local repository");
+        }
+        edited.root_commit.clone_from(commit);
+    }
+
+    if edited.name.eq(&repo_ref.name)
+        && edited.description.eq(&repo_ref.description)
+        && edited.git_server.eq(&repo_ref.git_server)
+        && edited.web.eq(&repo_ref.web)
+        && edited.relays.eq(&repo_ref.relays)
+        && edited.maintainers.eq(&repo_ref.maintainers)
+        && edited.root_commit.eq(&repo_ref.root_commit)
+    {
+        if args.update_maintainers_yaml {
+            update_maintainers_yaml(&git_repo, &edited)?;
+            return Ok(());
+        }
+        bail!("no changes to the repository announcement were specified");
+    }
+    if edited.relays.is_empty() {
+        bail!("the repository must list at least one relay");
+    }
+
+    let (signer, user_ref, _) = login::login_or_signup(
+        &Some(&git_repo),
+        &extract_signer_cli_arguments(cli_args).unwrap_or(None),
+        &cli_args.password,
+        Some(&client),
+        true,
+    )
+    .await?;
+
+    if !repo_ref.maintainers.contains(&user_ref.public_key) {
+        bail!("only repository maintainers can edit the repository announcement");
+    }
+    if !edited.maintainers.contains(&user_ref.public_key) {
+        bail!(
+            "you cannot remove yourself as a maintainer. ask another maintainer to remove you instead"
+        );
+    }
+
+    let repo_event = edited.to_event(&signer).await?;
+
+    client.set_signer(signer).await;
+
+    println!("publishing updated repository announcement...");
+
+    // publish to relays being removed too so they hold the latest announcement
+    let mut relays = edited.relays.clone();
+    for relay in &repo_ref.relays {
+        if !relays.contains(relay) {
+            relays.push(relay.clone());
+        }
+    }
+
+    send_events(
+        &client,
+        Some(git_repo_path),
+        vec![repo_event],
+        user_ref.relays.write(),
+        relays,
+        !cli_args.disable_cli_spinners,
+        false,
+    )
+    .await?;
+
+    if args.update_maintainers_yaml {
+        update_maintainers_yaml(&git_repo, &edited)?;
+    } else if get_repo_config_from_yaml(&git_repo).is_ok()
+        && (edited.maintainers.ne(&repo_ref.maintainers) || edited.relays.ne(&repo_ref.relays))
+    {
+        println!(
+            "maintainers.yaml is now out of date. run with --update-maintainers-yaml to rewrite it"
+        );
+    }
+    Ok(())
+}
+
+pub async fn launch_archive(cli_args: &Cli, args: &ArchiveSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let mut client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let mut client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    if let Some(moved_to) = &args.moved_to {
+        NostrUrlDecoded::parse_and_resolve(moved_to, &Some(&git_repo))
+            .await
+            .context(format!("{moved_to} is not a valid nostr git url"))?;
+    }
+
+    let mut archived = repo_ref.clone();
+    archived.archived = !args.undo;
+    archived.moved_to = if args.undo {
+        None
+    } else {
+        args.moved_to.clone()
+    };
+    if archived.archived == repo_ref.archived && archived.moved_to == repo_ref.moved_to {
+        bail!(
+            "repository is {}",
+            if args.undo {
+                "not archived"
+            } else {
+                "already archived"
+            }
+        );
+    }
+
+    let (signer, user_ref, _) = login::login_or_signup(
+        &Some(&git_repo),
+        &extract_signer_cli_arguments(cli_args).unwrap_or(None),
+        &cli_args.password,
+        Some(&client),
+        true,
+    )
+    .await?;
+
+    // users find the repository through the trusted maintainer's announcement
+    if !repo_ref.trusted_maintainer.eq(&user_ref.public_key) {
+        bail!(
+            "only the trusted maintainer {} can archive the repository",
+            repo_ref.trusted_maintainer.to_bech32()?
+        );
+    }
+
+    let repo_event = archived.to_event(&signer).await?;
+
+    client.set_signer(signer).await;
+
+    send_events(
+        &client,
+        Some(git_repo_path),
+        vec![repo_event],
+        user_ref.relays.write(),
+        archived.relays.clone(),
+        !cli_args.disable_cli_spinners,
+        false,
+    )
+    .await?;
+
+    println!(
+        "{}",
+        archived.archived_warning().unwrap_or(format!(
+            "repository {} is active again",
+            archived.identifier
+        ))
+    );
+    Ok(())
+}
+
+pub async fn launch_delete(cli_args: &Cli, args: &DeleteSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let mut client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let mut client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    let (signer, user_ref, _) = login::login_or_signup(
+        &Some(&git_repo),
+        &extract_signer_cli_arguments(cli_args).unwrap_or(None),
+        &cli_args.password,
+        Some(&client),
+        true,
+    )
+    .await?;
+
+    if !repo_ref.maintainers.contains(&user_ref.public_key) {
+        bail!("only repository maintainers can delete the repository announcement");
+    }
+
+    if !Interactor::default().confirm(
+        PromptConfirmParms::default()
+            .with_default(false)
+            .with_prompt(format!(
+                "request deletion of your announcement and state event for {}? relays that honour deletions will stop serving them",
+                repo_ref.identifier
+            )),
+    )? {
+        println!("deletion cancelled");
+        return Ok(());
+    }
+
+    // NIP-09 deletion of both the announcement and state event by address and id
+    let mut tags = vec![];
+    for kind in [Kind::GitRepoAnnouncement, STATE_KIND] {
+        tags.push(Tag::custom(
+            TagKind::Custom("a".into()),
+            vec![format!(
+                "{}:{}:{}",
+                kind.as_u16(),
+                user_ref.public_key,
+                repo_ref.identifier
+            )],
+        ));
+        tags.push(Tag::custom(
+            TagKind::Custom("k".into()),
+            vec![kind.as_u16().to_string()],
+        ));
+    }
+    for event in repo_ref
+        .events
+        .values()
+        .filter(|e| e.pubkey.eq(&user_ref.public_key))
+    {
+        tags.push(Tag::event(event.id));
+    }
+    if let Ok(repo_state) = get_state_from_cache(Some(git_repo_path), &repo_ref).await {
+        for event in repo_state
+            .events
+            .iter()
+            .filter(|e| e.pubkey.eq(&user_ref.public_key))
+        {
+            tags.push(Tag::event(event.id));
+        }
+    }
+
+    let deletion_event = sign_event(
+        EventBuilder::new(Kind::EventDeletion, args.reason.clone().unwrap_or_default()).tags(tags),
+        &signer,
+        "repository deletion".to_string(),
+    )
+    .await?;
+
+    client.set_signer(signer).await;
+
+    send_events(
+        &client,
+        Some(git_repo_path),
+        vec![deletion_event],
+        user_ref.relays.write(),
+        repo_ref.relays.clone(),
+        !cli_args.disable_cli_spinners,
+        false,
+    )
+    .await?;
+
+    println!("deletion requested for {}", repo_ref.identifier);
+    if repo_ref
+        .maintainers
+        .iter()
+        .any(|m| m.ne(&user_ref.public_key) && repo_ref.events.keys().any(|c| c.public_key.eq(m)))
+    {
+        println!("other maintainers' announcements remain. ask them to run `ngit repo delete` too");
+    }
+    Ok(())
+}
+
+fn edit_list<T: PartialEq + Clone + ToString>(
+    list: &mut Vec<T>,
+    add: &[T],
+    remove: &[T],
+    item_name: &str,
+) -> Result<()> {
+    for item in remove {
+        if !list.contains(item) {
+            bail!("{item_name} {} is not listed", item.to_string());
+        }
+        list.retain(|i| i.ne(item));
+    }
+    for item in add {
+        if !list.contains(item) {
+            list.push(item.clone());
+        }
+    }
+    Ok(())
+}
+
+fn parse_relays(relays: &[String]) -> Result<Vec<RelayUrl>> {
+    relays
+        .iter()
+        .map(|r| RelayUrl::parse(r).context(format!("{r} is not a valid relay url")))
+        .collect()
+}
+
+fn parse_public_keys(public_keys: &[String]) -> Result<Vec<PublicKey>> {
+    public_keys
+        .iter()
+        .map(|pk| PublicKey::parse(pk).context(format!("{pk} is not a valid npub")))
+        .collect()
+}
+
+fn update_maintainers_yaml(git_repo: &Repo, repo_ref: &RepoRef) -> Result<()> {
+    save_repo_config_to_yaml(
+        git_repo,
+        repo_ref.identifier.clone(),
+        repo_ref.maintainers.clone(),
+        repo_ref
+            .relays
+            .iter()
+            .map(std::string::ToString::to_string)
+            .collect(),
+    )?;
+    println!("maintainers.yaml updated. commit and push.");
+    Ok(())
+}
+
+async fn fetch_and_get_repo_ref(
+    git_repo: &Repo,
+    #[cfg(test)] client: &crate::client::MockConnect,
+    #[cfg(not(test))] client: &Client,
+) -> Result<RepoRef> {
+    let git_repo_path = git_repo.get_path()?;
+    let repo_coordinates = get_repo_coordinates_when_remote_unknown(git_repo, client).await?;
+    fetching_with_report(git_repo_path, client, &repo_coordinates).await?;
+    get_repo_ref_from_cache(Some(git_repo_path), &repo_coordinates).await
+}
@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use console::Style;
+use nostr::{Filter, Kind, ToBech32};
+use nostr_sdk::PublicKey;
+
+use crate::{
+    client,
+    client::{
+        Client, Connect, get_event_from_global_cache, is_offline, save_event_in_global_cache,
+    },
+    git::{Repo, RepoActions},
+    login::user::get_user_ref_from_cache,
+    repo_ref::RepoRef,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct SubCommandArgs {
+    /// text to find in repository names, identifiers, descriptions and hashtags
+    pub(crate) text: String,
+    /// only list repositories announced by this maintainer (npub or hex)
+    #[arg(long)]
+    pub(crate) author: Option<String>,
+    /// maximum number of repositories to request from each relay
+    #[arg(long, default_value_t = 100)]
+    pub(crate) limit: usize,
+}
+
+pub async fn launch(args: &SubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().ok();
+    let git_repo_path = if let Some(git_repo) = &git_repo {
+        Some(git_repo.get_path()?)
+    } else {
+        None
+    };
+
+    let author = if let Some(author) = &args.author {
+        Some(PublicKey::parse(author).context(format!("invalid author {author}"))?)
+    } else {
+        None
+    };
+
+    let base_filter = {
+        let filter = Filter::default()
+            .kind(Kind::GitRepoAnnouncement)
+            .limit(args.limit);
+        if let Some(author) = author {
+            filter.author(author)
+        } else {
+            filter
+        }
+    };
+
+    if !is_offline() {
+        #[cfg(test)]
+        let client = <client::MockConnect as client::Connect>::default();
+        #[cfg(not(test))]
+        let client = Client::default();
+
+        let relays = [
+            client.get_fallback_relays().clone(),
+            client.get_more_fallback_relays().clone(),
+        ]
+        .concat();
+
+        // NIP-50 search is sent separately as relays that don't support it may
+        // reject the whole request
+        let mut events = client
+            .get_events(
+                relays.clone(),
+                vec![base_filter.clone().search(args.text.clone())],
+            )
+            .await?;
+        events.extend(
+            client
+                .get_events(
+                    relays,
+                    vec![if author.is_some() {
+                        base_filter.clone()
+                    } else {
+                        base_filter.clone().hashtag(args.text.to_lowercase())
+                    }],
+                )
+                .await?,
+        );
+        for event in &events {
+            save_event_in_global_cache(git_repo_path, event).await?;
+        }
+        client.disconnect().await?;
+    }
+
+    let mut events = get_event_from_global_cache(
+        git_repo_path,
+        vec![if let Some(author) = author {
+            Filter::default()
+                .kind(Kind::GitRepoAnnouncement)
+                .author(author)
+        } else {
+            Filter::default().kind(Kind::GitRepoAnnouncement)
+        }],
+    )
+    .await?;
+    events.sort_by_key(|e| std::cmp::Reverse(e.created_at));
+
+    let text = args.text.to_lowercase();
+    let dim = Style::new().color256(247);
+    let mut found = 0;
+    for event in events {
+        let hashtags: Vec<String> = event
+            .tags
+            .hashtags()
+            .map(std::string::ToString::to_string)
+            .collect();
+        let Ok(repo_ref) = RepoRef::try_from((event.clone(), None)) else {
+            continue;
+        };
+        if ![&repo_ref.name, &repo_ref.identifier, &repo_ref.description]
+            .into_iter()
+            .chain(hashtags.iter())
+            .any(|field| field.to_lowercase().contains(&text))
+        {
+            continue;
+        }
+        found += 1;
+        println!(
+            "{} {} by {}",
+            Style::new().bold().apply_to(if repo_ref.name.is_empty() {
+                &repo_ref.identifier
+            } else {
+                &repo_ref.name
+            }),
+            dim.apply_to(format!("({})", repo_ref.identifier)),
+            get_user_ref_from_cache(git_repo_path, &event.pubkey)
+                .await
+                .map_or(event.pubkey.to_bech32()?, |user_ref| user_ref.metadata.name),
+        );
+        if !repo_ref.description.is_empty() {
+            println!("  {}", dim.apply_to(&repo_ref.description));
+        }
+        println!("  {}", repo_ref.to_nostr_git_url(&git_repo.as_ref()));
+    }
+    if found == 0 {
+        println!("no repositories found matching '{}'", args.text);
+    }
+    Ok(())
+}
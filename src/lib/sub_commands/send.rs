@@ -1,12 +1,6 @@
-use std::path::Path;
-
 use anyhow::{Context, Result, bail};
 use console::Style;
-use nostr::{
-    ToBech32,
-    nips::{nip10::Marker, nip19::Nip19Event},
-};
-use nostr_sdk::hashes::sha1::Hash as Sha1Hash;
+use nostr::{ToBech32, nips::nip19::Nip19Event};
 
 use crate::{
     cli::{Cli, extract_signer_cli_arguments},
@@ -15,13 +9,11 @@ use crate::{
     },
     client,
     client::{
-        Client, Connect, MockClient, fetching_with_report, get_events_from_local_cache,
-        get_repo_ref_from_cache, send_events,
+        Client, Connect, MockClient, fetching_with_report, get_repo_ref_from_cache, send_events,
     },
-    git::{Repo, RepoActions, identify_ahead_behind},
+    git::{CommitId, Repo, RepoActions, identify_ahead_behind},
     git_events::{
-        event_is_patch_set_root, event_tag_from_nip19_or_hex,
-        generate_cover_letter_and_patch_events,
+        generate_cover_letter_and_patch_events, get_root_proposal_id_and_mentions_from_in_reply_to,
     },
     login,
     repo_ref::get_repo_coordinates_when_remote_unknown,
@@ -80,7 +72,7 @@ pub async fn launch(cli_args: &Cli, args: &SubCommandArgs, no_fetch: bool) -> Re
         }
     }
 
-    let mut commits: Vec<Sha1Hash> = {
+    let mut commits: Vec<CommitId> = {
         if args.since_or_range.is_empty() {
             let branch_name = git_repo.get_checked_out_branch_name()?;
             let proposed_commits = if branch_name.eq(main_branch_name) {
@@ -202,6 +194,10 @@ pub async fn launch(cli_args: &Cli, args: &SubCommandArgs, no_fetch: bool) -> Re
 
     let repo_ref = get_repo_ref_from_cache(Some(git_repo_path), &repo_coordinates).await?;
 
+    if let Some(warning) = repo_ref.archived_warning() {
+        println!("WARNING: {warning}");
+    }
+
     // oldest first
     commits.reverse();
 
@@ -285,7 +281,7 @@ pub async fn launch(cli_args: &Cli, args: &SubCommandArgs, no_fetch: bool) -> Re
     Ok(())
 }
 
-fn choose_commits(git_repo: &Repo, proposed_commits: Vec<Sha1Hash>) -> Result<Vec<Sha1Hash>> {
+fn choose_commits(git_repo: &Repo, proposed_commits: Vec<CommitId>) -> Result<Vec<CommitId>> {
     let mut proposed_commits = if proposed_commits.len().gt(&10) {
         vec![]
     } else {
@@ -350,7 +346,7 @@ fn choose_commits(git_repo: &Repo, proposed_commits: Vec<Sha1Hash>) -> Result<Ve
     Ok(selected_commits)
 }
 
-fn summarise_commit_for_selection(git_repo: &Repo, commit: &Sha1Hash) -> Result<String> {
+fn summarise_commit_for_selection(git_repo: &Repo, commit: &CommitId) -> Result<String> {
     let references = git_repo.get_refs(commit)?;
     let dim = Style::new().color256(247);
     let prefix = format!("({})", git_repo.get_commit_author(commit)?[0],);
@@ -376,58 +372,6 @@ fn summarise_commit_for_selection(git_repo: &Repo, commit: &Sha1Hash) -> Result<
     ))
 }
 
-async fn get_root_proposal_id_and_mentions_from_in_reply_to(
-    git_repo_path: &Path,
-    in_reply_to: &[String],
-) -> Result<(Option<String>, Vec<nostr::Tag>)> {
-    let root_proposal_id = if let Some(first) = in_reply_to.first() {
-        match event_tag_from_nip19_or_hex(first, "in-reply-to", Marker::Root, true, false)?
-            .as_standardized()
-        {
-            Some(nostr_sdk::TagStandard::Event {
-                event_id,
-                relay_url: _,
-                marker: _,
-                public_key: _,
-                uppercase: false,
-            }) => {
-                let events = get_events_from_local_cache(
-                    git_repo_path,
-                    vec![nostr::Filter::new().id(*event_id)],
-                )
-                .await?;
-
-                if let Some(first) = events.iter().find(|e| e.id.eq(event_id)) {
-                    if event_is_patch_set_root(first) {
-                        Some(event_id.to_string())
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            }
-            _ => None,
-        }
-    } else {
-        return Ok((None, vec![]));
-    };
-
-    let mut mention_tags = vec![];
-    for (i, reply_to) in in_reply_to.iter().enumerate() {
-        if i.ne(&0) || root_proposal_id.is_none() {
-            mention_tags.push(
-                event_tag_from_nip19_or_hex(reply_to, "in-reply-to", Marker::Mention, true, false)
-                    .context(format!(
-                        "{reply_to} in 'in-reply-to' not a valid nostr reference"
-                    ))?,
-            );
-        }
-    }
-
-    Ok((root_proposal_id, mention_tags))
-}
-
 // TODO
 // - find profile
 // - file relays
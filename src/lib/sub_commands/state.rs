@@ -0,0 +1,491 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{Context, Result, bail};
+use console::Style;
+use nostr_sdk::{PublicKey, Timestamp};
+
+use crate::{
+    blossom::is_blossom_url,
+    cli::{Cli, extract_signer_cli_arguments},
+    client,
+    client::{
+        Client, Connect, fetching_with_report, get_repo_ref_from_cache, get_state_from_cache,
+        send_events,
+    },
+    git::{Repo, RepoActions, remote::list_from_remote},
+    login::{self, user::get_display_name},
+    repo_ref::{RepoRef, get_repo_coordinates_when_remote_unknown},
+    repo_state::{RepoState, get_non_fast_forward_refs},
+};
+
+#[derive(Debug, clap::Args)]
+pub struct PublishSubCommandArgs {
+    /// remove a branch or tag from the nostr state. can be repeated
+    #[arg(long, value_name = "REF")]
+    pub(crate) delete: Vec<String>,
+    /// publish branches that aren't fast-forwards of the current nostr state
+    #[arg(long, action)]
+    pub(crate) force: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct SyncFromSubCommandArgs {
+    /// git server url to take the branches and tags from
+    pub(crate) git_server: String,
+    /// publish branches that aren't fast-forwards of the current nostr state
+    /// and delete those the git server doesn't have
+    #[arg(long, action)]
+    pub(crate) force: bool,
+}
+
+pub async fn launch_show() -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    let Ok(repo_state) = get_state_from_cache(Some(git_repo_path), &repo_ref).await else {
+        bail!("no maintainer has published a state event for this repository");
+    };
+
+    let dim = Style::new().color256(247);
+    for event in &repo_state.events {
+        println!(
+            "{}",
+            dim.apply_to(format!(
+                "published by {} {}s ago",
+                get_display_name(Some(git_repo_path), &event.pubkey).await,
+                Timestamp::now()
+                    .as_u64()
+                    .saturating_sub(event.created_at.as_u64())
+            )),
+        );
+    }
+    let mut names: Vec<&String> = repo_state.state.keys().collect();
+    names.sort();
+    for name in names {
+        let value = &repo_state.state[name];
+        if let Some(target) = value.strip_prefix("ref: ") {
+            println!("@{target} {name}");
+        } else {
+            println!("{value} {name}");
+        }
+    }
+    Ok(())
+}
+
+pub async fn launch_publish(cli_args: &Cli, args: &PublishSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+
+    #[cfg(test)]
+    let mut client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let mut client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+    let existing_state = get_existing_state(&git_repo, &repo_ref).await?;
+
+    // branches and tags that aren't checked out locally are kept
+    let mut new_state = existing_state.0.clone();
+    new_state.extend(get_state_on_git_servers(
+        &git_repo,
+        &repo_ref,
+        get_local_state(&git_repo)?,
+    )?);
+    let deleted = remove_refs(&mut new_state, &args.delete)?;
+
+    publish_state(
+        cli_args,
+        &git_repo,
+        &mut client,
+        &repo_ref,
+        existing_state,
+        new_state,
+        &deleted,
+        args.force,
+    )
+    .await
+}
+
+pub async fn launch_sync_from(cli_args: &Cli, args: &SyncFromSubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+
+    #[cfg(test)]
+    let mut client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let mut client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    let term = console::Term::stderr();
+    let mut new_state = list_from_remote(
+        &term,
+        &git_repo,
+        &args.git_server,
+        &repo_ref.to_nostr_git_url(&Some(&git_repo)),
+    )?;
+    new_state.retain(|name, _| is_state_ref(name));
+
+    publish_state(
+        cli_args,
+        &git_repo,
+        &mut client,
+        &repo_ref,
+        get_existing_state(&git_repo, &repo_ref).await?,
+        new_state,
+        &[],
+        args.force,
+    )
+    .await
+}
+
+pub async fn launch_diff() -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let client = Client::default();
+
+    let repo_ref = fetch_and_get_repo_ref(&git_repo, &client).await?;
+
+    let Ok(repo_state) = get_state_from_cache(Some(git_repo_path), &repo_ref).await else {
+        bail!("no maintainer has published a state event for this repository");
+    };
+
+    let dim = Style::new().color256(247);
+    let mut names: HashMap<PublicKey, String> = HashMap::new();
+    for event in &repo_state.events {
+        names.insert(
+            event.pubkey,
+            get_display_name(Some(git_repo_path), &event.pubkey).await,
+        );
+    }
+    let name_width = names.values().map(|n| n.chars().count()).max().unwrap_or(0);
+
+    println!("state events:");
+    for event in &repo_state.events {
+        println!(
+            "  {:name_width$} {}",
+            names[&event.pubkey],
+            dim.apply_to(format!(
+                "published {}s ago",
+                Timestamp::now()
+                    .as_u64()
+                    .saturating_sub(event.created_at.as_u64())
+            )),
+        );
+    }
+
+    let maintainer_states = repo_state.maintainer_states();
+    let conflicts = repo_state.conflicts(&git_repo);
+    let ref_names: BTreeSet<&String> = maintainer_states
+        .iter()
+        .flat_map(|(_, state)| state.keys())
+        .collect();
+    let mut differences = 0;
+    for ref_name in ref_names {
+        let values: Vec<Option<&String>> = maintainer_states
+            .iter()
+            .map(|(_, state)| state.get(ref_name))
+            .collect();
+        if values.iter().all(|value| value.eq(&values[0])) {
+            continue;
+        }
+        differences += 1;
+        println!(
+            "{ref_name}{}",
+            if conflicts.iter().any(|c| c.name.eq(ref_name)) {
+                format!(" {}", Style::new().red().apply_to("diverged"))
+            } else {
+                String::new()
+            }
+        );
+        for ((public_key, _), value) in maintainer_states.iter().zip(values) {
+            println!(
+                "  {:name_width$} {}{}",
+                names[public_key],
+                value.map_or("missing", |value| shorten(value)),
+                if value.is_some() && value.eq(&repo_state.state.get(ref_name)) {
+                    format!(" {}", dim.apply_to("(used)"))
+                } else {
+                    String::new()
+                },
+            );
+        }
+    }
+    if differences == 0 {
+        println!("all maintainers' state events agree");
+    }
+    Ok(())
+}
+
+/// refs and blossom packs in the current nostr state. empty if no maintainer
+/// has published one
+async fn get_existing_state(
+    git_repo: &Repo,
+    repo_ref: &RepoRef,
+) -> Result<(HashMap<String, String>, Vec<String>)> {
+    Ok(
+        match get_state_from_cache(Some(git_repo.get_path()?), repo_ref).await {
+            Ok(existing_state) => (existing_state.state, existing_state.blossom_packs),
+            Err(_) => (HashMap::new(), vec![]),
+        },
+    )
+}
+
+/// removes each of `names` from `state`, accepting short branch and tag names.
+/// returns the full names removed, including peeled tags and HEAD if it
+/// pointed at a removed branch
+fn remove_refs(state: &mut HashMap<String, String>, names: &[String]) -> Result<Vec<String>> {
+    let mut removed = vec![];
+    for name in names {
+        let Some(full_name) = [
+            name.clone(),
+            format!("refs/heads/{name}"),
+            format!("refs/tags/{name}"),
+        ]
+        .into_iter()
+        .find(|full_name| state.contains_key(full_name)) else {
+            bail!("{name} isn't in the nostr state");
+        };
+        for full_name in [full_name.clone(), format!("{full_name}^{{}}")] {
+            if state.remove(&full_name).is_some() {
+                removed.push(full_name);
+            }
+        }
+        if state
+            .get("HEAD")
+            .is_some_and(|head| head.eq(&format!("ref: {full_name}")))
+        {
+            state.remove("HEAD");
+            removed.push("HEAD".to_string());
+        }
+    }
+    Ok(removed)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn publish_state(
+    cli_args: &Cli,
+    git_repo: &Repo,
+    #[cfg(test)] client: &mut crate::client::MockConnect,
+    #[cfg(not(test))] client: &mut Client,
+    repo_ref: &RepoRef,
+    (existing_refs, blossom_packs): (HashMap<String, String>, Vec<String>),
+    new_state: HashMap<String, String>,
+    requested_deletions: &[String],
+    force: bool,
+) -> Result<()> {
+    let git_repo_path = git_repo.get_path()?;
+    if new_state.is_empty() {
+        bail!("no branches or tags to publish");
+    }
+
+    if existing_refs.eq(&new_state) {
+        println!("nostr state is already up to date");
+        return Ok(());
+    }
+
+    let force_pushed = get_non_fast_forward_refs(git_repo, &existing_refs, &new_state);
+    if !force_pushed.is_empty() && !force {
+        bail!(
+            "{} not a fast-forward of the nostr state or the commits are missing locally. use --force to publish anyway",
+            list_with_verb(&force_pushed),
+        );
+    }
+
+    let mut forced_deletions: Vec<String> = existing_refs
+        .keys()
+        .filter(|name| !new_state.contains_key(*name) && !requested_deletions.contains(name))
+        .cloned()
+        .collect();
+    forced_deletions.sort();
+    if !forced_deletions.is_empty() && !force {
+        bail!(
+            "{} in the nostr state but would be deleted. use --force to delete anyway",
+            list_with_verb(&forced_deletions),
+        );
+    }
+
+    let (signer, user_ref, _) = login::login_or_signup(
+        &Some(git_repo),
+        &extract_signer_cli_arguments(cli_args).unwrap_or(None),
+        &cli_args.password,
+        Some(&*client),
+        true,
+    )
+    .await?;
+
+    if !repo_ref.maintainers.contains(&user_ref.public_key) {
+        bail!("only repository maintainers can publish the repository state");
+    }
+
+    client.set_signer(signer.clone()).await;
+
+    let mut names: Vec<&String> = new_state
+        .keys()
+        .chain(existing_refs.keys())
+        .collect::<BTreeSet<&String>>()
+        .into_iter()
+        .collect();
+    names.retain(|name| new_state.get(*name) != existing_refs.get(*name));
+    for name in names {
+        println!(
+            "{name} {} -> {}{}",
+            existing_refs
+                .get(name)
+                .map_or("none", |value| shorten(value)),
+            new_state
+                .get(name)
+                .map_or("deleted", |value| shorten(value)),
+            if force_pushed.contains(name) || forced_deletions.contains(name) {
+                " (forced)"
+            } else {
+                ""
+            },
+        );
+    }
+
+    let repo_state = RepoState::build(
+        repo_ref.identifier.clone(),
+        new_state,
+        blossom_packs,
+        force_pushed,
+        &signer,
+    )
+    .await?;
+
+    send_events(
+        client,
+        Some(git_repo_path),
+        vec![repo_state.event],
+        user_ref.relays.write(),
+        repo_ref.relays.clone(),
+        !cli_args.disable_cli_spinners,
+        false,
+    )
+    .await
+}
+
+/// branches, tags and HEAD of the local repository as they would appear in a
+/// state event
+fn get_local_state(git_repo: &Repo) -> Result<HashMap<String, String>> {
+    let mut state = HashMap::new();
+    for reference in git_repo.git_repo.references()? {
+        let reference = reference?;
+        let Some(name) = reference.name() else {
+            continue;
+        };
+        if !is_state_ref(name) {
+            continue;
+        }
+        if name.starts_with("refs/tags/") {
+            if let Ok(tag) = reference.peel(git2::ObjectType::Tag) {
+                state.insert(name.to_string(), tag.id().to_string());
+                state.insert(
+                    format!("{name}^{{}}"),
+                    reference.peel_to_commit()?.id().to_string(),
+                );
+                continue;
+            }
+        }
+        state.insert(
+            name.to_string(),
+            reference.peel_to_commit()?.id().to_string(),
+        );
+    }
+    if let Ok(head) = git_repo.git_repo.find_reference("HEAD") {
+        if let Some(target) = head.symbolic_target() {
+            if state.contains_key(target) {
+                state.insert("HEAD".to_string(), format!("ref: {target}"));
+            }
+        }
+    }
+    Ok(state)
+}
+
+/// the refs in `local_state` that at least one git server has with the same
+/// value. others are left out with a warning so that the state never points
+/// at commits that can't be fetched
+fn get_state_on_git_servers(
+    git_repo: &Repo,
+    repo_ref: &RepoRef,
+    local_state: HashMap<String, String>,
+) -> Result<HashMap<String, String>> {
+    let term = console::Term::stderr();
+    let decoded_nostr_url = repo_ref.to_nostr_git_url(&Some(git_repo));
+    let remote_states: Vec<HashMap<String, String>> = repo_ref
+        .git_server
+        .iter()
+        .filter(|url| !is_blossom_url(url))
+        .filter_map(|url| list_from_remote(&term, git_repo, url, &decoded_nostr_url).ok())
+        .collect();
+    if remote_states.is_empty() {
+        bail!("couldn't list the branches and tags on any of the repository's git servers");
+    }
+
+    let mut state = HashMap::new();
+    let mut names: Vec<&String> = local_state.keys().collect();
+    names.sort();
+    for name in names {
+        let value = &local_state[name];
+        if name.eq("HEAD")
+            || remote_states
+                .iter()
+                .any(|remote_state| remote_state.get(name).is_some_and(|v| v.eq(value)))
+        {
+            state.insert(name.clone(), value.clone());
+        } else {
+            term.write_line(&format!(
+                "WARNING: {name} isn't on any git server so won't be published. push it first"
+            ))?;
+        }
+    }
+    if let Some(target) = state.get("HEAD").and_then(|v| v.strip_prefix("ref: ")) {
+        if !state.contains_key(target) {
+            state.remove("HEAD");
+        }
+    }
+    Ok(state)
+}
+
+fn is_state_ref(name: &str) -> bool {
+    (name.starts_with("refs/heads/") && !name.starts_with("refs/heads/pr/"))
+        || name.starts_with("refs/tags/")
+        || name.eq("HEAD")
+}
+
+async fn fetch_and_get_repo_ref(
+    git_repo: &Repo,
+    #[cfg(test)] client: &crate::client::MockConnect,
+    #[cfg(not(test))] client: &Client,
+) -> Result<RepoRef> {
+    let git_repo_path = git_repo.get_path()?;
+    let repo_coordinates = get_repo_coordinates_when_remote_unknown(git_repo, client).await?;
+    fetching_with_report(git_repo_path, client, &repo_coordinates).await?;
+    get_repo_ref_from_cache(Some(git_repo_path), &repo_coordinates).await
+}
+
+/// "a is" or "a, b are"
+fn list_with_verb(names: &[String]) -> String {
+    if names.len() == 1 {
+        format!("{} is", names[0])
+    } else {
+        format!("{} are", names.join(", "))
+    }
+}
+
+fn shorten(value: &str) -> &str {
+    if value.starts_with("ref: ") {
+        value
+    } else {
+        &value[..value.len().min(7)]
+    }
+}
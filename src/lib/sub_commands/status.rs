@@ -0,0 +1,206 @@
+use anyhow::{Context, Result, bail};
+use nostr::nips::nip10::Marker;
+use nostr_sdk::{Kind, Tag, TagKind, TagStandard};
+
+use crate::{
+    cli::{Cli, extract_signer_cli_arguments},
+    client,
+    client::{
+        Client, Connect, fetching_with_report, get_all_proposal_patch_events_from_cache,
+        get_events_from_local_cache, get_proposal_from_cache_by_reference, get_repo_ref_from_cache,
+        send_events,
+    },
+    git::{Repo, RepoActions, str_to_commit_id},
+    git_events::{
+        event_is_revision_root, event_to_cover_letter, generate_status_event, get_event_root,
+        get_most_recent_patch_with_ancestors, get_status_from_events, get_status_name,
+        status_kinds,
+    },
+    login,
+    repo_ref::get_repo_coordinates_when_remote_unknown,
+};
+
+#[derive(Debug, clap::Args)]
+#[command(group(
+    clap::ArgGroup::new("new_status")
+        .required(true)
+        .args(["open", "draft", "closed", "applied"]),
+))]
+pub struct SubCommandArgs {
+    /// proposal nevent, note, hex id, shorthand id or `pr/` branch name
+    pub(crate) proposal: String,
+    /// reopen the proposal
+    #[arg(long, action)]
+    pub(crate) open: bool,
+    /// mark the proposal as a draft
+    #[arg(long, action)]
+    pub(crate) draft: bool,
+    /// close the proposal without applying it
+    #[arg(long, action)]
+    pub(crate) closed: bool,
+    /// mark the proposal as applied / merged
+    #[arg(long, action)]
+    pub(crate) applied: bool,
+    /// commits the proposal was applied as. used with --applied
+    #[clap(long, value_parser, num_args = 1.., requires = "applied")]
+    pub(crate) applied_as_commits: Vec<String>,
+    /// commit that merged the proposal. used with --applied
+    #[clap(long, requires = "applied", conflicts_with = "applied_as_commits")]
+    pub(crate) merge_commit: Option<String>,
+}
+
+impl SubCommandArgs {
+    fn status(&self) -> Kind {
+        if self.applied {
+            Kind::GitStatusApplied
+        } else if self.closed {
+            Kind::GitStatusClosed
+        } else if self.draft {
+            Kind::GitStatusDraft
+        } else {
+            Kind::GitStatusOpen
+        }
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+pub async fn launch(cli_args: &Cli, args: &SubCommandArgs) -> Result<()> {
+    let git_repo = Repo::discover().context("failed to find a git repository")?;
+    let git_repo_path = git_repo.get_path()?;
+
+    #[cfg(test)]
+    let mut client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let mut client = Client::default();
+
+    let repo_coordinates = get_repo_coordinates_when_remote_unknown(&git_repo, &client).await?;
+
+    fetching_with_report(git_repo_path, &client, &repo_coordinates).await?;
+
+    let repo_ref = get_repo_ref_from_cache(Some(git_repo_path), &repo_coordinates).await?;
+
+    let proposal =
+        get_proposal_from_cache_by_reference(git_repo_path, &repo_ref, &args.proposal).await?;
+
+    let title = event_to_cover_letter(&proposal)
+        .map(|cl| cl.title)
+        .unwrap_or(proposal.id.to_hex());
+
+    let status = args.status();
+
+    let current_status = get_status_from_events(
+        &proposal,
+        &get_events_from_local_cache(
+            git_repo_path,
+            vec![
+                nostr::Filter::default()
+                    .kinds(status_kinds())
+                    .event(proposal.id),
+            ],
+        )
+        .await?,
+        &repo_ref.maintainers,
+    );
+
+    if current_status.eq(&status)
+        && args.applied_as_commits.is_empty()
+        && args.merge_commit.is_none()
+    {
+        println!("proposal '{title}' is already {}", get_status_name(status));
+        return Ok(());
+    }
+
+    let (signer, user_ref, _) = login::login_or_signup(
+        &Some(&git_repo),
+        &extract_signer_cli_arguments(cli_args).unwrap_or(None),
+        &cli_args.password,
+        Some(&client),
+        true,
+    )
+    .await?;
+
+    if !repo_ref.maintainers.contains(&user_ref.public_key)
+        && !user_ref.public_key.eq(&proposal.pubkey)
+    {
+        bail!("only repository maintainers and the proposal author can change a proposal's status");
+    }
+
+    let mut extra_tags = vec![];
+
+    if status.eq(&Kind::GitStatusApplied) {
+        let (tag_name, commits) = if let Some(merge_commit) = &args.merge_commit {
+            ("merge-commit-id", vec![merge_commit.clone()])
+        } else {
+            ("applied-as-commits", args.applied_as_commits.clone())
+        };
+        for commit in &commits {
+            let commit =
+                str_to_commit_id(commit).context(format!("{commit} is not a valid commit id"))?;
+            if !git_repo.does_commit_exist(&commit.to_string())? {
+                bail!("commit {commit} doesn't exist in the local repository");
+            }
+        }
+        if !commits.is_empty() {
+            extra_tags.push(Tag::custom(
+                TagKind::Custom(std::borrow::Cow::Borrowed(tag_name)),
+                commits.clone(),
+            ));
+            for commit in &commits {
+                extra_tags.push(Tag::from_standardized(TagStandard::Reference(
+                    commit.clone(),
+                )));
+            }
+        }
+
+        // reference the patches of the latest revision as they are what was applied
+        if let Ok(patches) = get_most_recent_patch_with_ancestors(
+            get_all_proposal_patch_events_from_cache(git_repo_path, &repo_ref, &proposal.id)
+                .await?,
+        ) {
+            if let Some(revision_root_id) = patches
+                .iter()
+                .find(|e| event_is_revision_root(e))
+                .map(|e| e.id)
+                .or(patches.first().and_then(|p| get_event_root(p).ok()))
+                .filter(|id| !id.eq(&proposal.id))
+            {
+                extra_tags.push(Tag::from_standardized(TagStandard::Event {
+                    event_id: revision_root_id,
+                    relay_url: repo_ref.relays.first().cloned(),
+                    marker: Some(Marker::Root),
+                    public_key: None,
+                    uppercase: false,
+                }));
+            }
+            for patch in &patches {
+                extra_tags.push(Tag::from_standardized(TagStandard::Event {
+                    event_id: patch.id,
+                    relay_url: repo_ref.relays.first().cloned(),
+                    marker: Some(Marker::Mention),
+                    public_key: None,
+                    uppercase: false,
+                }));
+            }
+        }
+    }
+
+    client.set_signer(signer.clone()).await;
+
+    let event = generate_status_event(&signer, &repo_ref, status, &proposal, extra_tags).await?;
+
+    println!(
+        "marking proposal '{title}' as {}...",
+        get_status_name(status)
+    );
+
+    send_events(
+        &client,
+        Some(git_repo_path),
+        vec![event],
+        user_ref.relays.write(),
+        repo_ref.relays.clone(),
+        !cli_args.disable_cli_spinners,
+        false,
+    )
+    .await
+}
@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result, bail};
+use console::Style;
+use nostr::{Kind, ToBech32, nips::nip19::Nip19Coordinate};
+use nostr_sdk::Timestamp;
+
+use crate::{
+    client,
+    client::{Client, Connect, FetchOptions, get_event_from_global_cache, is_offline},
+    git::{Repo, RepoActions},
+    repo_ref::{
+        RepoRef, get_repo_coordinates_when_remote_unknown,
+        try_and_get_repo_coordinates_when_remote_unknown,
+    },
+};
+
+#[derive(Debug, clap::Args)]
+pub struct SubCommandArgs {
+    /// sync every repository in the global cache, not just the current one
+    #[arg(long, action)]
+    pub(crate) all: bool,
+    /// only fetch events created after this time. a unix timestamp or an age
+    /// such as 30m, 12h, 7d or 2w
+    #[arg(long, value_parser = parse_since, conflicts_with = "full")]
+    pub(crate) since: Option<Timestamp>,
+    /// ignore the checkpoint recorded for each relay and fetch every event
+    #[arg(long, action)]
+    pub(crate) full: bool,
+}
+
+pub async fn launch(args: &SubCommandArgs) -> Result<()> {
+    if is_offline() {
+        bail!("cannot sync in offline mode");
+    }
+
+    let git_repo = Repo::discover().ok();
+
+    #[cfg(test)]
+    let client = <client::MockConnect as client::Connect>::default();
+    #[cfg(not(test))]
+    let client = Client::default();
+
+    let current_coordinate = if let Some(git_repo) = &git_repo {
+        if args.all {
+            try_and_get_repo_coordinates_when_remote_unknown(git_repo)
+                .await
+                .ok()
+        } else {
+            Some(get_repo_coordinates_when_remote_unknown(git_repo, &client).await?)
+        }
+    } else if args.all {
+        None
+    } else {
+        bail!("failed to find a git repository. use --all to sync all cached repositories");
+    };
+
+    let git_repo_path = if let Some(git_repo) = &git_repo {
+        Some(git_repo.get_path()?)
+    } else {
+        None
+    };
+
+    let mut coordinates: Vec<Nip19Coordinate> = current_coordinate.iter().cloned().collect();
+    if args.all {
+        for coordinate in get_cached_repo_coordinates(git_repo_path).await? {
+            if !coordinates.iter().any(|c| {
+                c.public_key.eq(&coordinate.public_key) && c.identifier.eq(&coordinate.identifier)
+            }) {
+                coordinates.push(coordinate);
+            }
+        }
+    }
+    if coordinates.is_empty() {
+        bail!("no repositories found in the global cache");
+    }
+
+    let dim = Style::new().color256(247);
+    let mut failed_to_sync = 0;
+
+    for coordinate in &coordinates {
+        let is_current = current_coordinate.as_ref().is_some_and(|c| {
+            c.public_key.eq(&coordinate.public_key) && c.identifier.eq(&coordinate.identifier)
+        });
+        println!(
+            "syncing {} {}",
+            coordinate.identifier,
+            dim.apply_to(coordinate.to_bech32()?),
+        );
+        let (relay_reports, progress_reporter) = match client
+            .fetch_all(
+                // only announcements and profiles are kept for other repositories,
+                // in the global cache
+                if is_current { git_repo_path } else { None },
+                Some(coordinate),
+                &HashSet::new(),
+                FetchOptions {
+                    since: args.since,
+                    full: args.full,
+                    ..FetchOptions::default()
+                },
+            )
+            .await
+        {
+            Ok(res) => res,
+            // one repository failing shouldn't stop the rest from syncing
+            Err(error) if args.all => {
+                println!("  {}", console::style(format!("error: {error:#}")).red());
+                failed_to_sync += 1;
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        let _ = progress_reporter.clear();
+        if relay_reports.is_empty() {
+            println!("  no relays to sync with");
+        }
+        for relay_report in relay_reports {
+            match relay_report {
+                Ok(report) => println!(
+                    "  {} {}",
+                    report.relay().map(ToString::to_string).unwrap_or_default(),
+                    if report.to_string().is_empty() {
+                        "no updates".to_string()
+                    } else {
+                        format!("updates: {report}")
+                    },
+                ),
+                Err(error) => println!(
+                    "  {}",
+                    console::style(format!("{error:#}").replace("relay pool error:", "error:"))
+                        .red()
+                ),
+            }
+        }
+    }
+    if failed_to_sync > 0 {
+        bail!(
+            "failed to sync {failed_to_sync} of {} repositories",
+            coordinates.len()
+        );
+    }
+    Ok(())
+}
+
+/// coordinates of all repository announcements in the global cache
+async fn get_cached_repo_coordinates(
+    git_repo_path: Option<&std::path::Path>,
+) -> Result<Vec<Nip19Coordinate>> {
+    let mut coordinates = vec![];
+    for event in get_event_from_global_cache(
+        git_repo_path,
+        vec![nostr::Filter::default().kind(Kind::GitRepoAnnouncement)],
+    )
+    .await?
+    {
+        if let Ok(repo_ref) = RepoRef::try_from((event, None)) {
+            coordinates.push(repo_ref.coordinate_with_hint());
+        }
+    }
+    Ok(coordinates)
+}
+
+fn parse_since(value: &str) -> Result<Timestamp> {
+    if let Ok(timestamp) = value.parse::<u64>() {
+        return Ok(Timestamp::from(timestamp));
+    }
+    let mut chars = value.chars();
+    let unit = chars.next_back();
+    let number = chars
+        .as_str()
+        .parse::<u64>()
+        .context("expected a unix timestamp or an age such as 30m, 12h, 7d or 2w")?;
+    let seconds_per_unit: u64 = match unit {
+        Some('s') => 1,
+        Some('m') => 60,
+        Some('h') => 60 * 60,
+        Some('d') => 60 * 60 * 24,
+        Some('w') => 60 * 60 * 24 * 7,
+        _ => bail!("expected a unix timestamp or an age such as 30m, 12h, 7d or 2w"),
+    };
+    let seconds = number
+        .checked_mul(seconds_per_unit)
+        .context(format!("{value} is too far in the past"))?;
+    Ok(Timestamp::from(
+        Timestamp::now().as_u64().saturating_sub(seconds),
+    ))
+}
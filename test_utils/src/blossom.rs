@@ -0,0 +1,93 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+/// minimal in-memory stand-in for a Blossom blob server. it accepts any
+/// authorization and only supports `PUT /upload` and `GET /<sha256>`
+pub struct BlossomServer {
+    pub port: u16,
+    pub blobs: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl BlossomServer {
+    /// listens on a random free port until the test process exits
+    pub fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").context("failed to bind blossom server")?;
+        let port = listener.local_addr()?.port();
+        let blobs: Arc<Mutex<HashMap<String, Vec<u8>>>> = Arc::new(Mutex::new(HashMap::new()));
+        let server_blobs = blobs.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = handle_request(stream, &server_blobs, port);
+            }
+        });
+        Ok(Self { port, blobs })
+    }
+}
+
+fn handle_request(
+    mut stream: TcpStream,
+    blobs: &Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    port: u16,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let (status, content_type, response_body) = match (method.as_str(), path.as_str()) {
+        ("PUT", "/upload") => {
+            let sha256 = format!("{:x}", Sha256::digest(&body));
+            let size = body.len();
+            blobs.lock().unwrap().insert(sha256.clone(), body);
+            (
+                "200 OK",
+                "application/json",
+                format!(
+                    "{{\"url\":\"http://localhost:{port}/{sha256}\",\"sha256\":\"{sha256}\",\"size\":{size},\"type\":\"application/octet-stream\",\"uploaded\":0}}"
+                )
+                .into_bytes(),
+            )
+        }
+        ("GET", path) => match blobs.lock().unwrap().get(path.trim_start_matches('/')) {
+            Some(blob) => ("200 OK", "application/octet-stream", blob.clone()),
+            None => ("404 Not Found", "text/plain", b"not found".to_vec()),
+        },
+        _ => ("404 Not Found", "text/plain", b"not found".to_vec()),
+    };
+    stream.write_all(
+        format!(
+            "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            response_body.len()
+        )
+        .as_bytes(),
+    )?;
+    stream.write_all(&response_body)?;
+    stream.flush()?;
+    Ok(())
+}
@@ -21,6 +21,7 @@ use rexpect::session::{Options, PtySession};
 use strip_ansi_escapes::strip_str;
 use tokio::runtime::Handle;
 
+pub mod blossom;
 pub mod git;
 pub mod relay;
 
@@ -803,6 +804,19 @@ impl CliTester {
         }
     }
 
+    /// for ngit commands, such as fork, that run git against a nostr url
+    pub fn new_with_remote_helper_from_dir<I, S>(dir: &PathBuf, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        Self {
+            rexpect_session: ngit_with_remote_helper_rexpect_with_from_dir(dir, args, 4000)
+                .expect("rexpect to spawn new process"),
+            formatter: ColorfulTheme::default(),
+        }
+    }
+
     pub fn new_git_with_remote_helper_from_dir<I, S>(dir: &PathBuf, args: I) -> Self
     where
         I: IntoIterator<Item = S>,
@@ -1062,15 +1076,8 @@ pub fn remote_helper_rexpect_with_from_dir(
     )
 }
 
-pub fn git_with_remote_helper_rexpect_with_from_dir<I, S>(
-    dir: &PathBuf,
-    args: I,
-    timeout_ms: u64,
-) -> Result<PtySession>
-where
-    I: IntoIterator<Item = S>,
-    S: AsRef<std::ffi::OsStr>,
-{
+/// a copy of git's exec path with git-remote-nostr added so git can find it
+fn git_exec_path_with_remote_helper(dir: &Path) -> Result<PathBuf> {
     let git_exec_dir = dir.parent().unwrap().join("tmpgit-git-exec-path");
     if !git_exec_dir.exists() {
         std::fs::create_dir_all(&git_exec_dir)?;
@@ -1095,9 +1102,46 @@ where
         assert_cmd::cargo::cargo_bin("git-remote-nostr"),
         git_exec_dir.join("git-remote-nostr"),
     )?;
+    Ok(git_exec_dir)
+}
 
+pub fn git_with_remote_helper_rexpect_with_from_dir<I, S>(
+    dir: &PathBuf,
+    args: I,
+    timeout_ms: u64,
+) -> Result<PtySession>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
     let mut cmd = std::process::Command::new("git");
-    cmd.env("GIT_EXEC_PATH", git_exec_dir);
+    cmd.env("GIT_EXEC_PATH", git_exec_path_with_remote_helper(dir)?);
+    cmd.env("NGITTEST", "TRUE");
+    cmd.env("RUST_BACKTRACE", "0");
+    cmd.current_dir(dir);
+    cmd.args(args);
+    // using branch for PR https://github.com/rust-cli/rexpect/pull/103 to strip ansi escape codes
+    rexpect::session::spawn_with_options(
+        cmd,
+        Options {
+            timeout_ms: Some(timeout_ms),
+            strip_ansi_escape_codes: true,
+        },
+    )
+    .context("spawning failed")
+}
+
+pub fn ngit_with_remote_helper_rexpect_with_from_dir<I, S>(
+    dir: &PathBuf,
+    args: I,
+    timeout_ms: u64,
+) -> Result<PtySession>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    let mut cmd = std::process::Command::new(assert_cmd::cargo::cargo_bin("ngit"));
+    cmd.env("GIT_EXEC_PATH", git_exec_path_with_remote_helper(dir)?);
     cmd.env("NGITTEST", "TRUE");
     cmd.env("RUST_BACKTRACE", "0");
     cmd.current_dir(dir);
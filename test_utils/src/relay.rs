@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
+use negentropy::{Id, Negentropy, NegentropyStorageVector};
 use nostr::{ClientMessage, JsonUtil, RelayMessage};
 
 use crate::CliTester;
@@ -15,8 +16,13 @@ pub struct Relay<'a> {
     clients: HashMap<u64, simple_websockets::Responder>,
     pub events: Vec<nostr::Event>,
     pub reqs: Vec<Vec<nostr::Filter>>,
+    /// filters of NIP-77 NEG-OPEN messages received
+    pub negentropy_reqs: Vec<nostr::Filter>,
     event_listener: Option<ListenerEventFunc<'a>>,
     req_listener: Option<ListenerReqFunc<'a>>,
+    supports_negentropy: bool,
+    negentropy_sessions:
+        HashMap<(u64, nostr::SubscriptionId), Negentropy<'static, NegentropyStorageVector>>,
 }
 
 impl<'a> Relay<'a> {
@@ -31,12 +37,85 @@ impl<'a> Relay<'a> {
             port,
             events: vec![],
             reqs: vec![],
+            negentropy_reqs: vec![],
             event_hub,
             clients: HashMap::new(),
             event_listener,
             req_listener,
+            supports_negentropy: false,
+            negentropy_sessions: HashMap::new(),
         }
     }
+
+    /// respond to NIP-77 negentropy messages rather than rejecting them with
+    /// NEG-ERR
+    pub fn with_negentropy(mut self) -> Self {
+        self.supports_negentropy = true;
+        self
+    }
+
+    fn respond_negentropy(
+        &mut self,
+        client_id: u64,
+        subscription_id: nostr::SubscriptionId,
+        message: &str,
+    ) -> Result<bool> {
+        let responder = self.clients.get(&client_id).unwrap();
+        let response = match self
+            .negentropy_sessions
+            .get_mut(&(client_id, subscription_id.clone()))
+        {
+            None => RelayMessage::NegErr {
+                subscription_id: std::borrow::Cow::Owned(subscription_id),
+                message: std::borrow::Cow::Borrowed("closed: unknown subscription"),
+            },
+            Some(negentropy) => RelayMessage::NegMsg {
+                subscription_id: std::borrow::Cow::Owned(subscription_id),
+                message: std::borrow::Cow::Owned(hex::encode(
+                    negentropy
+                        .reconcile(&hex::decode(message).context("invalid negentropy message")?)
+                        .context("failed to reconcile negentropy message")?,
+                )),
+            },
+        };
+        Ok(responder.send(simple_websockets::Message::Text(response.as_json())))
+    }
+
+    fn open_negentropy_session(
+        &mut self,
+        client_id: u64,
+        subscription_id: nostr::SubscriptionId,
+        filter: &nostr::Filter,
+        initial_message: &str,
+    ) -> Result<bool> {
+        if !self.supports_negentropy {
+            let responder = self.clients.get(&client_id).unwrap();
+            return Ok(responder.send(simple_websockets::Message::Text(
+                RelayMessage::NegErr {
+                    subscription_id: std::borrow::Cow::Owned(subscription_id),
+                    message: std::borrow::Cow::Borrowed("blocked: negentropy not supported"),
+                }
+                .as_json(),
+            )));
+        }
+        let mut storage = NegentropyStorageVector::new();
+        for event in self.events.iter().filter(|e| filter.match_event(e)) {
+            storage
+                .insert(
+                    event.created_at.as_u64(),
+                    Id::from_byte_array(event.id.to_bytes()),
+                )
+                .context("failed to add event to negentropy storage")?;
+        }
+        storage
+            .seal()
+            .context("failed to seal negentropy storage")?;
+        self.negentropy_sessions.insert(
+            (client_id, subscription_id.clone()),
+            Negentropy::owned(storage, 0).context("failed to create negentropy session")?,
+        );
+        self.respond_negentropy(client_id, subscription_id, initial_message)
+    }
     pub fn respond_ok(
         &self,
         client_id: u64,
@@ -67,6 +146,25 @@ impl<'a> Relay<'a> {
         )))
     }
 
+    /// end a subscription with CLOSED, as relays do for filters they don't
+    /// support
+    pub fn respond_closed(
+        &self,
+        client_id: u64,
+        subscription_id: nostr::SubscriptionId,
+        message: &str,
+    ) -> Result<bool> {
+        let responder = self.clients.get(&client_id).unwrap();
+
+        Ok(responder.send(simple_websockets::Message::Text(
+            RelayMessage::Closed {
+                subscription_id: std::borrow::Cow::Owned(subscription_id),
+                message: std::borrow::Cow::Owned(message.to_string()),
+            }
+            .as_json(),
+        )))
+    }
+
     /// send events and eose
     pub fn respond_events(
         &self,
@@ -171,6 +269,39 @@ impl<'a> Relay<'a> {
                         // respond with events
                         // respond with EOSE
                     }
+                    if let simple_websockets::Message::Text(s) = &message {
+                        match ClientMessage::from_json(s) {
+                            Ok(ClientMessage::NegOpen {
+                                subscription_id,
+                                filter,
+                                initial_message,
+                                ..
+                            }) => {
+                                self.negentropy_reqs.push(filter.clone().into_owned());
+                                self.open_negentropy_session(
+                                    client_id,
+                                    subscription_id.into_owned(),
+                                    &filter,
+                                    &initial_message,
+                                )?;
+                            }
+                            Ok(ClientMessage::NegMsg {
+                                subscription_id,
+                                message,
+                            }) => {
+                                self.respond_negentropy(
+                                    client_id,
+                                    subscription_id.into_owned(),
+                                    &message,
+                                )?;
+                            }
+                            Ok(ClientMessage::NegClose { subscription_id }) => {
+                                self.negentropy_sessions
+                                    .remove(&(client_id, subscription_id.into_owned()));
+                            }
+                            _ => {}
+                        }
+                    }
                     if is_nclose(&message) {
                         println!("{} recieved nostr close", self.port);
                         // break;
@@ -0,0 +1,81 @@
+use anyhow::Result;
+use futures::join;
+use serial_test::serial;
+use test_utils::{git::GitTestRepo, relay::Relay, *};
+
+mod when_proposal_branch_doesnt_exist {
+    use super::*;
+
+    /// returns (originating_repo, test_repo)
+    async fn prep_and_run() -> Result<(GitTestRepo, GitTestRepo)> {
+        // fallback (51,52) user write (53, 55) repo (55, 56)
+        let (mut r51, mut r52, mut r53, mut r55, mut r56) = (
+            Relay::new(8051, None, None),
+            Relay::new(8052, None, None),
+            Relay::new(8053, None, None),
+            Relay::new(8055, None, None),
+            Relay::new(8056, None, None),
+        );
+
+        r51.events.push(generate_test_key_1_relay_list_event());
+        r51.events.push(generate_test_key_1_metadata_event("fred"));
+        r51.events.push(generate_repo_ref_event());
+
+        r55.events.push(generate_repo_ref_event());
+        r55.events.push(generate_test_key_1_metadata_event("fred"));
+        r55.events.push(generate_test_key_1_relay_list_event());
+
+        let cli_tester_handle = std::thread::spawn(
+            move || -> Result<(GitTestRepo, GitTestRepo)> {
+                let originating_repo = cli_tester_create_proposals()?;
+                let branch_name =
+                    get_proposal_branch_name(&originating_repo, FEATURE_BRANCH_NAME_1)?;
+
+                let test_repo = GitTestRepo::default();
+                test_repo.populate()?;
+                let mut p = CliTester::new_from_dir(&test_repo.dir, ["checkout", &branch_name]);
+                p.expect("fetching updates...\r\n")?;
+                p.expect_eventually("\r\n")?; // some updates listed here
+                p.expect_end_with(&format!(
+                    "checked out proposal as '{branch_name}' branch (2 ahead 0 behind 'main')\r\n"
+                ))?;
+
+                // running again finds the branch already up-to-date
+                let mut p = CliTester::new_from_dir(&test_repo.dir, ["checkout", &branch_name]);
+                p.expect("fetching updates...\r\n")?;
+                p.expect_eventually("\r\n")?; // may be 'no updates' or some updates
+                p.expect_end_with(&format!(
+                    "checked out up-to-date proposal branch '{branch_name}' (2 ahead 0 behind 'main')\r\n"
+                ))?;
+
+                for p in [51, 52, 53, 55, 56] {
+                    relay::shutdown_relay(8000 + p)?;
+                }
+                Ok((originating_repo, test_repo))
+            },
+        );
+
+        // launch relay
+        let _ = join!(
+            r51.listen_until_close(),
+            r52.listen_until_close(),
+            r53.listen_until_close(),
+            r55.listen_until_close(),
+            r56.listen_until_close(),
+        );
+        cli_tester_handle.join().unwrap()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn proposal_branch_checked_out_at_latest_revision_tip() -> Result<()> {
+        let (originating_repo, test_repo) = prep_and_run().await?;
+        let branch_name = get_proposal_branch_name(&test_repo, FEATURE_BRANCH_NAME_1)?;
+        assert_eq!(branch_name, test_repo.get_checked_out_branch_name()?);
+        assert_eq!(
+            originating_repo.get_tip_of_local_branch(FEATURE_BRANCH_NAME_1)?,
+            test_repo.get_tip_of_local_branch(&branch_name)?,
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use futures::{executor::block_on, join};
+use nostr_sdk::Kind;
+use serial_test::serial;
+use test_utils::{git::GitTestRepo, relay::Relay, *};
+
+fn get_proposal_id(originating_repo: &GitTestRepo, branch_name: &str) -> Result<String> {
+    block_on(get_events_from_cache(
+        &originating_repo.dir,
+        vec![
+            nostr::Filter::default()
+                .kind(Kind::GitPatch)
+                .hashtag("root"),
+        ],
+    ))?
+    .iter()
+    .find(|e| {
+        e.tags
+            .iter()
+            .any(|t| t.as_slice()[0].eq("branch-name") && t.as_slice()[1].eq(branch_name))
+    })
+    .map(|e| e.id.to_hex())
+    .context("failed to find proposal root event in cache")
+}
+
+mod comment_on_proposal {
+    use super::*;
+
+    async fn prep_and_run() -> Result<(Vec<nostr::Event>, String)> {
+        // fallback (51,52) user write (53, 55) repo (55, 56)
+        let (mut r51, mut r52, mut r53, mut r55, mut r56) = (
+            Relay::new(8051, None, None),
+            Relay::new(8052, None, None),
+            Relay::new(8053, None, None),
+            Relay::new(8055, None, None),
+            Relay::new(8056, None, None),
+        );
+
+        r51.events.push(generate_test_key_1_relay_list_event());
+        r51.events.push(generate_test_key_1_metadata_event("fred"));
+        r51.events.push(generate_repo_ref_event());
+
+        r55.events.push(generate_repo_ref_event());
+        r55.events.push(generate_test_key_1_metadata_event("fred"));
+        r55.events.push(generate_test_key_1_relay_list_event());
+
+        let cli_tester_handle = std::thread::spawn(move || -> Result<String> {
+            let originating_repo = cli_tester_create_proposals()?;
+            let proposal_id = get_proposal_id(&originating_repo, FEATURE_BRANCH_NAME_1)?;
+
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+            let mut p = CliTester::new_from_dir(
+                &test_repo.dir,
+                [
+                    "--nsec",
+                    TEST_KEY_1_NSEC,
+                    "--password",
+                    TEST_PASSWORD,
+                    "--disable-cli-spinners",
+                    "comment",
+                    &proposal_id,
+                    "--message",
+                    "looks good to me",
+                ],
+            );
+            p.expect("fetching updates...\r\n")?;
+            p.expect_eventually("\r\n")?; // some updates listed here
+            p.expect(format!("commenting on '\"{PROPOSAL_TITLE_1}\"'\r\n"))?;
+            p.expect("posting comment...\r\n")?;
+            p.expect_end_eventually()?;
+
+            for p in [51, 52, 53, 55, 56] {
+                relay::shutdown_relay(8000 + p)?;
+            }
+            Ok(proposal_id)
+        });
+
+        // launch relay
+        let _ = join!(
+            r51.listen_until_close(),
+            r52.listen_until_close(),
+            r53.listen_until_close(),
+            r55.listen_until_close(),
+            r56.listen_until_close(),
+        );
+        let proposal_id = cli_tester_handle.join().unwrap()?;
+        Ok((r55.events, proposal_id))
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn comment_sent_to_repo_relays_with_proposal_as_root_and_parent() -> Result<()> {
+        let (r55_events, proposal_id) = prep_and_run().await?;
+        let comment = r55_events
+            .iter()
+            .find(|e| e.kind.eq(&Kind::Comment))
+            .expect("comment event sent to repo relay");
+        assert_eq!(comment.content, "looks good to me");
+        for tag_name in ["E", "e"] {
+            assert!(
+                comment
+                    .tags
+                    .iter()
+                    .any(|t| t.as_slice()[0].eq(tag_name) && t.as_slice()[1].eq(&proposal_id)),
+                "{tag_name} tag should reference the proposal"
+            );
+        }
+        Ok(())
+    }
+}
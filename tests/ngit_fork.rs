@@ -0,0 +1,177 @@
+use std::env::current_dir;
+
+use anyhow::{Context, Result};
+use futures::join;
+use nostr::nips::{nip01::Coordinate, nip19::Nip19Coordinate};
+use nostr_sdk::{Kind, RelayUrl, ToBech32, secp256k1::rand};
+use serial_test::serial;
+use test_utils::{git::GitTestRepo, relay::Relay, *};
+
+fn get_upstream_nostr_url() -> Result<String> {
+    let repo_event = generate_repo_ref_event();
+    let naddr = Nip19Coordinate {
+        coordinate: Coordinate {
+            kind: Kind::GitRepoAnnouncement,
+            public_key: repo_event.pubkey,
+            identifier: repo_event.tags.identifier().unwrap().to_string(),
+        },
+        relays: vec![
+            RelayUrl::parse("ws://localhost:8055").unwrap(),
+            RelayUrl::parse("ws://localhost:8056").unwrap(),
+        ],
+    }
+    .to_bech32()?;
+    Ok(format!("nostr://{naddr}"))
+}
+
+struct Forked {
+    r55_events: Vec<nostr::Event>,
+    fork_keys: nostr::Keys,
+    fork_repo: GitTestRepo,
+    upstream_tip: String,
+}
+
+/// forks the upstream repository announced on the relays, with its git
+/// server a local bare repository, using a key that isn't a maintainer
+async fn prep_and_run() -> Result<Forked> {
+    let source_repo = GitTestRepo::default();
+    source_repo.populate()?;
+    let upstream_repo = GitTestRepo::recreate_as_bare(&source_repo)?;
+    let upstream_tip = upstream_repo.get_tip_of_local_branch("main")?.to_string();
+    let events = vec![
+        generate_test_key_1_metadata_event("fred"),
+        generate_test_key_1_relay_list_event(),
+        generate_repo_ref_event_with_git_server(vec![
+            upstream_repo.dir.to_str().unwrap().to_string(),
+        ]),
+    ];
+
+    // fallback (51,52) user write (53, 55) repo (55, 56)
+    let (mut r51, mut r52, mut r53, mut r55, mut r56) = (
+        Relay::new(8051, None, None),
+        Relay::new(8052, None, None),
+        Relay::new(8053, None, None),
+        Relay::new(8055, None, None),
+        Relay::new(8056, None, None),
+    );
+    r51.events = events.clone();
+    r55.events = events;
+
+    let fork_keys = nostr::Keys::generate();
+    let nsec = fork_keys.secret_key().to_bech32()?;
+    let cli_tester_handle = std::thread::spawn(move || -> Result<GitTestRepo> {
+        let fork_dir = current_dir()?.join(format!("tmpgit-fork{}", rand::random::<u64>()));
+        let mut p = CliTester::new_with_remote_helper_from_dir(
+            &upstream_repo.dir,
+            [
+                "--nsec",
+                &nsec,
+                "--password",
+                TEST_PASSWORD,
+                "--disable-cli-spinners",
+                "fork",
+                &get_upstream_nostr_url()?,
+                fork_dir.to_str().unwrap(),
+                "--clone-url",
+                "https://example.com/fork.git",
+            ],
+        );
+        p.expect("cloning upstream repository...\r\n")?;
+        p.expect_eventually("publishing fork announcement...\r\n")?;
+        p.expect_eventually(format!("forked into {}\r\n", fork_dir.display()))?;
+        p.expect_end_eventually()?;
+
+        for p in [51, 52, 53, 55, 56] {
+            relay::shutdown_relay(8000 + p)?;
+        }
+        GitTestRepo::open(&fork_dir)
+    });
+
+    // launch relay
+    let _ = join!(
+        r51.listen_until_close(),
+        r52.listen_until_close(),
+        r53.listen_until_close(),
+        r55.listen_until_close(),
+        r56.listen_until_close(),
+    );
+    let fork_repo = cli_tester_handle.join().unwrap()?;
+    Ok(Forked {
+        r55_events: r55.events,
+        fork_keys,
+        fork_repo,
+        upstream_tip,
+    })
+}
+
+fn get_fork_announcement(forked: &Forked) -> Result<&nostr::Event> {
+    forked
+        .r55_events
+        .iter()
+        .find(|e| {
+            e.kind.eq(&Kind::GitRepoAnnouncement) && e.pubkey.eq(&forked.fork_keys.public_key())
+        })
+        .context("fork announcement sent to upstream relays")
+}
+
+fn remote_url(fork_repo: &GitTestRepo, name: &str) -> Result<String> {
+    Ok(fork_repo
+        .git_repo
+        .find_remote(name)?
+        .url()
+        .context("remote should have a url")?
+        .to_string())
+}
+
+#[tokio::test]
+#[serial]
+async fn announcement_references_upstream_with_fork_a_tag() -> Result<()> {
+    let forked = prep_and_run().await?;
+    let announcement = get_fork_announcement(&forked)?;
+    let upstream = generate_repo_ref_event();
+    let a_tag = announcement
+        .tags
+        .iter()
+        .find(|t| t.as_slice()[0].eq("a"))
+        .context("fork announcement has an a tag")?;
+    assert_eq!(
+        a_tag.as_slice()[1],
+        format!(
+            "{}:{}:{}",
+            Kind::GitRepoAnnouncement.as_u16(),
+            upstream.pubkey,
+            upstream.tags.identifier().unwrap(),
+        )
+    );
+    assert_eq!(a_tag.as_slice().last().unwrap(), "fork");
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn earliest_unique_commit_is_upstream_tip() -> Result<()> {
+    let forked = prep_and_run().await?;
+    let announcement = get_fork_announcement(&forked)?;
+    assert!(announcement.tags.iter().any(|t| {
+        t.as_slice().eq(&[
+            "r".to_string(),
+            forked.upstream_tip.clone(),
+            "euc".to_string(),
+        ])
+    }));
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn origin_is_fork_and_upstream_is_upstream() -> Result<()> {
+    let forked = prep_and_run().await?;
+    assert_eq!(
+        remote_url(&forked.fork_repo, "upstream")?,
+        get_upstream_nostr_url()?
+    );
+    let origin = remote_url(&forked.fork_repo, "origin")?;
+    assert!(origin.starts_with("nostr://"));
+    assert!(origin.contains(&forked.fork_keys.public_key().to_bech32()?));
+    Ok(())
+}
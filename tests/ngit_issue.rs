@@ -0,0 +1,109 @@
+use anyhow::Result;
+use futures::join;
+use nostr_sdk::Kind;
+use serial_test::serial;
+use test_utils::{git::GitTestRepo, relay::Relay, *};
+
+static ISSUE_TITLE: &str = "build fails on main";
+static ISSUE_DESCRIPTION: &str = "cargo build errors after the last merge";
+
+fn cli_tester_new_issue(test_repo: &GitTestRepo) -> CliTester {
+    CliTester::new_from_dir(
+        &test_repo.dir,
+        [
+            "--nsec",
+            TEST_KEY_1_NSEC,
+            "--password",
+            TEST_PASSWORD,
+            "--disable-cli-spinners",
+            "issue",
+            "new",
+            "--title",
+            ISSUE_TITLE,
+            "--description",
+            ISSUE_DESCRIPTION,
+            "--label",
+            "bug",
+        ],
+    )
+}
+
+mod new_then_list {
+    use super::*;
+
+    async fn prep_and_run() -> Result<(Vec<nostr::Event>, String)> {
+        // fallback (51,52) user write (53, 55) repo (55, 56)
+        let (mut r51, mut r52, mut r53, mut r55, mut r56) = (
+            Relay::new(8051, None, None),
+            Relay::new(8052, None, None),
+            Relay::new(8053, None, None),
+            Relay::new(8055, None, None),
+            Relay::new(8056, None, None),
+        );
+
+        r51.events.push(generate_test_key_1_relay_list_event());
+        r51.events.push(generate_test_key_1_metadata_event("fred"));
+        r51.events.push(generate_repo_ref_event());
+
+        r55.events.push(generate_repo_ref_event());
+        r55.events.push(generate_test_key_1_metadata_event("fred"));
+        r55.events.push(generate_test_key_1_relay_list_event());
+
+        let cli_tester_handle = std::thread::spawn(move || -> Result<String> {
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+
+            let mut p = cli_tester_new_issue(&test_repo);
+            p.expect_eventually("posting issue...\r\n")?;
+            p.expect_end_eventually()?;
+
+            let mut p = CliTester::new_from_dir(&test_repo.dir, ["issue", "list"]);
+            p.expect("fetching updates...\r\n")?;
+            p.expect_eventually("\r\n")?; // may be 'no updates' or some updates
+            let output = p.expect_end_eventually()?;
+
+            for p in [51, 52, 53, 55, 56] {
+                relay::shutdown_relay(8000 + p)?;
+            }
+            Ok(output)
+        });
+
+        // launch relay
+        let _ = join!(
+            r51.listen_until_close(),
+            r52.listen_until_close(),
+            r53.listen_until_close(),
+            r55.listen_until_close(),
+            r56.listen_until_close(),
+        );
+        let output = cli_tester_handle.join().unwrap()?;
+        Ok((r55.events, output))
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn issue_with_subject_label_and_description_sent_to_repo_relays() -> Result<()> {
+        let (r55_events, _) = prep_and_run().await?;
+        let issue = r55_events
+            .iter()
+            .find(|e| e.kind.eq(&Kind::GitIssue))
+            .expect("issue event sent to repo relay");
+        assert_eq!(issue.content, ISSUE_DESCRIPTION);
+        assert!(
+            issue
+                .tags
+                .iter()
+                .any(|t| t.as_slice()[0].eq("subject") && t.as_slice()[1].eq(ISSUE_TITLE))
+        );
+        assert!(issue.tags.hashtags().any(|h| h.eq("bug")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn list_shows_new_issue_as_open_with_label() -> Result<()> {
+        let (_, output) = prep_and_run().await?;
+        assert!(output.contains(&format!("open {ISSUE_TITLE} [bug]")));
+        Ok(())
+    }
+}
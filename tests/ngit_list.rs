@@ -214,7 +214,7 @@ mod when_main_branch_is_uptodate {
                                 format!(
                                     "create and checkout proposal branch (2 ahead 0 behind 'main')"
                                 ),
-                                format!("apply to current branch with `git am`"),
+                                format!("apply to current branch"),
                                 format!("download to ./patches"),
                                 format!("back"),
                             ])?;
@@ -334,7 +334,7 @@ mod when_main_branch_is_uptodate {
                                 format!(
                                     "create and checkout proposal branch (2 ahead 0 behind 'main')"
                                 ),
-                                format!("apply to current branch with `git am`"),
+                                format!("apply to current branch"),
                                 format!("download to ./patches"),
                                 format!("back"),
                             ])?;
@@ -458,7 +458,7 @@ mod when_main_branch_is_uptodate {
                                 format!(
                                     "create and checkout proposal branch (2 ahead 0 behind 'main')"
                                 ),
-                                format!("apply to current branch with `git am`"),
+                                format!("apply to current branch"),
                                 format!("download to ./patches"),
                                 format!("back"),
                             ])?;
@@ -536,7 +536,7 @@ mod when_main_branch_is_uptodate {
                                 format!(
                                     "create and checkout proposal branch (2 ahead 0 behind 'main')"
                                 ),
-                                format!("apply to current branch with `git am`"),
+                                format!("apply to current branch"),
                                 format!("download to ./patches"),
                                 format!("back"),
                             ])?;
@@ -658,7 +658,7 @@ mod when_main_branch_is_uptodate {
                                 format!(
                                     "create and checkout proposal branch (2 ahead 0 behind 'main')"
                                 ),
-                                format!("apply to current branch with `git am`"),
+                                format!("apply to current branch"),
                                 format!("download to ./patches"),
                                 format!("back"),
                             ])?;
@@ -683,7 +683,7 @@ mod when_main_branch_is_uptodate {
                                 "",
                                 vec![
                                     format!("checkout proposal branch (2 ahead 0 behind 'main')"),
-                                    format!("apply to current branch with `git am`"),
+                                    format!("apply to current branch"),
                                     format!("download to ./patches"),
                                     format!("back"),
                                 ],
@@ -754,7 +754,7 @@ mod when_main_branch_is_uptodate {
                                 format!(
                                     "create and checkout proposal branch (2 ahead 0 behind 'main')"
                                 ),
-                                format!("apply to current branch with `git am`"),
+                                format!("apply to current branch"),
                                 format!("download to ./patches"),
                                 format!("back"),
                             ])?;
@@ -779,7 +779,7 @@ mod when_main_branch_is_uptodate {
                                 "",
                                 vec![
                                     format!("checkout proposal branch (2 ahead 0 behind 'main')"),
-                                    format!("apply to current branch with `git am`"),
+                                    format!("apply to current branch"),
                                     format!("download to ./patches"),
                                     format!("back"),
                                 ],
@@ -870,7 +870,7 @@ mod when_main_branch_is_uptodate {
                                 "",
                                 vec![
                                     format!("checkout proposal branch and apply 1 appendments"),
-                                    format!("apply to current branch with `git am`"),
+                                    format!("apply to current branch"),
                                     format!("download to ./patches"),
                                     format!("back"),
                                 ],
@@ -946,7 +946,7 @@ mod when_main_branch_is_uptodate {
                                 "",
                                 vec![
                                     format!("checkout proposal branch and apply 1 appendments"),
-                                    format!("apply to current branch with `git am`"),
+                                    format!("apply to current branch"),
                                     format!("download to ./patches"),
                                     format!("back"),
                                 ],
@@ -1064,7 +1064,7 @@ mod when_main_branch_is_uptodate {
                                     format!(
                                         "discard unpublished changes and checkout new revision"
                                     ),
-                                    format!("apply to current branch with `git am`"),
+                                    format!("apply to current branch"),
                                     format!("download to ./patches"),
                                     "back".to_string(),
                                 ],
@@ -1148,7 +1148,7 @@ mod when_main_branch_is_uptodate {
                                     format!(
                                         "discard unpublished changes and checkout new revision"
                                     ),
-                                    format!("apply to current branch with `git am`"),
+                                    format!("apply to current branch"),
                                     format!("download to ./patches"),
                                     "back".to_string(),
                                 ],
@@ -1429,7 +1429,7 @@ mod when_main_branch_is_uptodate {
                                 vec![
                                     format!("checkout and overwrite existing proposal branch"),
                                     format!("checkout existing outdated proposal branch"),
-                                    format!("apply to current branch with `git am`"),
+                                    format!("apply to current branch"),
                                     format!("download to ./patches"),
                                     format!("back"),
                                 ],
@@ -1502,7 +1502,7 @@ mod when_main_branch_is_uptodate {
                                     vec![
                                         format!("checkout and overwrite existing proposal branch"),
                                         format!("checkout existing outdated proposal branch"),
-                                        format!("apply to current branch with `git am`"),
+                                        format!("apply to current branch"),
                                         format!("download to ./patches"),
                                         format!("back"),
                                     ],
@@ -1560,3 +1560,137 @@ mod when_main_branch_is_uptodate {
         }
     }
 }
+
+mod non_interactive {
+    use super::*;
+
+    async fn run_list_with_args(args: Vec<&'static str>) -> Result<String> {
+        let (mut r51, mut r52, mut r53, mut r55, mut r56) = (
+            Relay::new(8051, None, None),
+            Relay::new(8052, None, None),
+            Relay::new(8053, None, None),
+            Relay::new(8055, None, None),
+            Relay::new(8056, None, None),
+        );
+
+        r51.events.push(generate_test_key_1_relay_list_event());
+        r51.events.push(generate_test_key_1_metadata_event("fred"));
+        r51.events.push(generate_repo_ref_event());
+
+        r55.events.push(generate_repo_ref_event());
+        r55.events.push(generate_test_key_1_metadata_event("fred"));
+        r55.events.push(generate_test_key_1_relay_list_event());
+
+        let cli_tester_handle = std::thread::spawn(move || -> Result<String> {
+            cli_tester_create_proposals()?;
+
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+            let mut p = CliTester::new_from_dir(&test_repo.dir, [vec!["list"], args].concat());
+
+            p.expect("fetching updates...\r\n")?;
+            p.expect_eventually("\r\n")?; // some updates listed here
+            let output = p.expect_end_eventually()?;
+
+            for p in [51, 52, 53, 55, 56] {
+                relay::shutdown_relay(8000 + p)?;
+            }
+            Ok(output)
+        });
+
+        // launch relay
+        let _ = join!(
+            r51.listen_until_close(),
+            r52.listen_until_close(),
+            r53.listen_until_close(),
+            r55.listen_until_close(),
+            r56.listen_until_close(),
+        );
+        cli_tester_handle.join().unwrap()
+    }
+
+    /// runs `ngit list --json` without a terminal so stdout can be read
+    /// separately from the fetch report written to stderr
+    async fn run_list_json() -> Result<String> {
+        let (mut r51, mut r52, mut r53, mut r55, mut r56) = (
+            Relay::new(8051, None, None),
+            Relay::new(8052, None, None),
+            Relay::new(8053, None, None),
+            Relay::new(8055, None, None),
+            Relay::new(8056, None, None),
+        );
+
+        r51.events.push(generate_test_key_1_relay_list_event());
+        r51.events.push(generate_test_key_1_metadata_event("fred"));
+        r51.events.push(generate_repo_ref_event());
+
+        r55.events.push(generate_repo_ref_event());
+        r55.events.push(generate_test_key_1_metadata_event("fred"));
+        r55.events.push(generate_test_key_1_relay_list_event());
+
+        let cli_tester_handle = std::thread::spawn(move || -> Result<String> {
+            cli_tester_create_proposals()?;
+
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+            let output = std::process::Command::new(assert_cmd::cargo::cargo_bin("ngit"))
+                .env("NGITTEST", "TRUE")
+                .current_dir(&test_repo.dir)
+                .args(["list", "--json"])
+                .output()?;
+
+            for p in [51, 52, 53, 55, 56] {
+                relay::shutdown_relay(8000 + p)?;
+            }
+            Ok(String::from_utf8(output.stdout)?)
+        });
+
+        // launch relay
+        let _ = join!(
+            r51.listen_until_close(),
+            r52.listen_until_close(),
+            r53.listen_until_close(),
+            r55.listen_until_close(),
+            r56.listen_until_close(),
+        );
+        cli_tester_handle.join().unwrap()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn json_lists_all_proposals_without_prompting() -> Result<()> {
+        let stdout = run_list_json().await?;
+        let proposals: Vec<serde_json::Value> = serde_json::from_str(&stdout)?;
+        let titles = proposals
+            .iter()
+            .map(|p| p["title"].as_str().unwrap_or_default())
+            .collect::<Vec<&str>>();
+        for title in [PROPOSAL_TITLE_1, PROPOSAL_TITLE_2, PROPOSAL_TITLE_3] {
+            assert!(titles.contains(&format!("\"{title}\"").as_str()));
+        }
+        assert!(proposals.iter().all(|p| p["status"].eq("open")));
+        assert!(proposals.iter().any(|p| {
+            p["branch_name"]
+                .as_str()
+                .is_some_and(|b| b.starts_with(&format!("pr/{FEATURE_BRANCH_NAME_1}(")))
+        }));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn limit_prints_most_recent_proposals_only() -> Result<()> {
+        let output = run_list_with_args(vec!["--limit", "1"]).await?;
+        assert!(output.contains(PROPOSAL_TITLE_3));
+        assert!(!output.contains(PROPOSAL_TITLE_1));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn status_filter_excludes_open_proposals() -> Result<()> {
+        let output = run_list_with_args(vec!["--status", "closed"]).await?;
+        assert!(output.contains("no matching proposals found"));
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+use anyhow::Result;
+use futures::join;
+use nostr::ToBech32;
+use nostr_sdk::Kind;
+use serial_test::serial;
+use test_utils::{git::GitTestRepo, relay::Relay, *};
+
+fn cli_tester_repo_edit(test_repo: &GitTestRepo, edit_args: &[&str]) -> CliTester {
+    let mut args = vec![
+        "--nsec",
+        TEST_KEY_1_NSEC,
+        "--password",
+        TEST_PASSWORD,
+        "--disable-cli-spinners",
+        "repo",
+        "edit",
+    ];
+    args.extend_from_slice(edit_args);
+    CliTester::new_from_dir(&test_repo.dir, args)
+}
+
+/// runs `run` against a populated test repo with the repository announced on
+/// the relays. returns the events received by repo relay 55 and the output of
+/// `run`
+async fn prep_and_run<T: Send + 'static>(
+    run: impl FnOnce(&GitTestRepo) -> Result<T> + Send + 'static,
+) -> Result<(Vec<nostr::Event>, GitTestRepo, T)> {
+    // fallback (51,52) user write (53, 55) repo (55, 56)
+    let (mut r51, mut r52, mut r53, mut r55, mut r56) = (
+        Relay::new(8051, None, None),
+        Relay::new(8052, None, None),
+        Relay::new(8053, None, None),
+        Relay::new(8055, None, None),
+        Relay::new(8056, None, None),
+    );
+
+    r51.events.push(generate_test_key_1_relay_list_event());
+    r51.events.push(generate_test_key_1_metadata_event("fred"));
+    r51.events.push(generate_repo_ref_event());
+
+    r55.events.push(generate_repo_ref_event());
+    r55.events.push(generate_test_key_1_metadata_event("fred"));
+    r55.events.push(generate_test_key_1_relay_list_event());
+
+    let cli_tester_handle = std::thread::spawn(move || -> Result<(GitTestRepo, T)> {
+        let test_repo = GitTestRepo::default();
+        test_repo.populate()?;
+        let output = run(&test_repo)?;
+        for p in [51, 52, 53, 55, 56] {
+            relay::shutdown_relay(8000 + p)?;
+        }
+        Ok((test_repo, output))
+    });
+
+    // launch relay
+    let _ = join!(
+        r51.listen_until_close(),
+        r52.listen_until_close(),
+        r53.listen_until_close(),
+        r55.listen_until_close(),
+        r56.listen_until_close(),
+    );
+    let (test_repo, output) = cli_tester_handle.join().unwrap()?;
+    Ok((r55.events, test_repo, output))
+}
+
+fn get_announcements(events: &[nostr::Event]) -> Vec<&nostr::Event> {
+    events
+        .iter()
+        .filter(|e| e.kind.eq(&Kind::GitRepoAnnouncement))
+        .collect()
+}
+
+fn tag_values(event: &nostr::Event, tag_name: &str) -> Vec<String> {
+    event
+        .tags
+        .iter()
+        .find(|t| t.as_slice()[0].eq(tag_name))
+        .map(|t| {
+            t.as_slice()[1..]
+                .iter()
+                .map(|v| v.trim_end_matches('/').to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+mod edit {
+    use super::*;
+
+    mod add_and_remove_each_list {
+        use super::*;
+
+        static NEW_CLONE_URL: &str = "https://example.com/new.git";
+        static NEW_WEB: &str = "https://example.com";
+
+        /// returns the published announcement and the public key of the added
+        /// maintainer
+        async fn prep_and_run_edit() -> Result<(nostr::Event, nostr::PublicKey)> {
+            let new_maintainer = nostr::Keys::generate().public_key();
+            let new_maintainer_npub = new_maintainer.to_bech32()?;
+            let (r55_events, _, ()) = prep_and_run(move |test_repo| {
+                let mut p = cli_tester_repo_edit(
+                    test_repo,
+                    &[
+                        "--add-clone-url",
+                        NEW_CLONE_URL,
+                        "--remove-clone-url",
+                        "git:://123.gitexample.com/test",
+                        "--add-web",
+                        NEW_WEB,
+                        "--remove-web",
+                        "https://exampleproject.xyz",
+                        "--add-relay",
+                        "ws://localhost:8053",
+                        "--remove-relay",
+                        "ws://localhost:8056",
+                        "--add-maintainer",
+                        &new_maintainer_npub,
+                        "--remove-maintainer",
+                        TEST_KEY_2_NPUB,
+                    ],
+                );
+                p.expect("fetching updates...\r\n")?;
+                p.expect_eventually("\r\n")?; // some updates listed here
+                p.expect_eventually("publishing updated repository announcement...\r\n")?;
+                p.expect_end_eventually()?;
+                Ok(())
+            })
+            .await?;
+            let announcements = get_announcements(&r55_events);
+            assert_eq!(announcements.len(), 2);
+            Ok(((*announcements[1]).clone(), new_maintainer))
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn clone_url_added_and_removed() -> Result<()> {
+            let (announcement, _) = prep_and_run_edit().await?;
+            assert_eq!(tag_values(&announcement, "clone"), vec![NEW_CLONE_URL]);
+            Ok(())
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn web_added_and_removed() -> Result<()> {
+            let (announcement, _) = prep_and_run_edit().await?;
+            assert_eq!(
+                tag_values(&announcement, "web"),
+                vec!["https://gitworkshop.dev/123", NEW_WEB]
+            );
+            Ok(())
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn relay_added_and_removed() -> Result<()> {
+            let (announcement, _) = prep_and_run_edit().await?;
+            assert_eq!(
+                tag_values(&announcement, "relays"),
+                vec!["ws://localhost:8055", "ws://localhost:8053"]
+            );
+            Ok(())
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn maintainer_added_and_removed() -> Result<()> {
+            let (announcement, new_maintainer) = prep_and_run_edit().await?;
+            assert_eq!(
+                tag_values(&announcement, "maintainers"),
+                vec![TEST_KEY_1_PUBKEY_HEX.to_string(), new_maintainer.to_hex()]
+            );
+            Ok(())
+        }
+    }
+
+    mod without_changes {
+        use super::*;
+
+        #[tokio::test]
+        #[serial]
+        async fn errors_and_publishes_nothing() -> Result<()> {
+            let (r55_events, _, ()) = prep_and_run(|test_repo| {
+                let mut p = cli_tester_repo_edit(test_repo, &[]);
+                p.expect("fetching updates...\r\n")?;
+                p.expect_eventually("\r\n")?; // some updates listed here
+                p.expect_end_with(
+                    "Error: no changes to the repository announcement were specified\r\n",
+                )?;
+                Ok(())
+            })
+            .await?;
+            assert_eq!(get_announcements(&r55_events).len(), 1);
+            Ok(())
+        }
+    }
+
+    mod removing_yourself_as_maintainer {
+        use super::*;
+
+        #[tokio::test]
+        #[serial]
+        async fn errors_and_publishes_nothing() -> Result<()> {
+            let (r55_events, _, ()) = prep_and_run(|test_repo| {
+                let mut p =
+                    cli_tester_repo_edit(test_repo, &["--remove-maintainer", TEST_KEY_1_NPUB]);
+                p.expect("fetching updates...\r\n")?;
+                p.expect_eventually("\r\n")?; // some updates listed here
+                p.expect_end_eventually_with(
+                    "Error: you cannot remove yourself as a maintainer. ask another maintainer to remove you instead\r\n",
+                )?;
+                Ok(())
+            })
+            .await?;
+            assert_eq!(get_announcements(&r55_events).len(), 1);
+            Ok(())
+        }
+    }
+
+    mod update_maintainers_yaml {
+        use super::*;
+
+        #[tokio::test]
+        #[serial]
+        async fn rewritten_with_updated_relays_and_maintainers() -> Result<()> {
+            let (r55_events, test_repo, ()) = prep_and_run(|test_repo| {
+                let mut p = cli_tester_repo_edit(
+                    test_repo,
+                    &[
+                        "--add-relay",
+                        "ws://localhost:8053",
+                        "--remove-maintainer",
+                        TEST_KEY_2_NPUB,
+                        "--update-maintainers-yaml",
+                    ],
+                );
+                p.expect("fetching updates...\r\n")?;
+                p.expect_eventually("\r\n")?; // some updates listed here
+                p.expect_eventually("publishing updated repository announcement...\r\n")?;
+                p.expect_end_eventually_with("maintainers.yaml updated. commit and push.\r\n")?;
+                Ok(())
+            })
+            .await?;
+            assert_eq!(get_announcements(&r55_events).len(), 2);
+            let yaml = std::fs::read_to_string(test_repo.dir.join("maintainers.yaml"))?;
+            assert!(yaml.contains("ws://localhost:8053"));
+            assert!(yaml.contains(TEST_KEY_1_NPUB));
+            assert!(!yaml.contains(TEST_KEY_2_NPUB));
+            Ok(())
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn rewritten_without_publishing_when_announcement_unchanged() -> Result<()> {
+            let (r55_events, test_repo, ()) = prep_and_run(|test_repo| {
+                let mut p = cli_tester_repo_edit(test_repo, &["--update-maintainers-yaml"]);
+                p.expect("fetching updates...\r\n")?;
+                p.expect_eventually("\r\n")?; // some updates listed here
+                p.expect_end_with("maintainers.yaml updated. commit and push.\r\n")?;
+                Ok(())
+            })
+            .await?;
+            assert_eq!(get_announcements(&r55_events).len(), 1);
+            let yaml = std::fs::read_to_string(test_repo.dir.join("maintainers.yaml"))?;
+            assert!(yaml.contains(TEST_KEY_1_NPUB));
+            assert!(yaml.contains(TEST_KEY_2_NPUB));
+            Ok(())
+        }
+    }
+}
+
+mod show {
+    use super::*;
+
+    #[tokio::test]
+    #[serial]
+    async fn json_prints_repository_details_to_stdout() -> Result<()> {
+        let (_, _, stdout) = prep_and_run(|test_repo| {
+            let output = std::process::Command::new(assert_cmd::cargo::cargo_bin("ngit"))
+                .env("NGITTEST", "TRUE")
+                .current_dir(&test_repo.dir)
+                .args(["repo", "show", "--json"])
+                .output()?;
+            Ok(String::from_utf8(output.stdout)?)
+        })
+        .await?;
+        let summary: serde_json::Value = serde_json::from_str(&stdout)?;
+        assert_eq!(
+            summary["identifier"],
+            "9ee507fc4357d7ee16a5d8901bedcd103f23c17d-consider-it-random"
+        );
+        assert_eq!(summary["name"], "example name");
+        assert_eq!(summary["description"], "example description");
+        assert_eq!(
+            summary["clone_urls"],
+            serde_json::json!(["git:://123.gitexample.com/test"])
+        );
+        assert_eq!(
+            summary["maintainers"]
+                .as_array()
+                .map(|maintainers| maintainers
+                    .iter()
+                    .map(|m| m["npub"].as_str().unwrap_or_default())
+                    .collect::<Vec<&str>>()),
+            Some(vec![TEST_KEY_1_NPUB, TEST_KEY_2_NPUB])
+        );
+        assert_eq!(summary["archived"], false);
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+use anyhow::Result;
+use futures::join;
+use nostr::{EventBuilder, Tag, TagKind, TagStandard};
+use nostr_sdk::Kind;
+use serial_test::serial;
+use test_utils::{git::GitTestRepo, relay::Relay, *};
+
+static HASHTAG_REPO_IDENTIFIER: &str = "hashtag-repo";
+static UNRELATED_REPO_IDENTIFIER: &str = "unrelated-repo";
+
+fn generate_repo_event(
+    keys: &nostr::Keys,
+    identifier: &str,
+    name: &str,
+    hashtags: &[&str],
+) -> nostr::Event {
+    let mut tags = vec![
+        Tag::identifier(identifier),
+        Tag::from_standardized(TagStandard::Name(name.into())),
+        Tag::custom(
+            TagKind::Custom(std::borrow::Cow::Borrowed("relays")),
+            vec!["ws://localhost:8055".to_string()],
+        ),
+        Tag::custom(
+            TagKind::Custom(std::borrow::Cow::Borrowed("maintainers")),
+            vec![keys.public_key().to_string()],
+        ),
+    ];
+    for hashtag in hashtags {
+        tags.push(Tag::hashtag(*hashtag));
+    }
+    EventBuilder::new(Kind::GitRepoAnnouncement, "")
+        .tags(tags)
+        .sign_with_keys(keys)
+        .unwrap()
+}
+
+/// a NIP-50 relay that, like many, returns loose matches
+fn respond_ignoring_search(
+    relay: &mut Relay,
+    client_id: u64,
+    subscription_id: nostr::SubscriptionId,
+    mut filters: Vec<nostr::Filter>,
+) -> Result<()> {
+    for filter in &mut filters {
+        filter.search = None;
+    }
+    relay.respond_standard_req(client_id, &subscription_id, &filters)?;
+    Ok(())
+}
+
+fn reject_search(
+    relay: &mut Relay,
+    client_id: u64,
+    subscription_id: nostr::SubscriptionId,
+    filters: Vec<nostr::Filter>,
+) -> Result<()> {
+    if filters.iter().any(|filter| filter.search.is_some()) {
+        relay.respond_closed(
+            client_id,
+            subscription_id,
+            "unsupported: search is not supported",
+        )?;
+    } else {
+        relay.respond_standard_req(client_id, &subscription_id, &filters)?;
+    }
+    Ok(())
+}
+
+/// runs `ngit search` with `args` while relay 51 supports NIP-50 and relay 55
+/// rejects it. returns stdout and the filters relay 55 received
+async fn prep_and_run(args: Vec<&'static str>) -> Result<(String, Vec<nostr::Filter>)> {
+    // fallback (51,52) more fallback (55, 56)
+    let (mut r51, mut r52, mut r53, mut r55, mut r56) = (
+        Relay::new(8051, None, Some(&respond_ignoring_search)),
+        Relay::new(8052, None, None),
+        Relay::new(8053, None, None),
+        Relay::new(8055, None, Some(&reject_search)),
+        Relay::new(8056, None, None),
+    );
+
+    r51.events.push(generate_repo_ref_event());
+    r51.events.push(generate_repo_event(
+        &TEST_KEY_2_KEYS,
+        UNRELATED_REPO_IDENTIFIER,
+        "unrelated",
+        &[],
+    ));
+
+    r55.events.push(generate_repo_event(
+        &TEST_KEY_2_KEYS,
+        HASHTAG_REPO_IDENTIFIER,
+        "other project",
+        &["example"],
+    ));
+
+    let cli_tester_handle = std::thread::spawn(move || -> Result<String> {
+        let test_repo = GitTestRepo::default();
+        test_repo.populate()?;
+        let output = std::process::Command::new(assert_cmd::cargo::cargo_bin("ngit"))
+            .env("NGITTEST", "TRUE")
+            .current_dir(&test_repo.dir)
+            .args([vec!["search"], args].concat())
+            .output()?;
+
+        for p in [51, 52, 53, 55, 56] {
+            relay::shutdown_relay(8000 + p)?;
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    });
+
+    // launch relay
+    let _ = join!(
+        r51.listen_until_close(),
+        r52.listen_until_close(),
+        r53.listen_until_close(),
+        r55.listen_until_close(),
+        r56.listen_until_close(),
+    );
+    let stdout = cli_tester_handle.join().unwrap()?;
+    Ok((stdout, r55.reqs.concat()))
+}
+
+mod text {
+    use super::*;
+
+    #[tokio::test]
+    #[serial]
+    async fn lists_name_match_from_search_relay() -> Result<()> {
+        let (stdout, _) = prep_and_run(vec!["example"]).await?;
+        assert!(stdout.contains(
+            "example name (9ee507fc4357d7ee16a5d8901bedcd103f23c17d-consider-it-random)"
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn lists_hashtag_match_from_relay_rejecting_search() -> Result<()> {
+        let (stdout, r55_filters) = prep_and_run(vec!["example"]).await?;
+        assert!(r55_filters.iter().any(|filter| filter.search.is_some()));
+        assert!(stdout.contains(&format!("other project ({HASHTAG_REPO_IDENTIFIER})")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn excludes_loose_matches_returned_by_search_relay() -> Result<()> {
+        let (stdout, _) = prep_and_run(vec!["example"]).await?;
+        assert!(!stdout.contains(UNRELATED_REPO_IDENTIFIER));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn reports_when_nothing_matches() -> Result<()> {
+        let (stdout, _) = prep_and_run(vec!["nothingmatchesthis"]).await?;
+        assert!(stdout.contains("no repositories found matching 'nothingmatchesthis'"));
+        Ok(())
+    }
+}
+
+mod author {
+    use super::*;
+
+    #[tokio::test]
+    #[serial]
+    async fn only_lists_repositories_announced_by_author() -> Result<()> {
+        let (stdout, _) = prep_and_run(vec!["example", "--author", TEST_KEY_2_NPUB]).await?;
+        assert!(stdout.contains(&format!("other project ({HASHTAG_REPO_IDENTIFIER})")));
+        assert!(!stdout.contains("example name"));
+        Ok(())
+    }
+}
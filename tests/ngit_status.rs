@@ -0,0 +1,97 @@
+use anyhow::Result;
+use futures::join;
+use nostr_sdk::Kind;
+use serial_test::serial;
+use test_utils::{git::GitTestRepo, relay::Relay, *};
+
+fn cli_tester_close_proposal(test_repo: &GitTestRepo, branch_name: &str) -> CliTester {
+    CliTester::new_from_dir(
+        &test_repo.dir,
+        [
+            "--nsec",
+            TEST_KEY_1_NSEC,
+            "--password",
+            TEST_PASSWORD,
+            "--disable-cli-spinners",
+            "status",
+            branch_name,
+            "--closed",
+        ],
+    )
+}
+
+mod close_proposal {
+    use super::*;
+
+    async fn prep_and_run() -> Result<Vec<nostr::Event>> {
+        // fallback (51,52) user write (53, 55) repo (55, 56)
+        let (mut r51, mut r52, mut r53, mut r55, mut r56) = (
+            Relay::new(8051, None, None),
+            Relay::new(8052, None, None),
+            Relay::new(8053, None, None),
+            Relay::new(8055, None, None),
+            Relay::new(8056, None, None),
+        );
+
+        r51.events.push(generate_test_key_1_relay_list_event());
+        r51.events.push(generate_test_key_1_metadata_event("fred"));
+        r51.events.push(generate_repo_ref_event());
+
+        r55.events.push(generate_repo_ref_event());
+        r55.events.push(generate_test_key_1_metadata_event("fred"));
+        r55.events.push(generate_test_key_1_relay_list_event());
+
+        let cli_tester_handle = std::thread::spawn(move || -> Result<()> {
+            let originating_repo = cli_tester_create_proposals()?;
+            let branch_name = get_proposal_branch_name(&originating_repo, FEATURE_BRANCH_NAME_1)?;
+
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+            let mut p = cli_tester_close_proposal(&test_repo, &branch_name);
+            p.expect("fetching updates...\r\n")?;
+            p.expect_eventually("\r\n")?; // some updates listed here
+            p.expect(format!(
+                "marking proposal '\"{PROPOSAL_TITLE_1}\"' as closed...\r\n"
+            ))?;
+            p.expect_end_eventually()?;
+
+            // the status just published is used to resolve the current status
+            let mut p = cli_tester_close_proposal(&test_repo, &branch_name);
+            p.expect("fetching updates...\r\n")?;
+            p.expect_eventually("\r\n")?; // may be 'no updates' or some updates
+            p.expect_end_with(&format!(
+                "proposal '\"{PROPOSAL_TITLE_1}\"' is already closed\r\n"
+            ))?;
+
+            for p in [51, 52, 53, 55, 56] {
+                relay::shutdown_relay(8000 + p)?;
+            }
+            Ok(())
+        });
+
+        // launch relay
+        let _ = join!(
+            r51.listen_until_close(),
+            r52.listen_until_close(),
+            r53.listen_until_close(),
+            r55.listen_until_close(),
+            r56.listen_until_close(),
+        );
+        cli_tester_handle.join().unwrap()?;
+        Ok(r55.events)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn closed_status_sent_once_to_repo_relays() -> Result<()> {
+        let r55_events = prep_and_run().await?;
+        assert_eq!(
+            r55_events
+                .iter()
+                .filter(|e| e.kind.eq(&Kind::GitStatusClosed))
+                .count(),
+            1
+        );
+        Ok(())
+    }
+}
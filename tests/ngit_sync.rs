@@ -0,0 +1,130 @@
+use anyhow::Result;
+use futures::join;
+use serial_test::serial;
+use test_utils::{git::GitTestRepo, relay::Relay, *};
+
+mod negentropy {
+    use super::*;
+
+    #[tokio::test]
+    #[serial]
+    async fn used_with_relays_that_support_it_and_req_used_with_others() -> Result<()> {
+        // fallback (51,52) user write (53, 55) repo (55, 56)
+        let (mut r51, mut r52, mut r53, mut r55, mut r56) = (
+            Relay::new(8051, None, None),
+            Relay::new(8052, None, None),
+            Relay::new(8053, None, None),
+            Relay::new(8055, None, None).with_negentropy(),
+            Relay::new(8056, None, None),
+        );
+
+        r51.events.push(generate_test_key_1_relay_list_event());
+        r51.events.push(generate_test_key_1_metadata_event("fred"));
+        r51.events.push(generate_repo_ref_event());
+
+        r55.events.push(generate_repo_ref_event());
+        r55.events.push(generate_test_key_1_metadata_event("fred"));
+        r55.events.push(generate_test_key_1_relay_list_event());
+
+        let cli_tester_handle = std::thread::spawn(move || -> Result<()> {
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+            let mut p = CliTester::new_from_dir(&test_repo.dir, ["sync"]);
+            p.expect_eventually("ws://localhost:8055 updates: ")?;
+            p.expect_end_eventually()?;
+            for p in [51, 52, 53, 55, 56] {
+                relay::shutdown_relay(8000 + p)?;
+            }
+            Ok(())
+        });
+
+        // launch relay
+        let _ = join!(
+            r51.listen_until_close(),
+            r52.listen_until_close(),
+            r53.listen_until_close(),
+            r55.listen_until_close(),
+            r56.listen_until_close(),
+        );
+        cli_tester_handle.join().unwrap()?;
+
+        assert!(!r55.negentropy_reqs.is_empty());
+        // only the ids found missing by negentropy are requested
+        assert!(r55.reqs.iter().flatten().all(|filter| filter.ids.is_some()));
+        // relays without negentropy support are sent REQ filters instead
+        assert!(r51.reqs.iter().flatten().any(|filter| filter.ids.is_none()));
+        Ok(())
+    }
+}
+
+mod since_checkpoints {
+    use nostr::{EventBuilder, Kind, Tag, Timestamp};
+
+    use super::*;
+
+    #[tokio::test]
+    #[serial]
+    async fn not_applied_to_proposals_new_to_relay() -> Result<()> {
+        // fallback (51,52) user write (53, 55) repo (55, 56)
+        let (mut r51, mut r52, mut r53, mut r55, mut r56) = (
+            Relay::new(8051, None, None),
+            Relay::new(8052, None, None),
+            Relay::new(8053, None, None),
+            Relay::new(8055, None, None),
+            Relay::new(8056, None, None),
+        );
+
+        r51.events.push(generate_test_key_1_relay_list_event());
+        r51.events.push(generate_test_key_1_metadata_event("fred"));
+        r51.events.push(generate_repo_ref_event());
+
+        r55.events.push(generate_repo_ref_event());
+        r55.events.push(generate_test_key_1_metadata_event("fred"));
+        r55.events.push(generate_test_key_1_relay_list_event());
+        let proposal = get_pretend_proposal_root_event();
+        r55.events.push(proposal.clone());
+
+        // only found on 56 and older than any checkpoint overlap
+        let status = EventBuilder::new(Kind::GitStatusClosed, "")
+            .tags([Tag::event(proposal.id)])
+            .custom_created_at(Timestamp::from(
+                Timestamp::now().as_u64() - (60 * 60 * 24 * 2),
+            ))
+            .sign_with_keys(&TEST_KEY_1_KEYS)?;
+        r56.events.push(generate_repo_ref_event());
+        r56.events.push(status.clone());
+
+        let cli_tester_handle = std::thread::spawn(move || -> Result<GitTestRepo> {
+            let test_repo = GitTestRepo::default();
+            test_repo.populate()?;
+            // the first sync records a checkpoint for each relay, possibly
+            // before the proposal is known
+            for _ in 0..2 {
+                let mut p = CliTester::new_from_dir(&test_repo.dir, ["sync"]);
+                p.expect_end_eventually()?;
+            }
+            for p in [51, 52, 53, 55, 56] {
+                relay::shutdown_relay(8000 + p)?;
+            }
+            Ok(test_repo)
+        });
+
+        // launch relay
+        let _ = join!(
+            r51.listen_until_close(),
+            r52.listen_until_close(),
+            r53.listen_until_close(),
+            r55.listen_until_close(),
+            r56.listen_until_close(),
+        );
+        let test_repo = cli_tester_handle.join().unwrap()?;
+
+        let cached = get_events_from_cache(
+            &test_repo.dir,
+            vec![nostr::Filter::default().kind(Kind::GitStatusClosed)],
+        )
+        .await?;
+        assert!(cached.iter().any(|e| e.id.eq(&status.id)));
+        Ok(())
+    }
+}